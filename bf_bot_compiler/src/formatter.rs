@@ -0,0 +1,181 @@
+//! Formats the source code of bots, without changing what they do.
+//! `minify` keeps only the instructions, on a single line. `prettify` keeps the comments, each on
+//! a line of its own, and spreads every loop that holds another loop or a comment over several
+//! lines, with its body indented. Other loops stay on a single line, so short loops like `[-]`
+//! remain readable at a glance.
+
+use parser::{self, ParseError};
+
+const INDENT: &str = "    ";
+
+enum Node {
+    Instruction(char),
+    /// A loop, with its opening bracket, its body and its closing bracket, including the
+    /// iteration count of a `(...)*N` loop.
+    Loop(char, Vec<Node>, String),
+    Comment(String),
+}
+
+/// Returns the instructions of the bot on a single line, without comments or whitespace.
+pub fn minify(source_code: &str) -> Result<String, ParseError> {
+    let nodes = parse_nodes(source_code)?;
+    let mut minified = String::new();
+    write_inline(&nodes, &mut minified);
+    Ok(minified)
+}
+
+/// Lays out the source code of the bot consistently, keeping its comments.
+pub fn prettify(source_code: &str) -> Result<String, ParseError> {
+    let nodes = parse_nodes(source_code)?;
+    let mut lines = vec![];
+    let mut line = String::new();
+    write_lines(&nodes, 0, &mut line, &mut lines);
+    flush(0, &mut line, &mut lines);
+    Ok(lines.iter().map(|line| format!("{}\n", line)).collect())
+}
+
+/// Splits valid source code into instructions, loops and comments.
+fn parse_nodes(source_code: &str) -> Result<Vec<Node>, ParseError> {
+    parser::try_parse_bot(source_code)?;
+    let chars: Vec<char> = source_code.chars().collect();
+    // Every open loop holds its opening bracket and the nodes of its body so far.
+    let mut stack: Vec<(char, Vec<Node>)> = vec![];
+    let mut nodes = vec![];
+    let mut comment = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let character = chars[index];
+        index += 1;
+        let node = match character {
+            '<' | '>' | '+' | '-' | '.' => Node::Instruction(character),
+            '[' | '(' => {
+                end_comment(&mut comment, &mut nodes);
+                stack.push((character, nodes));
+                nodes = vec![];
+                continue;
+            }
+            ']' | ')' => {
+                let mut closing = character.to_string();
+                if character == ')' {
+                    // The parser has checked that an iteration count follows.
+                    closing.push('*');
+                    index += 1;
+                    while index < chars.len() && chars[index].is_ascii_digit() {
+                        closing.push(chars[index]);
+                        index += 1;
+                    }
+                }
+                end_comment(&mut comment, &mut nodes);
+                let (opening, outer) = stack.pop().unwrap();
+                let body = nodes;
+                nodes = outer;
+                Node::Loop(opening, body, closing)
+            }
+            '\n' => {
+                end_comment(&mut comment, &mut nodes);
+                continue;
+            }
+            _ => {
+                if !character.is_whitespace() || !comment.is_empty() {
+                    comment.push(character);
+                }
+                continue;
+            }
+        };
+        end_comment(&mut comment, &mut nodes);
+        nodes.push(node);
+    }
+    end_comment(&mut comment, &mut nodes);
+    Ok(nodes)
+}
+
+fn end_comment(comment: &mut String, nodes: &mut Vec<Node>) {
+    let text = comment.trim_end();
+    if !text.is_empty() {
+        nodes.push(Node::Comment(text.to_string()));
+    }
+    comment.clear();
+}
+
+fn write_inline(nodes: &[Node], out: &mut String) {
+    for node in nodes {
+        match *node {
+            Node::Instruction(character) => out.push(character),
+            Node::Loop(opening, ref body, ref closing) => {
+                out.push(opening);
+                write_inline(body, out);
+                out.push_str(closing);
+            }
+            Node::Comment(_) => {}
+        }
+    }
+}
+
+fn write_lines(nodes: &[Node], depth: usize, line: &mut String, lines: &mut Vec<String>) {
+    for node in nodes {
+        match *node {
+            Node::Instruction(character) => line.push(character),
+            Node::Loop(opening, ref body, ref closing) if is_multi_line(body) => {
+                line.push(opening);
+                flush(depth, line, lines);
+                write_lines(body, depth + 1, line, lines);
+                flush(depth + 1, line, lines);
+                line.push_str(closing);
+            }
+            Node::Loop(..) => write_inline(std::slice::from_ref(node), line),
+            Node::Comment(ref text) => {
+                flush(depth, line, lines);
+                lines.push(format!("{}{}", INDENT.repeat(depth), text));
+            }
+        }
+    }
+}
+
+fn is_multi_line(body: &[Node]) -> bool {
+    body.iter().any(|node| !matches!(*node, Node::Instruction(_)))
+}
+
+/// Ends the current line, if it holds anything.
+fn flush(depth: usize, line: &mut String, lines: &mut Vec<String>) {
+    if !line.is_empty() {
+        lines.push(format!("{}{}", INDENT.repeat(depth), line));
+        line.clear();
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minify_dropsCommentsAndWhitespace() {
+        assert_eq!(minify("go right\n>> >>\n(+ -)*12 done").unwrap(), ">>>>(+-)*12");
+    }
+
+    #[test]
+    fn prettify_nestedLoops_areIndented() {
+        assert_eq!(prettify(">>>>>>>>>([-]>)*21").unwrap(), ">>>>>>>>>(\n    [-]>\n)*21\n");
+    }
+
+    #[test]
+    fn prettify_keepsCommentsOnTheirOwnLines() {
+        assert_eq!(prettify("rush >>>> then clear [-] forever").unwrap(),
+                   "rush\n>>>>\nthen clear\n[-]\nforever\n");
+        assert_eq!(prettify("(clear the cell [-] )*3").unwrap(),
+                   "(\n    clear the cell\n    [-]\n)*3\n");
+    }
+
+    #[test]
+    fn prettify_isIdempotentAndKeepsTheProgram() {
+        let source_code = "a decoy\n>(+)*10>(-)*10 (>[(-)*5[+]]. wait)*20";
+        let pretty = prettify(source_code).unwrap();
+        assert_eq!(prettify(&pretty).unwrap(), pretty);
+        assert_eq!(parser::try_parse_bot(&pretty), parser::try_parse_bot(source_code));
+    }
+
+    #[test]
+    fn prettify_invalidSource_isRejected() {
+        assert_eq!(prettify("[[").unwrap_err(), ParseError::UnmatchedOpeningBracket(1, 1));
+    }
+}
@@ -1,10 +1,13 @@
-//! Takes `BrainFuck` bot script as string or as text file.
+//! Takes `BrainFuck` bot script as string or as text file. Whole directories of bots can be
+//! loaded with `loader`.
 //! Checks script for validity.
 //! Error if mismatching brackets, warning if possible accidental use of full-stop as comment. Also
 //! warn if use comma, it's a reserved character in original BF.
-//! Minifier that reduces code complexity without changing the functionality.
-//! Prettyfier that tries to format the code in an intellinent way using spacing and newlines.
+//! Minifier that reduces code complexity without changing the functionality, and prettyfier that
+//! tries to format the code in an intelligent way using spacing and newlines: see `formatter`.
 extern crate bf_bot_core;
 
 pub mod parser;
 pub mod linter;
+pub mod loader;
+pub mod formatter;
@@ -1,5 +1,10 @@
 #![allow(dead_code, unused_variables)]
 
+use std::fmt;
+
+use bf_bot_core::json::{Object, ToJson};
+use parser::{self, ParseError};
+
 #[derive(Debug)]
 #[derive(PartialEq)]
 pub enum Severity {
@@ -16,6 +21,22 @@ pub struct CodeLocation {
     column: u32,
 }
 
+impl CodeLocation {
+    /// Returns the location of the character at the given byte offset of the program.
+    pub fn of_offset(program: &str, offset: usize) -> CodeLocation {
+        let (line, column) = parser::line_and_column(program, offset);
+        CodeLocation { line, column }
+    }
+
+    pub fn get_line(&self) -> u32 {
+        self.line
+    }
+
+    pub fn get_column(&self) -> u32 {
+        self.column
+    }
+}
+
 #[derive(Debug)]
 #[derive(PartialEq)]
 pub struct Issue {
@@ -25,10 +46,18 @@ pub struct Issue {
 }
 
 impl Issue {
+    fn new_parse_error(code_location: CodeLocation, error: &ParseError) -> Issue {
+        Issue {
+            severity: Severity::ERROR,
+            code_location,
+            description: error.to_string(),
+        }
+    }
+
     fn new_unintentional_dot(code_location: CodeLocation) -> Issue {
         Issue {
             severity: Severity::WARNING,
-            code_location: code_location,
+            code_location,
             description: "Possible unintentional dot. It looks like you accidentally used a dot \
                           as part of your documentation. A dot or full-stop (.) is a command in \
                           the BrainFuck Jousting language. It tells the bot to do nothing that \
@@ -37,16 +66,52 @@ impl Issue {
         }
     }
 
-    pub fn to_string(&self) -> String {
+    pub fn get_severity(&self) -> &Severity {
+        &self.severity
+    }
+
+    pub fn get_code_location(&self) -> &CodeLocation {
+        &self.code_location
+    }
+
+    pub fn get_description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let severity_str: &str = match self.severity {
             Severity::WARNING => "WARNING",
             Severity::ERROR => "ERROR",
         };
-        format!("{} on line {}, column {} {}",
-                severity_str,
-                self.code_location.line,
-                self.code_location.column,
-                self.description)
+        write!(f,
+               "{} on line {}, column {} {}",
+               severity_str,
+               self.code_location.line,
+               self.code_location.column,
+               self.description)
+    }
+}
+
+impl Severity {
+    /// Returns "warning" or "error".
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Severity::WARNING => "warning",
+            Severity::ERROR => "error",
+        }
+    }
+}
+
+impl ToJson for Issue {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("severity", self.severity.name())
+            .field("line", &self.code_location.line)
+            .field("column", &self.code_location.column)
+            .field("description", &self.description)
+            .build()
     }
 }
 
@@ -57,8 +122,14 @@ pub fn lint_check(program: &str) -> Vec<Issue> {
     vec
 }
 
+/// Reports the first bracket that doesn't match, or loop without an iteration count, as an error.
 fn check_bracket_mismatch(program: &str) -> Vec<Issue> {
-    vec![]
+    let error = match parser::try_parse_bot(program) {
+        Ok(_) => return vec![],
+        Err(error) => error,
+    };
+    let (line, column) = error.get_line_and_column();
+    vec![Issue::new_parse_error(CodeLocation { line, column }, &error)]
 }
 
 /// Checks for dots that were probably intended as part of the bot's documentation.
@@ -67,31 +138,32 @@ fn check_bracket_mismatch(program: &str) -> Vec<Issue> {
 /// - A dot that immediatelly follows a non-whitespace comment character.
 ///
 fn check_unintended_dot(program: &str) -> Vec<Issue> {
-    program.chars()
-        .skip(1)
-        .scan((FollowsAfter::Nothing, program.chars().nth(0)),
-              |&mut (ref follows, previous_char), current_char| {
-            let state = if is_valid_instruction(previous_char.unwrap()) {
-                FollowsAfter::Instruction
-            } else {
-                FollowsAfter::Comment
-            };
-            Some((state, current_char))
-        })
-        .inspect(|a| {
-            println!("{:?}", a);
-        })
-        .enumerate()
-        .filter(|&(index, (ref state, character))| {
-            state == &FollowsAfter::Comment && character == '.'
-        })
-        .map(|(index, (state, character))| {
-            Issue::new_unintentional_dot(CodeLocation {
-                line: 0,
-                column: 0,
-            })
-        })
-        .collect::<Vec<_>>()
+    let chars: Vec<(usize, char)> = program.char_indices().collect();
+    let mut follows = FollowsAfter::Nothing;
+    let mut issues = vec![];
+    let mut index = 0;
+    while index < chars.len() {
+        let (offset, character) = chars[index];
+        if character == '.' && follows == FollowsAfter::Comment {
+            issues.push(Issue::new_unintentional_dot(CodeLocation::of_offset(program, offset)));
+        }
+        follows = if is_valid_instruction(character) {
+            FollowsAfter::Instruction
+        } else if character.is_whitespace() {
+            FollowsAfter::Nothing
+        } else {
+            FollowsAfter::Comment
+        };
+        index += 1;
+        // The iteration count of a loop is part of the instruction.
+        if character == ')' && chars.get(index).map(|&(_, next)| next) == Some('*') {
+            index += 1;
+            while chars.get(index).is_some_and(|&(_, next)| next.is_ascii_digit()) {
+                index += 1;
+            }
+        }
+    }
+    issues
 }
 
 #[derive(Debug)]
@@ -104,10 +176,7 @@ enum FollowsAfter {
 
 //TODO: Move. Tests. Doc.
 fn is_valid_instruction(character: char) -> bool {
-    match character {
-        '<' | '>' | '+' | '-' | '[' | ']' | '.' => true,
-        _ => false,
-    }
+    matches!(character, '<' | '>' | '+' | '-' | '[' | ']' | '(' | ')' | '.')
 }
 
 fn check_comma(program: &str) -> Vec<Issue> {
@@ -125,7 +194,6 @@ fn checkUnintendedDot_forEmptyProgram_raisesNoWarnings() {
     assert_eq!(&expected, &check_unintended_dot(input));
 }
 
-#[ignore]
 #[test]
 #[allow(non_snake_case)]
 fn checkUnintendedDot_forDotSurroundedByComments_raisesWarning() {
@@ -136,3 +204,38 @@ fn checkUnintendedDot_forDotSurroundedByComments_raisesWarning() {
                                     })];
     assert_eq!(&expected, &check_unintended_dot(input));
 }
+
+#[test]
+#[allow(non_snake_case)]
+fn checkUnintendedDot_forDotsAfterInstructionsOrWhitespace_raisesNoWarnings() {
+    let input: &str = "wait . [-]. (+)*5. >.";
+    let expected: Vec<Issue> = vec![];
+    assert_eq!(&expected, &check_unintended_dot(input));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn checkUnintendedDot_onLaterLine_reportsLineAndColumn() {
+    let input: &str = ">>\nClears the flag.";
+    let expected: Vec<Issue> = vec![Issue::new_unintentional_dot(CodeLocation {
+                                        line: 2,
+                                        column: 16,
+                                    })];
+    assert_eq!(&expected, &check_unintended_dot(input));
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn lintCheck_unmatchedBracket_raisesError() {
+    let issues = lint_check(">>\n[-]]");
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].get_severity(), &Severity::ERROR);
+    assert_eq!(issues[0].get_code_location(), &CodeLocation { line: 2, column: 4 });
+}
+
+#[test]
+#[allow(non_snake_case)]
+fn toJson_issue_writesSeverityLocationAndDescription() {
+    let issue = Issue::new_unintentional_dot(CodeLocation { line: 3, column: 7 });
+    assert!(issue.to_json().starts_with("{\"severity\":\"warning\",\"line\":3,\"column\":7,"));
+}
@@ -0,0 +1,141 @@
+//! Reads bots from their source files.
+//! A directory of bots is loaded file by file: a file that can't be read or parsed is reported
+//! on its own, and doesn't keep the other bots from loading.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bf_bot_core::bf::Bot;
+use parser::{self, ParseError};
+
+/// The extension of bot source files.
+pub const EXTENSION: &str = "bf";
+
+/// A bot together with the file it was read from.
+#[derive(Debug, Clone)]
+pub struct LoadedBot {
+    pub path: PathBuf,
+    pub source_code: String,
+    pub bot: Bot,
+}
+
+impl LoadedBot {
+    /// Returns the name of the bot: its file name without the extension.
+    pub fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Reasons why a bot can't be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file doesn't hold a valid program.
+    Parse(ParseError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Io(ref error) => write!(f, "{}", error),
+            LoadError::Parse(ref error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// The outcome of loading a directory of bots.
+#[derive(Debug)]
+pub struct LoadedDir {
+    /// The bots that were loaded, in order of their paths.
+    pub bots: Vec<LoadedBot>,
+    /// The files that could not be loaded, in order of their paths.
+    pub failures: Vec<(PathBuf, LoadError)>,
+}
+
+/// Reads and parses a single source file.
+pub fn load_bot(path: &Path) -> Result<LoadedBot, LoadError> {
+    let source_code = fs::read_to_string(path).map_err(LoadError::Io)?;
+    let bot = parser::try_parse_source(&source_code).map_err(LoadError::Parse)?;
+    let mut loaded = LoadedBot {
+        path: path.to_path_buf(),
+        source_code,
+        bot,
+    };
+    let name = loaded.name();
+    loaded.bot = loaded.bot.named(&name);
+    Ok(loaded)
+}
+
+/// Loads every `.bf` file directly inside the directory. Only failing to list the directory is
+/// an error; files that fail to load are collected in `LoadedDir::failures`.
+pub fn load_dir(dir: &Path) -> io::Result<LoadedDir> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|extension| extension == EXTENSION) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    let mut loaded = LoadedDir {
+        bots: vec![],
+        failures: vec![],
+    };
+    for path in paths {
+        match load_bot(&path) {
+            Ok(bot) => loaded.bots.push(bot),
+            Err(error) => loaded.failures.push((path, error)),
+        }
+    }
+    Ok(loaded)
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn make_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("bf_bot_compiler_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for &(file_name, contents) in files {
+            fs::write(dir.join(file_name), contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn loadDir_invalidFile_doesNotStopTheOthers() {
+        let dir = make_dir("mixed",
+                           &[("b.bf", "[-]"), ("a.bf", ">>"), ("broken.bf", "[["), ("notes", "")]);
+        let loaded = load_dir(&dir).unwrap();
+        let names: Vec<String> = loaded.bots.iter().map(LoadedBot::name).collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert_eq!(loaded.failures.len(), 1);
+        assert_eq!(loaded.failures[0].0, dir.join("broken.bf"));
+        match loaded.failures[0].1 {
+            LoadError::Parse(ParseError::UnmatchedOpeningBracket(1, 1)) => {}
+            ref other => panic!("Expected an unmatched bracket, got {:?}", other),
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn loadDir_missingDirectory_isAnError() {
+        assert!(load_dir(Path::new("/nonexistent/bots")).is_err());
+    }
+
+    #[test]
+    fn loadBot_sampleBot_parses() {
+        let loaded = load_bot(Path::new("../bots/DecoyBot.bf")).unwrap();
+        assert_eq!(loaded.name(), "DecoyBot");
+        assert!(!loaded.bot.get_program().is_empty());
+    }
+}
@@ -1,11 +1,64 @@
-use bf_bot_core::bf::Instruction;
+use std::fmt;
 
+use bf_bot_core::bf::{Bot, Instruction, SourceMap, SourceSpan};
+
+/// Reasons why source code can't be parsed into a program. Every error holds the line and column
+/// of the offending character, counted the same way as in a `SourceSpan`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ParseError {
+    /// A closing bracket at the given line and column has no matching opening bracket, or the
+    /// opening bracket is of the other kind.
+    UnmatchedClosingBracket(u32, u32),
+    /// An opening bracket at the given line and column is never closed.
+    UnmatchedOpeningBracket(u32, u32),
+    /// The ')' at the given line and column is not followed by an asterisk and a number.
+    MissingIterationCount(u32, u32),
+}
+
+impl ParseError {
+    /// Returns the line and column of the character the error is about.
+    pub fn get_line_and_column(&self) -> (u32, u32) {
+        match *self {
+            ParseError::UnmatchedClosingBracket(line, column) |
+            ParseError::UnmatchedOpeningBracket(line, column) |
+            ParseError::MissingIterationCount(line, column) => (line, column),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::UnmatchedClosingBracket(line, column) => {
+                write!(f, "Unmatched closing bracket at line {}, column {}.", line, column)
+            }
+            ParseError::UnmatchedOpeningBracket(line, column) => {
+                write!(f, "Unmatched opening bracket at line {}, column {}.", line, column)
+            }
+            ParseError::MissingIterationCount(line, column) => {
+                write!(f,
+                       "The ')' at line {}, column {} must be followed by '*' and a number.",
+                       line,
+                       column)
+            }
+        }
+    }
+}
+
+/// Parses source code into a program. Panics if the source code is invalid; use `try_parse_bot`
+/// for source code that hasn't been checked yet.
 //TODO: Take &str?
 pub fn parse_bot(source_code: String) -> Vec<Instruction> {
-    let (bracket_stack, instructions): (Vec<usize>, Vec<Instruction>) = source_code.chars()
-        .enumerate()
-        .fold((vec![], vec![]),
-              |(mut bracket_stack, mut instructions), (index, character)| {
+    try_parse_bot(&source_code).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Parses source code into a program, or explains why it isn't a valid program.
+pub fn try_parse_bot(source_code: &str) -> Result<Vec<Instruction>, ParseError> {
+    // The bracket stack holds the instruction index and the byte offset of every open bracket.
+    let (bracket_stack, instructions): (Vec<(usize, usize)>, Vec<Instruction>) = source_code
+        .char_indices()
+        .try_fold((vec![], vec![]),
+                  |(mut bracket_stack, mut instructions), (index, character)| {
             match character {
                 '<' => instructions.push(Instruction::MoveBack),
                 '>' => instructions.push(Instruction::MoveForward),
@@ -15,80 +68,158 @@ pub fn parse_bot(source_code: String) -> Vec<Instruction> {
                 '[' => {
                     open_bracket(&mut bracket_stack,
                                  &mut instructions,
+                                 index,
                                  start_while_not_zero_placeholder)
                 }
-                ']' => close_square_bracket(&mut bracket_stack, &mut instructions),
-                '(' => open_bracket(&mut bracket_stack, &mut instructions, start_for_placeholder),
+                ']' => {
+                    close_square_bracket(&mut bracket_stack, &mut instructions, source_code, index)?
+                }
+                '(' => {
+                    open_bracket(&mut bracket_stack,
+                                 &mut instructions,
+                                 index,
+                                 start_for_placeholder)
+                }
                 ')' => {
-                    close_round_bracket(&mut bracket_stack, &mut instructions, &source_code, index)
+                    close_round_bracket(&mut bracket_stack, &mut instructions, source_code, index)?
                 }
                 _ => {
                     //Comment character, ignore.
                 }
             };
-            (bracket_stack, instructions)
-        });
-    assert!(bracket_stack.is_empty(), "Unmatched opening bracket(s).");
-    instructions
+            Ok((bracket_stack, instructions))
+        })?;
+    match bracket_stack.first() {
+        Some(&(_, index)) => {
+            Err(error_at(source_code, index, ParseError::UnmatchedOpeningBracket))
+        }
+        None => Ok(instructions),
+    }
+}
+
+/// Parses source code into a bot that keeps the source code, and a source map from every
+/// instruction back to the code it was parsed from.
+pub fn try_parse_source(source_code: &str) -> Result<Bot, ParseError> {
+    let program = try_parse_bot(source_code)?;
+    Ok(Bot::with_source(program, source_code.to_string(), source_map(source_code)))
 }
 
-fn open_bracket(bracket_stack: &mut Vec<usize>,
+/// Maps every instruction of a program to its place in the source code. The source code must
+/// hold a valid program.
+pub fn source_map(source_code: &str) -> SourceMap {
+    let mut spans = vec![];
+    let (mut line, mut column) = (1, 1);
+    for (start, character) in source_code.char_indices() {
+        if "<>+-.[]()".contains(character) {
+            let mut end = start + 1;
+            if character == ')' {
+                let count = source_code[end..]
+                    .char_indices()
+                    .take_while(|&(offset, character)| {
+                        (offset == 0 && character == '*') ||
+                        (offset > 0 && character.is_ascii_digit())
+                    })
+                    .count();
+                end += count;
+            }
+            spans.push(SourceSpan {
+                line,
+                column,
+                start,
+                end,
+            });
+        }
+        if character == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    SourceMap::new(spans)
+}
+
+fn open_bracket(bracket_stack: &mut Vec<(usize, usize)>,
                 instructions: &mut Vec<Instruction>,
+                index: usize,
                 make_instruction: fn() -> Instruction) {
-    bracket_stack.push(instructions.len());
+    bracket_stack.push((instructions.len(), index));
     instructions.push(make_instruction());
 }
 
-fn close_square_bracket(bracket_stack: &mut Vec<usize>, instructions: &mut Vec<Instruction>) {
-    let opening_index: Option<usize> = bracket_stack.pop();
-    match opening_index {
-        Some(value) => {
-            assert_eq!(instructions[value], start_while_not_zero_placeholder());
+fn close_square_bracket(bracket_stack: &mut Vec<(usize, usize)>,
+                        instructions: &mut Vec<Instruction>,
+                        source_code: &str,
+                        index: usize)
+                        -> Result<(), ParseError> {
+    match bracket_stack.pop() {
+        Some((value, _)) if instructions[value] == start_while_not_zero_placeholder() => {
             instructions[value] =
                 Instruction::StartWhileNotZero { target_pointer: instructions.len() };
             instructions.push(Instruction::EndWhileNotZero { target_pointer: value });
+            Ok(())
         }
-        None => panic!("Unmatched square closing bracket."),
+        _ => Err(error_at(source_code, index, ParseError::UnmatchedClosingBracket)),
     }
 }
 
-//TODO: Give only the remaining part of the string slice, to cut performance down to O(N).
-fn close_round_bracket(bracket_stack: &mut Vec<usize>,
+fn close_round_bracket(bracket_stack: &mut Vec<(usize, usize)>,
                        instructions: &mut Vec<Instruction>,
                        source_code: &str,
-                       index: usize) {
-    let opening_index: Option<usize> = bracket_stack.pop();
-    match opening_index {
-        Some(value) => {
-            assert_eq!(instructions[value], start_for_placeholder());
+                       index: usize)
+                       -> Result<(), ParseError> {
+    match bracket_stack.pop() {
+        Some((value, _)) if instructions[value] == start_for_placeholder() => {
+            let nr_iterations = get_nr_iterations(source_code, index)
+                .ok_or_else(|| error_at(source_code, index, ParseError::MissingIterationCount))?;
             instructions[value] = Instruction::StartFor { target_pointer: instructions.len() };
             instructions.push(Instruction::EndFor {
                 target_pointer: value,
-                nr_iterations: get_nr_iterations(source_code, index),
+                nr_iterations,
             });
+            Ok(())
         }
-        None => panic!("Unmatched square closing bracket."),
+        _ => Err(error_at(source_code, index, ParseError::UnmatchedClosingBracket)),
     }
 }
 
-fn get_nr_iterations(source_code: &str, index: usize) -> usize {
-    let mut chars = source_code.chars().skip(index + 1);
-    assert_eq!(chars.next(),
-               Some('*'),
-               "Error. ')' must be followed by an asterisk.");
-    chars.take_while(|character| character.is_digit(10))
+fn get_nr_iterations(source_code: &str, index: usize) -> Option<usize> {
+    let mut chars = source_code[index + 1..].chars();
+    if chars.next() != Some('*') {
+        return None;
+    }
+    chars.take_while(|character| character.is_ascii_digit())
         .collect::<String>()
         .parse::<usize>()
-        .expect("Error. A for loop '(...)*' should be followed by a number that signifies its \
-                 number of iterations.")
+        .ok()
+}
+
+/// Returns the line and column of the character at the given byte offset of the source code.
+/// Both start at 1, and columns count characters, like those of a `SourceSpan`.
+pub fn line_and_column(source_code: &str, offset: usize) -> (u32, u32) {
+    source_code[..offset].chars().fold((1, 1), |(line, column), character| {
+        if character == '\n' {
+            (line + 1, 1)
+        } else {
+            (line, column + 1)
+        }
+    })
+}
+
+fn error_at(source_code: &str,
+            offset: usize,
+            make_error: fn(u32, u32) -> ParseError)
+            -> ParseError {
+    let (line, column) = line_and_column(source_code, offset);
+    make_error(line, column)
 }
 
 fn start_while_not_zero_placeholder() -> Instruction {
-    Instruction::StartWhileNotZero { target_pointer: <usize>::max_value() }
+    Instruction::StartWhileNotZero { target_pointer: usize::MAX }
 }
 
 fn start_for_placeholder() -> Instruction {
-    Instruction::StartFor { target_pointer: <usize>::max_value() }
+    Instruction::StartFor { target_pointer: usize::MAX }
 }
 
 #[cfg(test)]
@@ -273,4 +404,60 @@ mod tests {
         assert_eq!(&expected, &parse_bot(input));
     }
 
+    #[test]
+    fn tryParseBot_validSource_returnsProgram() {
+        assert_eq!(try_parse_bot("+."),
+                   Ok(vec![Instruction::Increment, Instruction::SkipExecution]));
+    }
+
+    #[test]
+    fn tryParseBot_wrongTypeOfBracket_reportsClosingBracket() {
+        assert_eq!(try_parse_bot(">(]"), Err(ParseError::UnmatchedClosingBracket(1, 3)));
+    }
+
+    #[test]
+    fn tryParseBot_unmatchedOpeningBracket_reportsOutermostBracket() {
+        assert_eq!(try_parse_bot("a[[]"), Err(ParseError::UnmatchedOpeningBracket(1, 2)));
+    }
+
+    #[test]
+    fn tryParseBot_missingIterationCount_reportsRoundBracket() {
+        assert_eq!(try_parse_bot("(>)*x"), Err(ParseError::MissingIterationCount(1, 3)));
+    }
+
+    #[test]
+    fn tryParseBot_errorOnLaterLine_reportsLineAndColumn() {
+        let error = try_parse_bot("Clear with ±1\n[-]]").unwrap_err();
+        assert_eq!(error, ParseError::UnmatchedClosingBracket(2, 4));
+        assert_eq!(error.to_string(), "Unmatched closing bracket at line 2, column 4.");
+    }
+
+    #[test]
+    fn lineAndColumn_countsCharactersRatherThanBytes() {
+        assert_eq!(line_and_column("±\n±)", "±\n±".len()), (2, 2));
+    }
+
+    #[test]
+    fn sourceMap_skipsCommentsAndSpansIterationCounts() {
+        let source_map = source_map("a >\n(+)*12 .");
+        let spans: Vec<(u32, u32, usize, usize)> = source_map.get_spans()
+            .iter()
+            .map(|span| (span.line, span.column, span.start, span.end))
+            .collect();
+        assert_eq!(spans,
+                   vec![(1, 3, 2, 3), (2, 1, 4, 5), (2, 2, 5, 6), (2, 3, 6, 10), (2, 8, 11, 12)]);
+    }
+
+    #[test]
+    fn tryParseSource_keepsSourceAndCountsBytes() {
+        let bot = try_parse_source("é+").unwrap();
+        assert_eq!(bot.get_source_code(), Some("é+"));
+        assert_eq!(bot.get_source_map().unwrap().get(0),
+                   Some(&SourceSpan {
+                       line: 1,
+                       column: 2,
+                       start: 2,
+                       end: 3,
+                   }));
+    }
 }
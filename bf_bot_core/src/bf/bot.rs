@@ -1,17 +1,99 @@
 use bf::instruction::Instruction;
+use bf::source_map::SourceMap;
 
 /// Represents a Bot. Holds variables that are related to the Bot in general.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Bot {
     program: Vec<Instruction>,
+    /// For every instruction, whether it starts a for loop that takes at least one cycle to run.
+    /// Worked out once here, so that entering a loop doesn't have to scan its body.
+    cycle_taking_loops: Vec<bool>,
+    /// The source code the program was parsed from, if it is known, along with the map from
+    /// every instruction back to that source code.
+    source: Option<(String, SourceMap)>,
+    name: Option<String>,
 }
 
 impl Bot {
     pub fn new(program: Vec<Instruction>) -> Bot {
-        Bot { program: program }
+        Bot {
+            cycle_taking_loops: find_cycle_taking_loops(&program),
+            program,
+            source: None,
+            name: None,
+        }
+    }
+
+    /// Creates a bot that remembers the source code its program was parsed from. The source map
+    /// must hold a span for every instruction of the program.
+    pub fn with_source(program: Vec<Instruction>,
+                       source_code: String,
+                       source_map: SourceMap)
+                       -> Bot {
+        assert_eq!(program.len(), source_map.len(), "Every instruction needs a source span.");
+        Bot {
+            cycle_taking_loops: find_cycle_taking_loops(&program),
+            program,
+            source: Some((source_code, source_map)),
+            name: None,
+        }
+    }
+
+    /// Gives the bot a name, to tell it apart in the output of tools.
+    pub fn named(mut self, name: &str) -> Bot {
+        self.name = Some(name.to_string());
+        self
     }
 
     pub fn get_program(&self) -> &Vec<Instruction> {
         &self.program
     }
+
+    /// Returns whether running the for loop that starts at the given index takes at least one
+    /// cycle. That is not the case if it has no iterations, or if its body consists of nothing
+    /// but for loops that don't take any cycles either. Returns false if no for loop starts at
+    /// the index.
+    pub fn for_loop_takes_cycles(&self, start_pointer: usize) -> bool {
+        self.cycle_taking_loops[start_pointer]
+    }
+
+    pub fn get_source_code(&self) -> Option<&str> {
+        self.source.as_ref().map(|(source_code, _)| source_code.as_str())
+    }
+
+    pub fn get_source_map(&self) -> Option<&SourceMap> {
+        self.source.as_ref().map(|(_, source_map)| source_map)
+    }
+
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+/// Works out `Bot::for_loop_takes_cycles` for every instruction. Loops are visited from the back,
+/// so that every loop nested in a body is known before the body is scanned.
+fn find_cycle_taking_loops(program: &[Instruction]) -> Vec<bool> {
+    let mut takes_cycles = vec![false; program.len()];
+    for start_pointer in (0..program.len()).rev() {
+        let end_pointer = match program[start_pointer] {
+            Instruction::StartFor { target_pointer } => target_pointer,
+            _ => continue,
+        };
+        if let Instruction::EndFor { nr_iterations: 0, .. } = program[end_pointer] {
+            continue;
+        }
+        let mut index = start_pointer + 1;
+        while index < end_pointer {
+            match program[index] {
+                Instruction::StartFor { target_pointer } if !takes_cycles[index] => {
+                    index = target_pointer + 1;
+                }
+                _ => {
+                    takes_cycles[start_pointer] = true;
+                    break;
+                }
+            }
+        }
+    }
+    takes_cycles
 }
@@ -3,3 +3,6 @@ mod bot;
 
 pub use self::instruction::Instruction;
 mod instruction;
+
+pub use self::source_map::{SourceMap, SourceSpan};
+mod source_map;
@@ -0,0 +1,58 @@
+/// Where a single instruction was written in the source code of a bot.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SourceSpan {
+    /// Starts at line 1.
+    pub line: u32,
+    /// Starts at column 1, and counts characters rather than bytes.
+    pub column: u32,
+    /// The byte offset of the first character of the instruction.
+    pub start: usize,
+    /// The byte offset just past the instruction. The closing bracket of a for loop spans its
+    /// iteration count as well, like `)*10`.
+    pub end: usize,
+}
+
+/// Maps every instruction of a program back to the source code it was parsed from, so tools can
+/// point at the code that ran.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SourceMap {
+    /// One span for every instruction, in the order of the instructions.
+    spans: Vec<SourceSpan>,
+}
+
+impl SourceMap {
+    pub fn new(spans: Vec<SourceSpan>) -> SourceMap {
+        SourceMap { spans }
+    }
+
+    /// Returns the span of the instruction at the given index of the program.
+    pub fn get(&self, index: usize) -> Option<&SourceSpan> {
+        self.spans.get(index)
+    }
+
+    pub fn get_spans(&self) -> &[SourceSpan] {
+        &self.spans
+    }
+
+    /// Returns the index of the instruction that starts at the given line and column, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bf_bot_core::bf::{SourceMap, SourceSpan};
+    /// let source_map = SourceMap::new(vec![SourceSpan { line: 2, column: 3, start: 5, end: 6 }]);
+    /// assert_eq!(source_map.find(2, 3), Some(0));
+    /// assert_eq!(source_map.find(1, 1), None);
+    /// ```
+    pub fn find(&self, line: u32, column: u32) -> Option<usize> {
+        self.spans.iter().position(|span| span.line == line && span.column == column)
+    }
+
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
@@ -2,13 +2,18 @@ use bf::Bot;
 use engine::{BotInPlay, Mutation, Polarity, Orientation};
 use simul_round::{RoundResult, RoundParams};
 
-#[derive(Debug)]
+/// The state of a single round: the tape, both bots and the number of steps so far. Cloning an
+/// arena is cheap, so a round can be branched at any step and played on in different ways.
+#[derive(Debug, Clone)]
 pub struct Arena<'a> {
     max_steps: u32,
     step_nr: u32,
-    pub tape: Vec<i8>, //FIXME: make this not public. Had to do this to give unit tests access.
+    /// Visible to the rest of the crate, so unit tests can set up the tape.
+    pub(crate) tape: Vec<i8>,
     start_bot: BotInPlay<'a>,
     end_bot: BotInPlay<'a>,
+    /// The cell mutations that were applied during the last step, if any.
+    last_mutations: (Option<Mutation>, Option<Mutation>),
 }
 
 impl<'a> Arena<'a> {
@@ -30,20 +35,52 @@ impl<'a> Arena<'a> {
                                     round_params.tape_length as i32,
                                     Orientation::Reversed,
                                     polarity),
+            last_mutations: (None, None),
         }
     }
 
     fn make_tape(length: usize) -> Vec<i8> {
         let mut tape = vec!(0i8; length);
-        tape[0] = i8::min_value();
-        tape[length - 1] = i8::min_value();
+        tape[0] = i8::MIN;
+        tape[length - 1] = i8::MIN;
         tape
     }
 
+    /// Returns a copy of the current state of the round, which the arena can be restored to.
+    pub fn snapshot(&self) -> Snapshot<'a> {
+        Snapshot { arena: self.clone() }
+    }
+
+    /// Restores the arena to the state it was in when the snapshot was taken.
+    pub fn restore(&mut self, snapshot: &Snapshot<'a>) {
+        self.clone_from(&snapshot.arena);
+    }
+
     pub fn get_tape(&self) -> &Vec<i8> {
         &self.tape
     }
 
+    /// Returns the number of steps that have been executed so far.
+    pub fn get_step_nr(&self) -> u32 {
+        self.step_nr
+    }
+
+    /// Returns the bot that started at the start of the tape.
+    pub fn get_start_bot(&self) -> &BotInPlay<'a> {
+        &self.start_bot
+    }
+
+    /// Returns the bot that started at the end of the tape.
+    pub fn get_end_bot(&self) -> &BotInPlay<'a> {
+        &self.end_bot
+    }
+
+    /// Returns the cell mutations that the start bot and the end bot respectively applied during
+    /// the last step.
+    pub fn get_last_mutations(&self) -> &(Option<Mutation>, Option<Mutation>) {
+        &self.last_mutations
+    }
+
     pub fn step(&mut self) -> RoundResult {
         if self.exceeded_max_steps() || self.sink_state_detected() {
             return RoundResult::draw();
@@ -66,6 +103,7 @@ impl<'a> Arena<'a> {
             self.tape[mutation.get_index()] = self.tape[mutation.get_index()]
                 .wrapping_add(mutation.get_addend());
         }
+        self.last_mutations = (optional_cell_mutation_1, optional_cell_mutation_2);
         self.step_nr += 1;
     }
 
@@ -111,3 +149,16 @@ impl<'a> Arena<'a> {
         self.tape[self.tape.len() - 1] == 0
     }
 }
+
+/// The state of a round at a single step, taken with `Arena::snapshot`.
+#[derive(Debug, Clone)]
+pub struct Snapshot<'a> {
+    arena: Arena<'a>,
+}
+
+impl<'a> Snapshot<'a> {
+    /// Returns the number of steps that had been executed when the snapshot was taken.
+    pub fn get_step_nr(&self) -> u32 {
+        self.arena.step_nr
+    }
+}
@@ -3,7 +3,7 @@ use engine::{Mutation, Orientation, Polarity};
 
 /// Represents a Bot during one specific game. This struct holds variables whose lifetime do not
 /// exceed that of a single game.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BotInPlay<'a> {
     /// A reference to the Bot itself.
     bot: &'a Bot,
@@ -20,21 +20,27 @@ pub struct BotInPlay<'a> {
     orientation: Orientation,
     /// The polarity of the bot during this game.
     polarity: Polarity,
+    /// For every for loop that is currently running, the number of iterations that remain after
+    /// the current one. The innermost loop is last.
+    remaining_iterations: Vec<usize>,
 }
 
 impl<'a> BotInPlay<'a> {
-    pub fn new(bot: &Bot, length: i32, orientation: Orientation, polarity: Polarity) -> BotInPlay {
-        BotInPlay {
-            bot: bot,
+    pub fn new(bot: &'a Bot, length: i32, orientation: Orientation, polarity: Polarity) -> Self {
+        let mut bot_in_play = BotInPlay {
+            bot,
             pos: if orientation == Orientation::Normal {
                 0
             } else {
                 length - 1
             },
             code_pointer: 0,
-            orientation: orientation,
-            polarity: polarity,
-        }
+            orientation,
+            polarity,
+            remaining_iterations: vec![],
+        };
+        bot_in_play.enter_for_loops();
+        bot_in_play
     }
 
     /// Returns the current position of the bot as a usize. It is not allowed to call this method
@@ -43,6 +49,31 @@ impl<'a> BotInPlay<'a> {
         self.pos as usize
     }
 
+    /// Returns the current position of the bot, which may lie outside of the tape if the bot has
+    /// just run off of it.
+    pub fn get_signed_pos(&self) -> i32 {
+        self.pos
+    }
+
+    /// Returns the index of the instruction that will be executed next.
+    pub fn get_code_pointer(&self) -> usize {
+        self.code_pointer
+    }
+
+    /// Returns the number of iterations that remain after the current one for every for loop
+    /// that is running, from the outermost loop to the innermost one.
+    pub fn get_remaining_iterations(&self) -> &[usize] {
+        &self.remaining_iterations
+    }
+
+    pub fn get_orientation(&self) -> &Orientation {
+        &self.orientation
+    }
+
+    pub fn get_polarity(&self) -> &Polarity {
+        &self.polarity
+    }
+
     pub fn program_has_ended(&self) -> bool {
         self.code_pointer >= self.bot.get_program().len()
     }
@@ -83,13 +114,156 @@ impl<'a> BotInPlay<'a> {
 
     pub fn increment_code_pointer(&mut self) {
         self.code_pointer += 1;
+        self.enter_for_loops();
+    }
+
+    /// Moves the code pointer past any for loop brackets. A for loop is shorthand for repeating
+    /// its body, so its brackets don't take a cycle to execute. A loop whose body would not take
+    /// a single cycle is skipped as a whole, rather than being spun through once per iteration.
+    fn enter_for_loops(&mut self) {
+        while !self.program_has_ended() {
+            match self.bot.get_program()[self.code_pointer] {
+                Instruction::StartFor { target_pointer } => {
+                    if self.bot.for_loop_takes_cycles(self.code_pointer) {
+                        let nr_iterations = self.get_nr_iterations(target_pointer);
+                        self.remaining_iterations.push(nr_iterations - 1);
+                        self.code_pointer += 1;
+                    } else {
+                        self.code_pointer = target_pointer + 1;
+                    }
+                }
+                Instruction::EndFor { target_pointer, .. } => {
+                    match self.remaining_iterations.pop() {
+                        Some(remaining) if remaining > 0 => {
+                            self.remaining_iterations.push(remaining - 1);
+                            self.code_pointer = target_pointer + 1;
+                        }
+                        _ => self.code_pointer += 1,
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn get_nr_iterations(&self, end_pointer: usize) -> usize {
+        match self.bot.get_program()[end_pointer] {
+            Instruction::EndFor { nr_iterations, .. } => nr_iterations,
+            _ => panic!("Error. There is no end of a for loop at index {}.", end_pointer),
+        }
     }
 
     pub fn bot_is_off_tape(&self, tape_length: &i32) -> bool {
         self.pos < 0 || &self.pos >= tape_length
     }
 
-    pub fn get_bot(&self) -> &Bot {
+    pub fn get_bot(&self) -> &'a Bot {
         self.bot
     }
 }
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    fn start_for(target_pointer: usize) -> Instruction {
+        Instruction::StartFor { target_pointer }
+    }
+
+    fn end_for(target_pointer: usize, nr_iterations: usize) -> Instruction {
+        Instruction::EndFor {
+            target_pointer,
+            nr_iterations,
+        }
+    }
+
+    fn make_bot_in_play(bot: &Bot) -> BotInPlay<'_> {
+        BotInPlay::new(bot, 10, Orientation::Normal, Polarity::Normal)
+    }
+
+    /// Executes the bot's program until it ends, and returns the index of every instruction that
+    /// was executed, in order.
+    fn executed_code_pointers(bot_in_play: &mut BotInPlay<'_>) -> Vec<usize> {
+        let mut code_pointers = vec![];
+        while !bot_in_play.program_has_ended() {
+            code_pointers.push(bot_in_play.get_code_pointer());
+            bot_in_play.execute_code(false);
+            bot_in_play.increment_code_pointer();
+        }
+        code_pointers
+    }
+
+    #[test]
+    fn new_programStartsWithForLoop_pointsIntoBody() {
+        // (+)*3
+        let bot = Bot::new(vec![start_for(2), Instruction::Increment, end_for(0, 3)]);
+        let bot_in_play = make_bot_in_play(&bot);
+        assert_eq!(bot_in_play.get_code_pointer(), 1);
+        assert_eq!(bot_in_play.remaining_iterations, vec![2]);
+    }
+
+    #[test]
+    fn incrementCodePointer_forLoop_repeatsBody() {
+        // (+-)*3.
+        let bot = Bot::new(vec![start_for(3),
+                                Instruction::Increment,
+                                Instruction::Decrement,
+                                end_for(0, 3),
+                                Instruction::SkipExecution]);
+        let mut bot_in_play = make_bot_in_play(&bot);
+        assert_eq!(executed_code_pointers(&mut bot_in_play),
+                   vec![1, 2, 1, 2, 1, 2, 4]);
+    }
+
+    #[test]
+    fn incrementCodePointer_nestedForLoops_repeatsInnerBodyForEveryOuterIteration() {
+        // ((+)*2-)*2
+        let bot = Bot::new(vec![start_for(5),
+                                start_for(3),
+                                Instruction::Increment,
+                                end_for(1, 2),
+                                Instruction::Decrement,
+                                end_for(0, 2)]);
+        let mut bot_in_play = make_bot_in_play(&bot);
+        assert_eq!(executed_code_pointers(&mut bot_in_play),
+                   vec![2, 2, 4, 2, 2, 4]);
+    }
+
+    #[test]
+    fn incrementCodePointer_zeroIterations_skipsBody() {
+        // .(+)*0-
+        let bot = Bot::new(vec![Instruction::SkipExecution,
+                                start_for(3),
+                                Instruction::Increment,
+                                end_for(1, 0),
+                                Instruction::Decrement]);
+        let mut bot_in_play = make_bot_in_play(&bot);
+        assert_eq!(executed_code_pointers(&mut bot_in_play), vec![0, 4]);
+    }
+
+    #[test]
+    fn new_emptyBodyWithHugeIterationCount_skipsLoopAtOnce() {
+        // ()*MAX+
+        let bot = Bot::new(vec![start_for(1), end_for(0, usize::MAX), Instruction::Increment]);
+        let bot_in_play = make_bot_in_play(&bot);
+        assert_eq!(bot_in_play.get_code_pointer(), 2);
+        assert!(bot_in_play.remaining_iterations.is_empty());
+    }
+
+    #[test]
+    fn new_bodyWithOnlyLoopsThatTakeNoCycles_skipsLoopAtOnce() {
+        // (()*5(+)*0)*MAX-
+        let bot = Bot::new(vec![start_for(6),
+                                start_for(2),
+                                end_for(1, 5),
+                                start_for(5),
+                                Instruction::Increment,
+                                end_for(3, 0),
+                                end_for(0, usize::MAX),
+                                Instruction::Decrement]);
+        let bot_in_play = make_bot_in_play(&bot);
+        assert_eq!(bot_in_play.get_code_pointer(), 7);
+        assert!(bot_in_play.remaining_iterations.is_empty());
+    }
+}
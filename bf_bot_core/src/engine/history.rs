@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+
+use engine::{Arena, Snapshot};
+use simul_round::RoundResult;
+
+/// Steps an arena while remembering its most recent states, so the round can also be stepped
+/// backwards. Only a bounded number of states is kept: once the history is full, the oldest
+/// state is forgotten with every new step.
+#[derive(Debug, Clone)]
+pub struct History<'a> {
+    arena: Arena<'a>,
+    /// The states before the most recent steps, the most recent one last.
+    past: VecDeque<Snapshot<'a>>,
+    capacity: usize,
+}
+
+impl<'a> History<'a> {
+    /// Keeps at most `capacity` states of the arena to go back to.
+    pub fn new(arena: Arena<'a>, capacity: usize) -> History<'a> {
+        History {
+            arena,
+            past: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn get_arena(&self) -> &Arena<'a> {
+        &self.arena
+    }
+
+    /// Executes a single step of the arena, and remembers the state before it.
+    pub fn step(&mut self) -> RoundResult {
+        if self.capacity > 0 {
+            if self.past.len() == self.capacity {
+                self.past.pop_front();
+            }
+            self.past.push_back(self.arena.snapshot());
+        }
+        self.arena.step()
+    }
+
+    /// Undoes the most recent step. Returns false if there is no state left to go back to.
+    pub fn step_back(&mut self) -> bool {
+        match self.past.pop_back() {
+            Some(snapshot) => {
+                self.arena.restore(&snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the number of steps that can currently be undone.
+    pub fn len(&self) -> usize {
+        self.past.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.past.is_empty()
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf::{Bot, Instruction};
+    use simul_round::RoundParams;
+
+    fn make_round_params() -> RoundParams {
+        RoundParams {
+            tape_length: 10,
+            invert_polarity: false,
+            max_steps: 100,
+        }
+    }
+
+    #[test]
+    fn stepBack_restoresTapeAndPositions() {
+        let bot_a = Bot::new(vec![Instruction::MoveForward, Instruction::Increment]);
+        let bot_b = Bot::new(vec![Instruction::MoveForward; 2]);
+        let mut history = History::new(Arena::new(&bot_a, &bot_b, &make_round_params()), 10);
+        history.step();
+        let tape = history.get_arena().get_tape().clone();
+        history.step();
+        assert_eq!(history.get_arena().get_tape()[1], 1);
+        assert!(history.step_back());
+        assert_eq!(history.get_arena().get_tape(), &tape);
+        assert_eq!(history.get_arena().get_step_nr(), 1);
+        assert_eq!(history.get_arena().get_start_bot().get_pos(), 1);
+        assert_eq!(history.get_arena().get_end_bot().get_pos(), 8);
+        assert_eq!(history.get_arena().get_start_bot().get_code_pointer(), 1);
+    }
+
+    #[test]
+    fn step_fullHistory_forgetsOldestState() {
+        let bot = Bot::new(vec![Instruction::SkipExecution; 10]);
+        let mut history = History::new(Arena::new(&bot, &bot, &make_round_params()), 2);
+        for _ in 0..5 {
+            history.step();
+        }
+        assert_eq!(history.len(), 2);
+        assert!(history.step_back());
+        assert!(history.step_back());
+        assert!(!history.step_back());
+        assert_eq!(history.get_arena().get_step_nr(), 3);
+    }
+
+    #[test]
+    fn restore_branchesFromSnapshot() {
+        let bot = Bot::new(vec![Instruction::Increment; 10]);
+        let mut arena = Arena::new(&bot, &bot, &make_round_params());
+        arena.step();
+        let snapshot = arena.snapshot();
+        let mut branch = arena.clone();
+        branch.step();
+        arena.step();
+        arena.step();
+        arena.restore(&snapshot);
+        assert_eq!(snapshot.get_step_nr(), 1);
+        assert_eq!(arena.get_step_nr(), 1);
+        assert_eq!(arena.get_tape()[0], -127);
+        assert_eq!(branch.get_tape()[0], -126);
+    }
+}
@@ -10,5 +10,8 @@ mod orientation;
 pub use self::polarity::Polarity;
 mod polarity;
 
-pub use self::arena::Arena;
+pub use self::arena::{Arena, Snapshot};
 mod arena;
+
+pub use self::history::History;
+mod history;
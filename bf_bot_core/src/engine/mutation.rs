@@ -1,4 +1,6 @@
-#[derive(Debug)]
+use json::{Object, ToJson};
+
+#[derive(Debug, Clone, Copy)]
 #[derive(PartialEq)]
 pub struct Mutation {
     /// Index of the cell on the tape that is mutated.
@@ -10,8 +12,8 @@ pub struct Mutation {
 impl Mutation {
     pub fn new(index: usize, addend: i8) -> Mutation {
         Mutation {
-            index: index,
-            addend: addend,
+            index,
+            addend,
         }
     }
 
@@ -23,3 +25,9 @@ impl Mutation {
         self.addend
     }
 }
+
+impl ToJson for Mutation {
+    fn to_json(&self) -> String {
+        Object::new().field("index", &self.index).field("addend", &self.addend).build()
+    }
+}
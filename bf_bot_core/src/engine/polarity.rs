@@ -1,6 +1,6 @@
 /// In half the matches, one of the bots will have its polarity reversed. This eliminates the
 /// strategy of taking a successful bot's code and merely exchanging + for - and vice versa.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 #[derive(PartialEq)]
 pub enum Polarity {
     /// Original polarity, aka Sieve. Decrement is interpreted as lowering the value of the cell,
@@ -0,0 +1,219 @@
+//! Machine-readable output of results, as JSON.
+//! The JSON is written by hand, so no dependencies are needed. Every document that the tools
+//! write is a single object with a `schema_version` and a `kind`, followed by the fields of that
+//! kind. The schema version is raised whenever a field is removed, renamed or changes meaning;
+//! new fields may be added without raising it, so readers should ignore fields they don't know.
+//!
+//! # Values
+//!
+//! Numbers that can't be represented in JSON, like infinity, are written as `null`.
+//!
+//! - round params: `{"tape_length": int, "polarity": "normal" | "reversed", "max_steps": int}`
+//! - round result: `{"bot_a_lost": bool, "bot_b_lost": bool, "winner": "a" | "b" | "draw" |
+//!   null}`, where bot a is the bot that starts at the start of the tape, and the winner is null
+//!   while the round is still going on.
+//! - game result: `{"bot_a_points": int, "bot_b_points": int}`
+//! - round record: `{"params": round params, "result": round result, "steps": int}`
+//! - game record: `{"result": game result, "rounds": [round record]}`
+//! - mutation: `{"index": int, "addend": int}`
+//! - step: `{"tape": [int], "pos_a": int, "pos_b": int, "mutation_a": mutation | null,
+//!   "mutation_b": mutation | null}`. Positions lie outside of the tape when a bot has just run
+//!   off of it.
+//! - trace: `{"initial_tape": [int], "steps": [step], "result": round result}`
+//! - issue: `{"severity": "warning" | "error", "line": int, "column": int, "description":
+//!   string}`. Lines and columns start at 1.
+//! - standing: `{"name": string, "score": number, "points": int, "wins": int, "losses": int,
+//!   "draws": int}`
+//! - leaderboard: `{"scoring": "points" | "markov" | "weighted", "standings": [standing],
+//!   "head_to_head": [[int | null]]}`, with the standings best first. Row a, column b of the
+//!   head-to-head matrix holds the points that the bot at index a of the standings scored against
+//!   the one at index b, or null on the diagonal.
+//! - swiss standing: a standing with `"buchholz": number` and `"byes": int`.
+//! - swiss: `{"rounds": int, "standings": [swiss standing]}`, best first.
+//! - opponent result: `{"name": string, "weight": number, "tags": [string], "points": int}`
+//! - gauntlet report: `{"candidate": string, "score": number, "results": [opponent result],
+//!   "tags": [{"tag": string, "score": number}]}`
+//! - round change: `{"opponent": string, "tape_length": int, "polarity": string, "old": outcome,
+//!   "new": outcome}`, where an outcome is `"win"`, `"loss"` or `"draw"`.
+//! - regression: `{"old": gauntlet report, "new": gauntlet report, "score_change": number,
+//!   "changes": [round change]}`
+//! - rating: `{"elo": number, "rating": number, "deviation": number, "volatility": number,
+//!   "games": int}`, where rating and deviation are those of Glicko-2, on the Glicko scale.
+//! - ranking: `[{"name": string, "score": number}]`, best first.
+//! - hill change: `{"challenger": string, "rank": int | null, "evicted": string | null, "before":
+//!   ranking, "after": ranking}`, where rank is the rank at which the challenger entered the
+//!   hill, counted from 1, or null if it didn't make it.
+//!
+//! # Documents
+//!
+//! - `match`: `bot_a` and `bot_b`, the names of the bots, and `game`, a game record.
+//! - `trace`: `bot_a`, `bot_b`, `params`, the round params, and `trace`.
+//! - `lint`: `files`, a list of `{"file": string, "issues": [issue]}`.
+//! - `format`: `file`, `formatted`, the formatted source code, and `changed`, a bool.
+//! - `leaderboard`: the fields of a leaderboard. Also written by `hill show`.
+//! - `swiss`: the fields of a swiss.
+//! - `gauntlet`: `reports`, a list of gauntlet reports.
+//! - `diff`: the fields of a regression.
+//! - `profile`: `bot`, the name of the bot, `counts`, how often every instruction of the bot was
+//!   executed in the selected rounds, in the order of the program, and `rounds`, a list of
+//!   `{"tape_length": int, "polarity": string, "executed": int}` with the number of instructions
+//!   executed over all rounds with that tape length and polarity.
+//! - `generation`: `generation`, an int, `fitness`, a number, and `bot`, the source code of the
+//!   best bot of the generation.
+//! - `refinement`: `initial_fitness` and `fitness`, numbers, `accepted`, the number of edits that
+//!   were accepted, and `bot`, the source code of the refined bot.
+//! - `ratings`: `ratings`, a list of `{"name": string, "hash": string, "rating": rating}` in the
+//!   order the bots were given, where hash is the content hash of the bot as 16 hex digits.
+//! - `hill`: `size`, an int, and `scoring`, the scoring of a new hill.
+//! - `hill_change`: `change`, a hill change. One is written for every challenger.
+//! - `hill_history`: `changes`, every hill change, oldest first.
+
+/// The version of the schema that is described above.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Values that can be written as JSON.
+pub trait ToJson {
+    fn to_json(&self) -> String;
+}
+
+/// Builds a JSON object, one field at a time.
+#[derive(Debug, Default)]
+pub struct Object {
+    fields: Vec<String>,
+}
+
+impl Object {
+    pub fn new() -> Object {
+        Object { fields: vec![] }
+    }
+
+    /// Starts a document of the given kind, with the current schema version.
+    pub fn document(kind: &str) -> Object {
+        Object::new().field("schema_version", &SCHEMA_VERSION).field("kind", kind)
+    }
+
+    pub fn field<T>(self, name: &str, value: &T) -> Object
+        where T: ToJson + ?Sized
+    {
+        self.raw_field(name, value.to_json())
+    }
+
+    /// Adds a field whose value has already been written as JSON.
+    pub fn raw_field(mut self, name: &str, json: String) -> Object {
+        self.fields.push(format!("{}:{}", name.to_json(), json));
+        self
+    }
+
+    pub fn build(self) -> String {
+        format!("{{{}}}", self.fields.join(","))
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> String {
+        let mut json = String::with_capacity(self.len() + 2);
+        json.push('"');
+        for character in self.chars() {
+            match character {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                character if (character as u32) < 0x20 => {
+                    json.push_str(&format!("\\u{:04x}", character as u32))
+                }
+                character => json.push(character),
+            }
+        }
+        json.push('"');
+        json
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> String {
+        self.as_str().to_json()
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ToJson for f64 {
+    fn to_json(&self) -> String {
+        if self.is_finite() {
+            self.to_string()
+        } else {
+            "null".to_string()
+        }
+    }
+}
+
+macro_rules! integer_to_json {
+    ($($integer:ty),*) => {
+        $(
+            impl ToJson for $integer {
+                fn to_json(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    }
+}
+
+integer_to_json!(i8, i32, i64, u32, u64, usize);
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> String {
+        let values: Vec<String> = self.iter().map(ToJson::to_json).collect();
+        format!("[{}]", values.join(","))
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> String {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> String {
+        match *self {
+            Some(ref value) => value.to_json(),
+            None => "null".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toJson_string_escapesQuotesAndControlCharacters() {
+        assert_eq!("say \"hi\"\n\\".to_json(), "\"say \\\"hi\\\"\\n\\\\\"");
+        assert_eq!("\u{1}".to_json(), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn toJson_numbers_nonFiniteBecomesNull() {
+        assert_eq!(1.5.to_json(), "1.5");
+        assert_eq!((-3i8).to_json(), "-3");
+        assert_eq!(f64::INFINITY.to_json(), "null");
+        assert_eq!(vec![Some(1u32), None].to_json(), "[1,null]");
+    }
+
+    #[test]
+    fn document_startsWithVersionAndKind() {
+        let json = Object::document("example").field("values", &vec![true, false]).build();
+        assert_eq!(json,
+                   format!("{{\"schema_version\":{},\"kind\":\"example\",\
+                            \"values\":[true,false]}}",
+                           SCHEMA_VERSION));
+    }
+}
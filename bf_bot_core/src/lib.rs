@@ -2,3 +2,4 @@ pub mod bf;
 pub mod simul_game;
 pub mod simul_round;
 pub mod engine;
+pub mod json;
@@ -6,15 +6,15 @@ use simul_round::RoundParams;
 
 /// A complete game consists of rounds with tape lengths from `MIN_TAPE_LENGTH` to
 /// `MAX_TAPE_LENGTH`. Both bounds are inclusive.
-const MIN_TAPE_LENGTH: u32 = 10;
+pub const MIN_TAPE_LENGTH: u32 = 10;
 /// A complete game consists of rounds with tape lengths from `MIN_TAPE_LENGTH` to
 /// `MAX_TAPE_LENGTH`. Both bounds are inclusive.
-const MAX_TAPE_LENGTH: u32 = 30;
+pub const MAX_TAPE_LENGTH: u32 = 30;
 /// Max steps in a round for a complete game.
 /// If an incomplete game is run for performance reasons, the max steps may be smaller than this
 /// value to save CPU time. However, if a smaller value than this is used, note that the result of
 /// the game may differ from reality.
-const COMPLETE_GAME_MAX_STEPS: u32 = 100_000;
+pub const COMPLETE_GAME_MAX_STEPS: u32 = 100_000;
 
 /// An iterator that returns all rounds in a complete game, covering all possible tape lengths and
 /// both polarities.
@@ -49,6 +49,12 @@ impl AllRounds {
     }
 }
 
+impl Default for AllRounds {
+    fn default() -> AllRounds {
+        AllRounds::new()
+    }
+}
+
 impl Iterator for AllRounds {
     type Item = RoundParams;
 
@@ -1,5 +1,6 @@
 use bf::Bot;
 use simul_round::{self, RoundParams};
+use simul_game::game_record::{GameRecord, RoundRecord};
 use simul_game::game_result::GameResult;
 use simul_game::all_rounds::AllRounds;
 
@@ -18,3 +19,21 @@ pub fn run<I>(bot_a: &Bot, bot_b: &Bot, rounds: I) -> GameResult
         game_result
     })
 }
+
+/// Compares two bots in a game consisting of the provided rounds. Returns a record of every round
+/// rather than just the total.
+pub fn run_recorded<I>(bot_a: &Bot, bot_b: &Bot, rounds: I) -> GameRecord
+    where I: Iterator<Item = RoundParams>
+{
+    GameRecord {
+        rounds: rounds.map(|round_params| {
+                let (result, steps) = simul_round::play_timed(bot_a, bot_b, &round_params);
+                RoundRecord {
+                    round_params,
+                    result,
+                    steps,
+                }
+            })
+            .collect(),
+    }
+}
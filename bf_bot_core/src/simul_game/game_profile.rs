@@ -0,0 +1,113 @@
+use bf::Bot;
+use simul_round::{self, RoundParams};
+
+/// How often each instruction of a bot was executed in a single round.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RoundCounts {
+    pub round_params: RoundParams,
+    /// One count for every instruction in the program of the bot.
+    pub counts: Vec<u32>,
+}
+
+/// How often each instruction of a bot was executed, round by round, over one or more games.
+/// Keeping the rounds apart makes it possible to tell which code only matters on some tape
+/// lengths or polarities.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GameProfile {
+    nr_instructions: usize,
+    rounds: Vec<RoundCounts>,
+}
+
+impl GameProfile {
+    /// Creates an empty profile for a bot with the given number of instructions.
+    pub fn new(nr_instructions: usize) -> GameProfile {
+        GameProfile {
+            nr_instructions,
+            rounds: vec![],
+        }
+    }
+
+    /// Plays the bot against an opponent in a game consisting of the provided rounds, and adds
+    /// the counts of the bot to the profile. The bot is always the start bot; the opponent's
+    /// polarity is the one that is reversed.
+    pub fn add_game<I>(&mut self, bot: &Bot, opponent: &Bot, rounds: I)
+        where I: Iterator<Item = RoundParams>
+    {
+        assert_eq!(bot.get_program().len(), self.nr_instructions);
+        for round_params in rounds {
+            let round_profile = simul_round::profile(bot, opponent, &round_params);
+            self.rounds.push(RoundCounts {
+                round_params,
+                counts: round_profile.start_bot_counts,
+            });
+        }
+    }
+
+    /// Adds all rounds of another profile of the same bot.
+    pub fn merge(&mut self, other: GameProfile) {
+        assert_eq!(other.nr_instructions, self.nr_instructions);
+        self.rounds.extend(other.rounds);
+    }
+
+    pub fn get_rounds(&self) -> &[RoundCounts] {
+        &self.rounds
+    }
+
+    /// Sums the counts of every instruction over the rounds that pass the filter.
+    pub fn total<F>(&self, filter: F) -> Vec<u64>
+        where F: Fn(&RoundParams) -> bool
+    {
+        let mut total = vec![0; self.nr_instructions];
+        for round in self.rounds.iter().filter(|round| filter(&round.round_params)) {
+            for (sum, &count) in total.iter_mut().zip(&round.counts) {
+                *sum += u64::from(count);
+            }
+        }
+        total
+    }
+
+    /// Returns the number of instructions that were executed for every combination of tape
+    /// length and polarity, ordered by tape length and with the normal polarity first.
+    pub fn executed_per_round_kind(&self) -> Vec<(u32, bool, u64)> {
+        let mut kinds: Vec<(u32, bool, u64)> = vec![];
+        for round in &self.rounds {
+            let params = &round.round_params;
+            let executed: u64 = round.counts.iter().map(|&count| u64::from(count)).sum();
+            match kinds.iter_mut()
+                .find(|kind| (kind.0, kind.1) == (params.tape_length, params.invert_polarity)) {
+                Some(kind) => kind.2 += executed,
+                None => kinds.push((params.tape_length, params.invert_polarity, executed)),
+            }
+        }
+        kinds.sort_by_key(|&(tape_length, invert_polarity, _)| (tape_length, invert_polarity));
+        kinds
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf::Instruction;
+    use simul_game::AllRounds;
+
+    #[test]
+    fn addGame_countsEveryRoundOfTheGame() {
+        // Its program, in BrainFuck: >[-]. It only enters the loop when the opponent has left
+        // a value behind on the second cell, which an idle opponent never does.
+        let bot = Bot::new(vec![Instruction::MoveForward,
+                                Instruction::StartWhileNotZero { target_pointer: 3 },
+                                Instruction::Decrement,
+                                Instruction::EndWhileNotZero { target_pointer: 1 }]);
+        let opponent = Bot::new(vec![Instruction::SkipExecution]);
+        let mut game_profile = GameProfile::new(4);
+        game_profile.add_game(&bot, &opponent, AllRounds::new());
+        assert_eq!(game_profile.get_rounds().len(), 42);
+        assert_eq!(game_profile.total(|_| true), vec![42, 42, 0, 0]);
+        assert_eq!(game_profile.total(|params| params.tape_length == 10), vec![2, 2, 0, 0]);
+        let kinds = game_profile.executed_per_round_kind();
+        assert_eq!(kinds.len(), 42);
+        assert_eq!(kinds[0], (10, false, 2));
+        assert_eq!(kinds[1], (10, true, 2));
+    }
+}
@@ -0,0 +1,129 @@
+use json::{Object, ToJson};
+use simul_game::game_result::GameResult;
+use simul_round::{RoundParams, RoundResult};
+
+/// The outcome of a single round of a game, together with the conditions it was played under.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RoundRecord {
+    pub round_params: RoundParams,
+    pub result: RoundResult,
+    /// The number of steps it took to finish the round.
+    pub steps: u32,
+}
+
+/// A round-by-round account of a game. Unlike a `GameResult`, this shows which rounds were won
+/// and lost, and how long they took.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct GameRecord {
+    pub rounds: Vec<RoundRecord>,
+}
+
+impl GameRecord {
+    pub fn new() -> Self {
+        GameRecord { rounds: vec![] }
+    }
+
+    /// Sums the rounds up into the result of the game.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bf_bot_core::simul_game::game_record::{GameRecord, RoundRecord};
+    /// use bf_bot_core::simul_round::{RoundParams, RoundResult};
+    /// let round_params = RoundParams { tape_length: 10, invert_polarity: false, max_steps: 10 };
+    /// let record = GameRecord {
+    ///     rounds: vec![RoundRecord {
+    ///         round_params: round_params,
+    ///         result: RoundResult::start_bot_wins(),
+    ///         steps: 5,
+    ///     }],
+    /// };
+    /// assert_eq!(record.to_result().bot_a_points, 1);
+    /// ```
+    pub fn to_result(&self) -> GameResult {
+        self.rounds.iter().fold(GameResult::new(), |mut game_result, round| {
+            game_result.add_result_to_total(&round.result);
+            game_result
+        })
+    }
+}
+
+impl RoundRecord {
+    /// Returns the points bot a scored in this round: 1 for a win, -1 for a loss and 0 for a
+    /// draw.
+    pub fn bot_a_points(&self) -> i32 {
+        if !self.result.has_winner() {
+            0
+        } else if self.result.bot_b_lost {
+            1
+        } else {
+            -1
+        }
+    }
+}
+
+impl ToJson for RoundRecord {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("params", &self.round_params)
+            .field("result", &self.result)
+            .field("steps", &self.steps)
+            .build()
+    }
+}
+
+impl ToJson for GameRecord {
+    fn to_json(&self) -> String {
+        Object::new().field("result", &self.to_result()).field("rounds", &self.rounds).build()
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+
+    use super::*;
+    use simul_round::{RoundParams, RoundResult};
+
+    fn make_record(result: RoundResult) -> RoundRecord {
+        RoundRecord {
+            round_params: RoundParams {
+                tape_length: 10,
+                invert_polarity: false,
+                max_steps: 100,
+            },
+            result,
+            steps: 1,
+        }
+    }
+
+    #[test]
+    fn botAPoints_followsRoundResult() {
+        assert_eq!(make_record(RoundResult::start_bot_wins()).bot_a_points(), 1);
+        assert_eq!(make_record(RoundResult::end_bot_wins()).bot_a_points(), -1);
+        assert_eq!(make_record(RoundResult::draw()).bot_a_points(), 0);
+    }
+
+    #[test]
+    fn toResult_sumsAllRounds() {
+        let record = GameRecord {
+            rounds: vec![make_record(RoundResult::start_bot_wins()),
+                         make_record(RoundResult::start_bot_wins()),
+                         make_record(RoundResult::end_bot_wins())],
+        };
+        assert_eq!(record.to_result(),
+                   GameResult {
+                       bot_a_points: 1,
+                       bot_b_points: -1,
+                   });
+    }
+
+    #[test]
+    fn toJson_writesResultAndEveryRound() {
+        let record = GameRecord { rounds: vec![make_record(RoundResult::end_bot_wins())] };
+        assert_eq!(record.to_json(),
+                   "{\"result\":{\"bot_a_points\":-1,\"bot_b_points\":1},\"rounds\":[{\"params\":\
+                    {\"tape_length\":10,\"polarity\":\"normal\",\"max_steps\":100},\"result\":\
+                    {\"bot_a_lost\":true,\"bot_b_lost\":false,\"winner\":\"b\"},\"steps\":1}]}");
+    }
+}
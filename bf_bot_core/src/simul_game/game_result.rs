@@ -1,3 +1,4 @@
+use json::{Object, ToJson};
 use simul_round::RoundResult;
 
 /// The result of a game of Brainfuck joust. A game consists of multiple rounds.
@@ -23,6 +24,15 @@ impl GameResult {
     }
 }
 
+impl ToJson for GameResult {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("bot_a_points", &self.bot_a_points)
+            .field("bot_b_points", &self.bot_b_points)
+            .build()
+    }
+}
+
 #[cfg(test)]
 #[allow(non_snake_case)]
 mod tests {
@@ -12,9 +12,14 @@
 //! For performance reasons, it is possible to run an incomplete game, consisting of fewer than 42
 //! rounds. An incomplete game gives non-deterministic results.
 
-pub use self::game::{run, run_complete};
+pub use self::game::{run, run_complete, run_recorded};
 mod game;
 
 pub mod game_result;
 
+pub mod game_record;
+
+pub mod game_profile;
+
+pub use self::all_rounds::{AllRounds, MIN_TAPE_LENGTH, MAX_TAPE_LENGTH, COMPLETE_GAME_MAX_STEPS};
 mod all_rounds;
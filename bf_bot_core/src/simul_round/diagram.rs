@@ -0,0 +1,206 @@
+//! Draws a traced round as a space-time diagram in SVG.
+//! Every row of the diagram is the tape at a single step, from the initial tape at the top down
+//! to the last step. The colour of a cell shows its value: white for zero, shades of blue for
+//! positive values and shades of red for negative ones. The positions of the start bot and the
+//! end bot are drawn as a green and an orange path respectively.
+
+use simul_round::RoundTrace;
+
+const MARGIN_LEFT: f64 = 48.0;
+const MARGIN_TOP: f64 = 20.0;
+/// The smallest distance between two labels along the cycle axis, in pixels.
+const MIN_LABEL_DISTANCE: f64 = 40.0;
+const START_BOT_COLOUR: &str = "#1a9850";
+const END_BOT_COLOUR: &str = "#f46d43";
+
+/// Settings for drawing a space-time diagram.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DiagramParams {
+    /// The width of a single cell, in pixels.
+    pub cell_width: f64,
+    /// The height of a single step, in pixels.
+    pub step_height: f64,
+    /// If set, only the first steps are drawn, which keeps the images of long rounds small.
+    pub max_steps: Option<usize>,
+}
+
+impl Default for DiagramParams {
+    fn default() -> DiagramParams {
+        DiagramParams {
+            cell_width: 16.0,
+            step_height: 4.0,
+            max_steps: None,
+        }
+    }
+}
+
+/// Writes a standalone SVG image of a traced round. The title is shown when the image is
+/// hovered over.
+pub fn to_svg(round_trace: &RoundTrace, params: &DiagramParams, title: &str) -> String {
+    let nr_steps = params.max_steps
+        .map_or(round_trace.steps.len(), |max_steps| max_steps.min(round_trace.steps.len()));
+    let tapes: Vec<&[i8]> = Some(round_trace.initial_tape.as_slice())
+        .into_iter()
+        .chain(round_trace.steps[..nr_steps].iter().map(|step| step.tape.as_slice()))
+        .collect();
+    let tape_length = round_trace.initial_tape.len();
+    let width = MARGIN_LEFT + tape_length as f64 * params.cell_width;
+    let height = MARGIN_TOP + tapes.len() as f64 * params.step_height;
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" \
+                           height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"monospace\" \
+                           font-size=\"10\">\n<title>{}</title>\n\
+                           <rect width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n",
+                          escape_xml(title),
+                          w = width,
+                          h = height);
+    // Cells rarely change, so every run of steps in which a cell keeps the same value is drawn
+    // as a single rectangle.
+    for cell in 0..tape_length {
+        let mut run_start = 0;
+        for row in 1..tapes.len() + 1 {
+            let value = tapes[run_start][cell];
+            if row < tapes.len() && tapes[row][cell] == value {
+                continue;
+            }
+            if value != 0 {
+                svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" \
+                                       fill=\"{}\"/>\n",
+                                      MARGIN_LEFT + cell as f64 * params.cell_width,
+                                      MARGIN_TOP + run_start as f64 * params.step_height,
+                                      params.cell_width,
+                                      (row - run_start) as f64 * params.step_height,
+                                      cell_colour(value)));
+            }
+            run_start = row;
+        }
+    }
+    let label_every = if params.cell_width >= 12.0 { 1 } else { 5 };
+    for cell in (0..tape_length).step_by(label_every) {
+        svg.push_str(&format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                              MARGIN_LEFT + (cell as f64 + 0.5) * params.cell_width,
+                              MARGIN_TOP - 6.0,
+                              cell));
+    }
+    let step_interval = label_interval(params.step_height);
+    for step_nr in (0..tapes.len()).step_by(step_interval) {
+        svg.push_str(&format!("<text x=\"{}\" y=\"{}\" text-anchor=\"end\" \
+                               dominant-baseline=\"middle\">{}</text>\n",
+                              MARGIN_LEFT - 4.0,
+                              MARGIN_TOP + (step_nr as f64 + 0.5) * params.step_height,
+                              step_nr));
+    }
+    let start_bot_positions = Some(0)
+        .into_iter()
+        .chain(round_trace.steps[..nr_steps].iter().map(|step| step.start_bot_pos));
+    let end_bot_positions = Some(tape_length as i32 - 1)
+        .into_iter()
+        .chain(round_trace.steps[..nr_steps].iter().map(|step| step.end_bot_pos));
+    svg.push_str(&bot_path(start_bot_positions, params, START_BOT_COLOUR));
+    svg.push_str(&bot_path(end_bot_positions, params, END_BOT_COLOUR));
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Draws the positions of a bot, one for every row, as a path through the middle of the cells.
+fn bot_path<I>(positions: I, params: &DiagramParams, colour: &str) -> String
+    where I: Iterator<Item = i32>
+{
+    let points: Vec<String> = positions.enumerate()
+        .map(|(row, pos)| {
+            format!("{},{}",
+                    MARGIN_LEFT + (pos as f64 + 0.5) * params.cell_width,
+                    MARGIN_TOP + (row as f64 + 0.5) * params.step_height)
+        })
+        .collect();
+    format!("<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\" \
+             stroke-linejoin=\"round\"/>\n",
+            points.join(" "),
+            colour)
+}
+
+/// Returns the number of steps between two labels along the cycle axis: 1, 2 or 5 times a power
+/// of ten, whichever is the smallest that keeps the labels apart.
+fn label_interval(step_height: f64) -> usize {
+    let mut power = 1;
+    loop {
+        for &factor in &[1, 2, 5] {
+            if (factor * power) as f64 * step_height >= MIN_LABEL_DISTANCE {
+                return factor * power;
+            }
+        }
+        power *= 10;
+    }
+}
+
+/// Returns the colour of a cell with the given value. The further the value lies from zero, the
+/// darker the colour.
+fn cell_colour(value: i8) -> String {
+    let lightness = 255 - (i32::from(value).abs() * 255 / 128);
+    if value > 0 {
+        format!("rgb({0},{0},255)", lightness)
+    } else {
+        format!("rgb(255,{0},{0})", lightness)
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf::{Bot, Instruction};
+    use simul_round::{self, RoundParams};
+
+    fn make_trace() -> RoundTrace {
+        let bot_a = Bot::new(vec![Instruction::MoveForward, Instruction::Increment]);
+        let bot_b = Bot::new(vec![Instruction::SkipExecution]);
+        let round_params = RoundParams {
+            tape_length: 10,
+            invert_polarity: false,
+            max_steps: 5,
+        };
+        simul_round::trace(&bot_a, &bot_b, &round_params)
+    }
+
+    #[test]
+    fn toSvg_drawsRunsOfCellsAndBothBotPaths() {
+        let params = DiagramParams {
+            cell_width: 10.0,
+            step_height: 10.0,
+            max_steps: None,
+        };
+        let svg = to_svg(&make_trace(), &params, "a <vs> b");
+        assert!(svg.contains("<title>a &lt;vs&gt; b</title>"));
+        // The round ends after 2 steps. Both flags keep their value for the whole round.
+        assert!(svg.contains("<rect x=\"48\" y=\"20\" width=\"10\" height=\"30\" \
+                              fill=\"rgb(255,0,0)\"/>"));
+        assert!(svg.contains("<rect x=\"138\" y=\"20\" width=\"10\" height=\"30\" \
+                              fill=\"rgb(255,0,0)\"/>"));
+        // The second cell is incremented in step 2.
+        assert!(svg.contains("<rect x=\"58\" y=\"40\" width=\"10\" height=\"10\" \
+                              fill=\"rgb(254,254,255)\"/>"));
+        assert!(svg.contains("<polyline points=\"53,25 63,35 63,45\""));
+        assert!(svg.contains("<polyline points=\"143,25 143,35 143,45\""));
+    }
+
+    #[test]
+    fn toSvg_maxSteps_drawsOnlyTheFirstSteps() {
+        let params = DiagramParams {
+            max_steps: Some(1),
+            ..DiagramParams::default()
+        };
+        let svg = to_svg(&make_trace(), &params, "");
+        assert!(svg.contains("height=\"28\""));
+        assert!(svg.contains("<polyline points=\"56,22 72,26\""));
+    }
+
+    #[test]
+    fn labelInterval_keepsLabelsApart() {
+        assert_eq!(label_interval(40.0), 1);
+        assert_eq!(label_interval(4.0), 10);
+        assert_eq!(label_interval(0.1), 500);
+    }
+}
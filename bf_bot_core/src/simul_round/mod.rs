@@ -1,4 +1,4 @@
-pub use self::round::play;
+pub use self::round::{play, play_timed};
 mod round;
 
 pub use self::round_params::RoundParams;
@@ -6,3 +6,12 @@ mod round_params;
 
 pub use self::round_result::RoundResult;
 mod round_result;
+
+pub use self::trace::{trace, RoundTrace, StepState};
+mod trace;
+
+pub use self::profile::{profile, RoundProfile};
+mod profile;
+
+pub use self::diagram::DiagramParams;
+pub mod diagram;
@@ -0,0 +1,108 @@
+//! Counts how often every instruction is executed during a single round.
+//! The brackets of for loops never take a cycle, so their count is always zero; the instructions
+//! in their body are counted once for every iteration.
+
+use bf::Bot;
+use engine::{Arena, BotInPlay};
+use simul_round::{RoundParams, RoundResult};
+
+/// The number of times each instruction of both bots was executed in a round.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RoundProfile {
+    /// One count for every instruction in the program of the start bot.
+    pub start_bot_counts: Vec<u32>,
+    /// One count for every instruction in the program of the end bot.
+    pub end_bot_counts: Vec<u32>,
+    pub result: RoundResult,
+    pub steps: u32,
+}
+
+/// Plays a single round and counts the executions of every instruction.
+pub fn profile(bot_a: &Bot, bot_b: &Bot, round_params: &RoundParams) -> RoundProfile {
+    let mut arena = Arena::new(bot_a, bot_b, round_params);
+    let mut start_bot_counts = vec![0; bot_a.get_program().len()];
+    let mut end_bot_counts = vec![0; bot_b.get_program().len()];
+    loop {
+        let step_nr = arena.get_step_nr();
+        let start_bot_instruction = next_instruction(arena.get_start_bot());
+        let end_bot_instruction = next_instruction(arena.get_end_bot());
+        let result = arena.step();
+        if arena.get_step_nr() != step_nr {
+            if let Some(index) = start_bot_instruction {
+                start_bot_counts[index] += 1;
+            }
+            if let Some(index) = end_bot_instruction {
+                end_bot_counts[index] += 1;
+            }
+        }
+        if result.round_is_finished() {
+            return RoundProfile {
+                start_bot_counts,
+                end_bot_counts,
+                result,
+                steps: arena.get_step_nr(),
+            };
+        }
+    }
+}
+
+/// Returns the index of the instruction the bot executes in the next step, if any.
+fn next_instruction(bot_in_play: &BotInPlay) -> Option<usize> {
+    if bot_in_play.program_has_ended() {
+        None
+    } else {
+        Some(bot_in_play.get_code_pointer())
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf::Instruction;
+    use simul_round::play_timed;
+
+    fn make_round_params(max_steps: u32) -> RoundParams {
+        RoundParams {
+            tape_length: 10,
+            invert_polarity: false,
+            max_steps,
+        }
+    }
+
+    #[test]
+    fn profile_forLoop_countsBodyOncePerIteration() {
+        // Its program, in BrainFuck: (>)*3.
+        let bot_a = Bot::new(vec![Instruction::StartFor { target_pointer: 2 },
+                                  Instruction::MoveForward,
+                                  Instruction::EndFor {
+                                      target_pointer: 0,
+                                      nr_iterations: 3,
+                                  },
+                                  Instruction::SkipExecution]);
+        let bot_b = Bot::new(vec![Instruction::SkipExecution; 2]);
+        let round_profile = profile(&bot_a, &bot_b, &make_round_params(100));
+        assert_eq!(round_profile.start_bot_counts, vec![0, 3, 0, 1]);
+        assert_eq!(round_profile.end_bot_counts, vec![1, 1]);
+    }
+
+    #[test]
+    fn profile_roundEndsEarly_laterInstructionsAreNeverCounted() {
+        // The start bot runs off the tape right away: <+
+        let bot_a = Bot::new(vec![Instruction::MoveBack, Instruction::Increment]);
+        let bot_b = Bot::new(vec![Instruction::SkipExecution]);
+        let round_profile = profile(&bot_a, &bot_b, &make_round_params(100));
+        assert_eq!(round_profile.start_bot_counts, vec![1, 0]);
+        assert_eq!(round_profile.result, RoundResult::end_bot_wins());
+    }
+
+    #[test]
+    fn profile_matchesPlayTimed() {
+        let bot_a = Bot::new(vec![Instruction::MoveForward, Instruction::Increment]);
+        let bot_b = Bot::new(vec![Instruction::MoveBack]);
+        let round_params = make_round_params(100);
+        let round_profile = profile(&bot_a, &bot_b, &round_params);
+        assert_eq!((round_profile.result, round_profile.steps),
+                   play_timed(&bot_a, &bot_b, &round_params));
+    }
+}
@@ -5,10 +5,17 @@ use engine::Arena;
 
 pub fn play(bot_a: &Bot, bot_b: &Bot, round_params: &RoundParams) -> RoundResult {
     StepsIterator::new(bot_a, bot_b, round_params)
-        .find(|&ref outcome| outcome.round_is_finished())
+        .find(|outcome| outcome.round_is_finished())
         .unwrap()
 }
 
+/// Plays a single round and returns its result together with the number of steps it took.
+pub fn play_timed(bot_a: &Bot, bot_b: &Bot, round_params: &RoundParams) -> (RoundResult, u32) {
+    let mut steps_iter = StepsIterator::new(bot_a, bot_b, round_params);
+    let result = steps_iter.find(|outcome| outcome.round_is_finished()).unwrap();
+    (result, steps_iter.arena.get_step_nr())
+}
+
 #[derive(Debug)]
 struct StepsIterator<'a> {
     arena: Arena<'a>,
@@ -54,14 +61,70 @@ mod tests {
         Bot::new(vec![Instruction::MoveBack])
     }
 
+    /// Constructs a Bot that moves forward the given number of times, using a for loop.
+    /// Its program, in BrainFuck: (>)*nr_iterations
+    fn make_for_loop_bot(nr_iterations: usize) -> Bot {
+        Bot::new(vec![Instruction::StartFor { target_pointer: 2 },
+                      Instruction::MoveForward,
+                      Instruction::EndFor {
+                          target_pointer: 0,
+                          nr_iterations,
+                      }])
+    }
+
     fn make_round_params(max_steps: u32) -> RoundParams {
         RoundParams {
             tape_length: 10,
             invert_polarity: false,
-            max_steps: max_steps,
+            max_steps,
         }
     }
 
+    #[test]
+    fn playTimed_idleBots_takesMaxSteps() {
+        let bot_a = make_bot_idle_three_turns();
+        let bot_b = make_bot_idle_three_turns();
+        assert_eq!(play_timed(&bot_a, &bot_b, &make_round_params(2)),
+                   (RoundResult::draw(), 2));
+    }
+
+    #[test]
+    fn playTimed_suicidalBot_takesOneStep() {
+        let bot_a = make_suicidal_bot();
+        let bot_b = make_empty_bot();
+        assert_eq!(play_timed(&bot_a, &bot_b, &make_round_params(100)),
+                   (RoundResult::end_bot_wins(), 1));
+    }
+
+    #[test]
+    fn playTimed_forLoop_repeatsBodyWithoutExtraSteps() {
+        let bot_a = make_for_loop_bot(10);
+        let bot_b = make_empty_bot();
+        assert_eq!(play_timed(&bot_a, &bot_b, &make_round_params(100)),
+                   (RoundResult::end_bot_wins(), 10));
+    }
+
+    #[test]
+    fn playTimed_forLoopWithZeroIterations_skipsBody() {
+        let bot_a = make_for_loop_bot(0);
+        let bot_b = make_bot_idle_three_turns();
+        assert_eq!(play_timed(&bot_a, &bot_b, &make_round_params(100)),
+                   (RoundResult::draw(), 3));
+    }
+
+    #[test]
+    fn playTimed_emptyForLoopWithHugeIterationCount_takesNoSteps() {
+        let bot_a = Bot::new(vec![Instruction::StartFor { target_pointer: 1 },
+                                  Instruction::EndFor {
+                                      target_pointer: 0,
+                                      nr_iterations: usize::MAX,
+                                  },
+                                  Instruction::MoveBack]);
+        let bot_b = make_empty_bot();
+        assert_eq!(play_timed(&bot_a, &bot_b, &make_round_params(100)),
+                   (RoundResult::end_bot_wins(), 1));
+    }
+
     #[test]
     fn iter_maxStepsIsZero_returnsDrawAtFirstStep() {
         let round_params = make_round_params(0);
@@ -1,8 +1,31 @@
+use json::{Object, ToJson};
+
 /// Specifies the conditions of a single round of Brainfuck Jousting.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[derive(PartialEq)]
 pub struct RoundParams {
     pub tape_length: u32,
     pub invert_polarity: bool,
     pub max_steps: u32,
 }
+
+impl RoundParams {
+    /// Returns "normal" or "reversed".
+    pub fn polarity_name(&self) -> &'static str {
+        if self.invert_polarity {
+            "reversed"
+        } else {
+            "normal"
+        }
+    }
+}
+
+impl ToJson for RoundParams {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("tape_length", &self.tape_length)
+            .field("polarity", self.polarity_name())
+            .field("max_steps", &self.max_steps)
+            .build()
+    }
+}
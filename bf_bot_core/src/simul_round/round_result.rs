@@ -1,4 +1,6 @@
-#[derive(PartialEq, Debug)]
+use json::{Object, ToJson};
+
+#[derive(PartialEq, Debug, Clone)]
 pub struct RoundResult {
     pub bot_a_lost: bool,
     pub bot_b_lost: bool,
@@ -7,8 +9,8 @@ pub struct RoundResult {
 impl RoundResult {
     pub fn new(bot_a_lost: bool, bot_b_lost: bool) -> Self {
         RoundResult {
-            bot_a_lost: bot_a_lost,
-            bot_b_lost: bot_b_lost,
+            bot_a_lost,
+            bot_b_lost,
         }
     }
 
@@ -60,4 +62,25 @@ impl RoundResult {
     pub fn round_is_finished(&self) -> bool {
         self.bot_a_lost || self.bot_b_lost
     }
+
+    /// Names the winner of the round: "a" for the start bot, "b" for the end bot, or "draw".
+    /// Returns None while the round is still ongoing.
+    pub fn winner_name(&self) -> Option<&'static str> {
+        match (self.bot_a_lost, self.bot_b_lost) {
+            (false, false) => None,
+            (false, true) => Some("a"),
+            (true, false) => Some("b"),
+            (true, true) => Some("draw"),
+        }
+    }
+}
+
+impl ToJson for RoundResult {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("bot_a_lost", &self.bot_a_lost)
+            .field("bot_b_lost", &self.bot_b_lost)
+            .field("winner", &self.winner_name().map(str::to_string))
+            .build()
+    }
 }
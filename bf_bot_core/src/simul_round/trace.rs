@@ -0,0 +1,137 @@
+//! Records the full course of a single round, step by step.
+//! Playing a round with `play` only yields its result. Tracing a round additionally keeps the
+//! state of the arena after every step, which is what is needed to characterise or visualise the
+//! behaviour of the bots.
+
+use bf::Bot;
+use engine::{Arena, Mutation};
+use json::{Object, ToJson};
+use simul_round::{RoundParams, RoundResult};
+
+/// The state of the arena after a single step.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StepState {
+    /// The contents of the tape after the step was executed.
+    pub tape: Vec<i8>,
+    /// Position of the start bot on the tape. This may lie outside of the tape if the bot has
+    /// just run off of it.
+    pub start_bot_pos: i32,
+    /// Position of the end bot on the tape. This may lie outside of the tape if the bot has just
+    /// run off of it.
+    pub end_bot_pos: i32,
+    /// The cell mutation that the start bot applied during this step, if any.
+    pub start_bot_mutation: Option<Mutation>,
+    /// The cell mutation that the end bot applied during this step, if any.
+    pub end_bot_mutation: Option<Mutation>,
+}
+
+/// The complete course of a single round.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RoundTrace {
+    /// The tape before the first step was executed.
+    pub initial_tape: Vec<i8>,
+    /// The state of the arena after each step, in order.
+    pub steps: Vec<StepState>,
+    /// The outcome of the round.
+    pub result: RoundResult,
+}
+
+/// Plays a single round and records the state of the arena after every step.
+pub fn trace(bot_a: &Bot, bot_b: &Bot, round_params: &RoundParams) -> RoundTrace {
+    let mut arena = Arena::new(bot_a, bot_b, round_params);
+    let initial_tape = arena.get_tape().clone();
+    let mut steps = vec![];
+    loop {
+        let step_nr = arena.get_step_nr();
+        let result = arena.step();
+        if arena.get_step_nr() != step_nr {
+            steps.push(capture_state(&arena));
+        }
+        if result.round_is_finished() {
+            return RoundTrace {
+                initial_tape,
+                steps,
+                result,
+            };
+        }
+    }
+}
+
+fn capture_state(arena: &Arena) -> StepState {
+    let &(start_bot_mutation, end_bot_mutation) = arena.get_last_mutations();
+    StepState {
+        tape: arena.get_tape().clone(),
+        start_bot_pos: arena.get_start_bot().get_signed_pos(),
+        end_bot_pos: arena.get_end_bot().get_signed_pos(),
+        start_bot_mutation,
+        end_bot_mutation,
+    }
+}
+
+impl ToJson for StepState {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("tape", &self.tape)
+            .field("pos_a", &self.start_bot_pos)
+            .field("pos_b", &self.end_bot_pos)
+            .field("mutation_a", &self.start_bot_mutation)
+            .field("mutation_b", &self.end_bot_mutation)
+            .build()
+    }
+}
+
+impl ToJson for RoundTrace {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("initial_tape", &self.initial_tape)
+            .field("steps", &self.steps)
+            .field("result", &self.result)
+            .build()
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf::Instruction;
+    use engine::Mutation;
+    use simul_round::{RoundParams, RoundResult};
+
+    fn make_round_params(max_steps: u32) -> RoundParams {
+        RoundParams {
+            tape_length: 10,
+            invert_polarity: false,
+            max_steps,
+        }
+    }
+
+    #[test]
+    fn trace_bothEmptyBots_recordsNoSteps() {
+        let bot = Bot::new(vec![]);
+        let round_trace = trace(&bot, &bot, &make_round_params(100));
+        assert!(round_trace.steps.is_empty());
+        assert_eq!(round_trace.result, RoundResult::draw());
+    }
+
+    #[test]
+    fn trace_idleBots_recordsOneStateForEveryStepUntilMaxSteps() {
+        let bot = Bot::new(vec![Instruction::SkipExecution; 10]);
+        let round_trace = trace(&bot, &bot, &make_round_params(3));
+        assert_eq!(round_trace.steps.len(), 3);
+        assert_eq!(round_trace.result, RoundResult::draw());
+    }
+
+    #[test]
+    fn trace_movingBots_recordsPositionsAndMutations() {
+        let bot_a = Bot::new(vec![Instruction::MoveForward, Instruction::Increment]);
+        let bot_b = Bot::new(vec![Instruction::SkipExecution]);
+        let round_trace = trace(&bot_a, &bot_b, &make_round_params(100));
+        assert_eq!(round_trace.initial_tape, round_trace.steps[0].tape);
+        assert_eq!(round_trace.steps[0].start_bot_pos, 1);
+        assert_eq!(round_trace.steps[0].end_bot_pos, 9);
+        assert_eq!(round_trace.steps[1].start_bot_mutation, Some(Mutation::new(1, 1)));
+        assert_eq!(round_trace.steps[1].end_bot_mutation, None);
+        assert_eq!(round_trace.steps[1].tape[1], 1);
+    }
+}
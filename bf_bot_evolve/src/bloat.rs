@@ -0,0 +1,144 @@
+//! Keeping evolved programs small.
+//! Variation operators insert code much more easily than they remove it, so without any pressure
+//! programs keep growing, most of it dead code. Growth is held back in three ways: a parsimony
+//! penalty on the selection score, hard limits on the size of offspring, and pruning of the
+//! instructions that never executed in complete games on every round.
+
+use genome::{Genome, Token};
+
+/// Settings for bloat control. The default applies none of it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BloatParams {
+    /// Selection score subtracted for every instruction of a program.
+    pub parsimony: f64,
+    /// Offspring with more instructions than this are discarded.
+    pub max_length: Option<usize>,
+    /// Offspring with more instructions than this, with for loops written out, are discarded.
+    pub max_expanded_length: Option<usize>,
+    /// If set, instructions that never executed during evaluation are removed. This is skipped
+    /// for evaluations that don't play complete games on every round: with a round sample, and
+    /// for the candidates that a race eliminates early.
+    pub prune: bool,
+}
+
+impl Default for BloatParams {
+    fn default() -> BloatParams {
+        BloatParams {
+            parsimony: 0.0,
+            max_length: None,
+            max_expanded_length: None,
+            prune: false,
+        }
+    }
+}
+
+impl BloatParams {
+    /// Returns true if the genome is within the size limits.
+    pub fn allows(&self, genome: &Genome) -> bool {
+        self.max_length.is_none_or(|max_length| genome.len() <= max_length) &&
+        self.max_expanded_length
+            .is_none_or(|max_expanded_length| genome.expanded_len() <= max_expanded_length)
+    }
+
+    /// The amount the selection score of the genome is lowered by.
+    pub fn penalty(&self, genome: &Genome) -> f64 {
+        self.parsimony * genome.len() as f64
+    }
+}
+
+/// How much bloat was removed in a single generation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct BloatStats {
+    /// Number of programs that lost at least one instruction to pruning.
+    pub pruned_programs: usize,
+    /// Total number of instructions removed by pruning.
+    pub pruned_tokens: usize,
+    /// Number of offspring that were discarded for exceeding a size limit.
+    pub rejected_offspring: usize,
+}
+
+/// Removes the instructions that were never executed, given how often each token was executed.
+/// A while loop that was never entered is removed as a whole, but the brackets of a loop that was
+/// reached are kept, as is every for loop with a remaining body. The result executes exactly the
+/// same sequence of instructions as the original in the rounds that were counted.
+pub fn prune(genome: &Genome, counts: &[u32]) -> Genome {
+    let tokens = genome.get_tokens();
+    assert_eq!(tokens.len(), counts.len(), "Every token needs an execution count.");
+    let mut keep = vec![true; tokens.len()];
+    let mut index = 0;
+    while index < tokens.len() {
+        match tokens[index] {
+            Token::StartWhileNotZero if counts[index] == 0 => {
+                let end = genome.matching_bracket(index).unwrap();
+                for kept in &mut keep[index..end + 1] {
+                    *kept = false;
+                }
+                index = end;
+            }
+            ref token if token.is_simple() => keep[index] = counts[index] > 0,
+            _ => {}
+        }
+        index += 1;
+    }
+    // Inner for loops close first, so emptied loops are removed from the inside out.
+    for (end, token) in tokens.iter().enumerate() {
+        if let Token::EndFor { .. } = *token {
+            let start = genome.matching_bracket(end).unwrap();
+            if keep[end] && !keep[start + 1..end].iter().any(|&kept| kept) {
+                keep[start] = false;
+                keep[end] = false;
+            }
+        }
+    }
+    Genome::new(tokens.iter()
+        .zip(keep)
+        .filter(|&(_, kept)| kept)
+        .map(|(&token, _)| token)
+        .collect())
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    fn prune_source(source_code: &str, counts: &[u32]) -> String {
+        prune(&Genome::from_source(source_code).unwrap(), counts).to_source()
+    }
+
+    #[test]
+    fn prune_removesUnexecutedInstructions() {
+        assert_eq!(prune_source(">+<-", &[1, 0, 2, 0]), "><");
+    }
+
+    #[test]
+    fn prune_unreachedWhileLoop_isRemovedWhole() {
+        assert_eq!(prune_source(">[-]+", &[1, 0, 0, 0, 1]), ">+");
+    }
+
+    #[test]
+    fn prune_skippedWhileLoop_keepsBrackets() {
+        assert_eq!(prune_source("[-]", &[3, 0, 0]), "[]");
+    }
+
+    #[test]
+    fn prune_emptiedForLoops_areRemovedFromTheInsideOut() {
+        assert_eq!(prune_source("((+)*2)*3>", &[0, 0, 0, 0, 0, 1]), ">");
+        assert_eq!(prune_source("(>+)*4", &[0, 4, 0, 0]), "(>)*4");
+    }
+
+    #[test]
+    fn allows_checksBothLimits() {
+        let genome = Genome::from_source("(>)*10").unwrap();
+        let params = BloatParams {
+            max_length: Some(3),
+            ..BloatParams::default()
+        };
+        assert!(params.allows(&genome));
+        let params = BloatParams {
+            max_expanded_length: Some(9),
+            ..params
+        };
+        assert!(!params.allows(&genome));
+    }
+}
@@ -0,0 +1,129 @@
+//! Saving and restoring the state of an evolution run.
+//! A checkpoint is a small text file: the generation number, the state of the random number
+//! generator, and the source code of every genome in the population, one per line. An empty line
+//! is an empty genome.
+//!
+//! ```text
+//! generation 12
+//! rng 1234567890
+//! >>>[-]
+//! (>)*8[+]
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use genome::Genome;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Checkpoint {
+    pub generation: usize,
+    pub rng_state: u64,
+    pub genomes: Vec<Genome>,
+}
+
+/// Reasons why a checkpoint can't be read.
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    /// The file is not a valid checkpoint. Holds the line number and a description.
+    Malformed(usize, String),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CheckpointError::Io(ref error) => write!(f, "{}", error),
+            CheckpointError::Malformed(line, ref description) => {
+                write!(f, "line {}: {}", line, description)
+            }
+        }
+    }
+}
+
+impl Checkpoint {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Checkpoint, CheckpointError> {
+        let text = fs::read_to_string(path).map_err(CheckpointError::Io)?;
+        Checkpoint::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Checkpoint, CheckpointError> {
+        let mut lines = text.lines().enumerate().map(|(index, line)| (index + 1, line));
+        let generation = read_header(lines.next(), "generation")?;
+        let rng_state = read_header(lines.next(), "rng")?;
+        let genomes = lines.map(|(number, line)| {
+                Genome::from_source(line)
+                    .map_err(|error| CheckpointError::Malformed(number, error.to_string()))
+            })
+            .collect::<Result<Vec<Genome>, CheckpointError>>()?;
+        Ok(Checkpoint {
+            generation: generation as usize,
+            rng_state,
+            genomes,
+        })
+    }
+}
+
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "generation {}", self.generation)?;
+        writeln!(f, "rng {}", self.rng_state)?;
+        for genome in &self.genomes {
+            writeln!(f, "{}", genome)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_header(line: Option<(usize, &str)>, name: &str) -> Result<u64, CheckpointError> {
+    let (number, line) = line.ok_or_else(|| {
+            CheckpointError::Malformed(0, format!("missing '{}' line", name))
+        })?;
+    let mut words = line.split_whitespace();
+    match (words.next(), words.next().map(str::parse::<u64>)) {
+        (Some(word), Some(Ok(value))) if word == name => Ok(value),
+        _ => Err(CheckpointError::Malformed(number, format!("expected '{} <number>'", name))),
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use genome::Genome;
+
+    #[test]
+    fn parse_roundTripsThroughDisplay() {
+        let checkpoint = Checkpoint {
+            generation: 7,
+            rng_state: 99,
+            genomes: vec![Genome::from_source(">>[-]").unwrap(), Genome::default()],
+        };
+        let parsed = Checkpoint::parse(&checkpoint.to_string()).unwrap();
+        assert_eq!(parsed.generation, 7);
+        assert_eq!(parsed.rng_state, 99);
+        assert_eq!(parsed.genomes, checkpoint.genomes);
+    }
+
+    #[test]
+    fn parse_missingRngLine_reportsLine() {
+        match Checkpoint::parse("generation 1\n>>>\n") {
+            Err(CheckpointError::Malformed(line, _)) => assert_eq!(line, 2),
+            other => panic!("Expected malformed checkpoint, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_brokenGenome_reportsLine() {
+        match Checkpoint::parse("generation 1\nrng 5\n>>>\n[[\n") {
+            Err(CheckpointError::Malformed(line, _)) => assert_eq!(line, 4),
+            other => panic!("Expected malformed checkpoint, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,910 @@
+//! Configuration files for evolution runs.
+//! An experiment is described in a plain text file of `key = value` lines, so it can be versioned
+//! and shared without touching any code. Lines starting with `#` are comments. Relative paths are
+//! resolved against the directory of the configuration file.
+//!
+//! ```text
+//! # Evolve rushers against the sample bots.
+//! opponents = ../bots/DecoyBot.bf, ../bots/FastClearBot.bf
+//! gauntlet = rushers.gauntlet
+//! seed_bots = ../bots
+//! seed_bots.fill = classic
+//! population_size = 100
+//! selection = pareto
+//! objectives = total_points, expanded_length
+//! operators.crossover = 0.5
+//! rounds.sample = 10
+//! rounds.polarity = both
+//! seed = 42
+//! threads = 4
+//! checkpoint = run.checkpoint
+//! log = run.csv
+//! lineage = champion.dot
+//! bloat.max_length = 200
+//! bloat.prune = true
+//! stop.generations = 500
+//! ```
+//!
+//! Every key is optional except at least one stop criterion, and `opponents` or a `gauntlet`, or
+//! both. The opponents of a gauntlet are weighted as the gauntlet says. The recognised keys are
+//! listed in `KEYS`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use bf_bot_core::simul_game::{COMPLETE_GAME_MAX_STEPS, MAX_TAPE_LENGTH, MIN_TAPE_LENGTH};
+use bf_bot_core::simul_round::RoundParams;
+use bloat::BloatParams;
+use evolution::{Evolution, EvolutionParams, OperatorRates, Selection};
+use lineage::LineageFormat;
+use local_search::{Annealing, LocalSearchParams, MemeticParams};
+use novelty::NoveltyParams;
+use pareto::Objective;
+use racing::RacingParams;
+use seeding::FillStrategy;
+use speciation::SpeciationParams;
+use stats::LogFormat;
+
+/// Every key that may appear in a configuration file.
+pub const KEYS: &[&str] = &["population_size",
+                            "elitism",
+                            "tournament_size",
+                            "initial_program_length",
+                            "selection",
+                            "objectives",
+                            "operators.crossover",
+                            "operators.point_mutation",
+                            "operators.insertion",
+                            "operators.deletion",
+                            "operators.wrap_loop",
+                            "operators.unwrap_loop",
+                            "operators.repeat_count",
+                            "opponents",
+                            "gauntlet",
+                            "rounds.sample",
+                            "rounds.min_tape_length",
+                            "rounds.max_tape_length",
+                            "rounds.polarity",
+                            "rounds.max_steps",
+                            "novelty",
+                            "novelty.k",
+                            "novelty.sample_interval",
+                            "novelty.archive_threshold",
+                            "novelty.max_archive_size",
+                            "novelty.fitness_weight",
+                            "novelty.novelty_weight",
+                            "speciation",
+                            "speciation.compatibility_threshold",
+                            "speciation.interspecies_crossover_rate",
+                            "speciation.youth_generations",
+                            "speciation.youth_bonus",
+                            "local_search",
+                            "local_search.rate",
+                            "local_search.iterations",
+                            "local_search.temperature",
+                            "local_search.cooling_rate",
+                            "bloat.parsimony",
+                            "bloat.max_length",
+                            "bloat.max_expanded_length",
+                            "bloat.prune",
+                            "racing",
+                            "racing.stages",
+                            "racing.keep_fraction",
+                            "racing.opponents",
+                            "racing.rounds",
+                            "racing.max_steps",
+                            "seed",
+                            "seed_bots",
+                            "seed_bots.fill",
+                            "threads",
+                            "checkpoint",
+                            "checkpoint_interval",
+                            "log",
+                            "lineage",
+                            "lineage.depth",
+                            "stop.generations",
+                            "stop.fitness",
+                            "stop.seconds"];
+
+/// A fully validated description of an evolution run.
+#[derive(Debug, Clone)]
+pub struct RunConfig {
+    pub params: EvolutionParams,
+    /// Source files of the bots that the population is evaluated against.
+    pub opponents: Vec<PathBuf>,
+    /// A gauntlet file with more opponents, which are evaluated against after the others.
+    pub gauntlet: Option<PathBuf>,
+    pub seed: u64,
+    /// A directory of bots that the initial population starts with, and the way the rest of the
+    /// population is filled up.
+    pub seed_bots: Option<(PathBuf, FillStrategy)>,
+    /// File the state of the run is saved to, and resumed from if it exists.
+    pub checkpoint: Option<PathBuf>,
+    /// A checkpoint is saved every this many generations, and when the run stops.
+    pub checkpoint_interval: usize,
+    /// File the statistics of every generation are written to. The format follows from the
+    /// extension.
+    pub log: Option<(PathBuf, LogFormat)>,
+    /// File the ancestry of the best individual is written to when the run stops. The format
+    /// follows from the extension.
+    pub lineage: Option<(PathBuf, LineageFormat)>,
+    /// Number of generations of ancestors included in a Newick lineage.
+    pub lineage_depth: usize,
+    pub stop: StopCriteria,
+}
+
+/// Conditions under which a run stops. The run stops as soon as any of them is met.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct StopCriteria {
+    /// Stop after this many generations.
+    pub generations: Option<usize>,
+    /// Stop once the best individual reaches this fitness. Note that racing changes the scale of
+    /// the fitness.
+    pub fitness: Option<f64>,
+    /// Stop once the run has taken this long. Time spent before a resume doesn't count.
+    pub duration: Option<Duration>,
+}
+
+impl StopCriteria {
+    pub fn is_met(&self, evolution: &Evolution, elapsed: Duration) -> bool {
+        self.generations.is_some_and(|generations| evolution.get_generation() >= generations) ||
+        self.fitness.is_some_and(|fitness| evolution.best().get_fitness() >= fitness) ||
+        self.duration.is_some_and(|duration| elapsed >= duration)
+    }
+}
+
+/// Reason why a configuration file was rejected.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConfigError {
+    /// The line the problem was found on, if it can be attributed to one.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ConfigError {
+    fn new(line: Option<usize>, message: String) -> ConfigError {
+        ConfigError { line, message }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl RunConfig {
+    /// Reads and validates a configuration file.
+    pub fn from_file(path: &Path) -> Result<RunConfig, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|error| {
+                ConfigError::new(None, format!("Can't read {}: {}", path.display(), error))
+            })?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        RunConfig::parse(&text, base_dir)
+    }
+
+    /// Parses and validates the text of a configuration file. Relative paths are resolved
+    /// against the given directory.
+    pub fn parse(text: &str, base_dir: &Path) -> Result<RunConfig, ConfigError> {
+        let entries = Entries::parse(text)?;
+        let opponents: Vec<PathBuf> = match entries.get_raw("opponents") {
+            Some(list) => split_list(list).map(|path| base_dir.join(path)).collect(),
+            None => vec![],
+        };
+        let gauntlet = entries.get_raw("gauntlet").map(|path| base_dir.join(path));
+        entries.check(!opponents.is_empty() || gauntlet.is_some(),
+                      "opponents",
+                      "At least one opponent or a gauntlet is required.")?;
+        let params = EvolutionParams {
+            population_size: entries.get("population_size", 50)?,
+            elitism: entries.get("elitism", 2)?,
+            tournament_size: entries.get("tournament_size", 3)?,
+            initial_program_length: entries.get("initial_program_length", 20)?,
+            selection: parse_selection(&entries)?,
+            operator_rates: parse_operator_rates(&entries)?,
+            rounds: parse_rounds(&entries)?,
+            round_sample: entries.get_optional("rounds.sample")?,
+            threads: entries.get("threads", 1)?,
+            novelty: parse_novelty(&entries)?,
+            speciation: parse_speciation(&entries)?,
+            local_search: parse_local_search(&entries)?,
+            bloat: BloatParams {
+                parsimony: entries.get_number("bloat.parsimony", 0.0)?,
+                max_length: entries.get_optional("bloat.max_length")?,
+                max_expanded_length: entries.get_optional("bloat.max_expanded_length")?,
+                prune: entries.get("bloat.prune", false)?,
+            },
+            racing: parse_racing(&entries)?,
+            opponent_weights: vec![],
+        };
+        entries.check(params.population_size > 0,
+                      "population_size",
+                      "The population size must be at least 1.")?;
+        entries.check(params.elitism <= params.population_size,
+                      "elitism",
+                      "Elitism can't exceed the population size.")?;
+        entries.check(params.tournament_size > 0,
+                      "tournament_size",
+                      "The tournament size must be at least 1.")?;
+        entries.check(params.round_sample != Some(0),
+                      "rounds.sample",
+                      "The round sample must contain at least 1 round.")?;
+        entries.check(params.threads > 0, "threads", "At least 1 thread is required.")?;
+        entries.check(params.racing.is_none() || params.selection == Selection::Tournament,
+                      "racing",
+                      "Racing can't be combined with pareto selection.")?;
+        entries.check(params.novelty.is_none() || params.selection == Selection::Tournament,
+                      "novelty",
+                      "Novelty search can't be combined with pareto selection.")?;
+        entries.check(params.speciation.is_none() || params.selection == Selection::Tournament,
+                      "speciation",
+                      "Speciation can't be combined with pareto selection.")?;
+        entries.check(params.bloat.parsimony >= 0.0,
+                      "bloat.parsimony",
+                      "The parsimony penalty can't be negative.")?;
+        entries.check(params.bloat
+                          .max_length
+                          .is_none_or(|max_length| params.initial_program_length <= max_length),
+                      "bloat.max_length",
+                      "The maximum length can't be below the initial program length.")?;
+        entries.check(params.bloat
+                          .max_expanded_length
+                          .is_none_or(|max_expanded_length| {
+                              params.initial_program_length <= max_expanded_length
+                          }),
+                      "bloat.max_expanded_length",
+                      "The maximum expanded length can't be below the initial program length.")?;
+        let stop = StopCriteria {
+            generations: entries.get_optional("stop.generations")?,
+            fitness: entries.get_optional_number("stop.fitness")?,
+            duration: entries.get_optional("stop.seconds")?.map(Duration::from_secs),
+        };
+        if stop == StopCriteria::default() {
+            return Err(ConfigError::new(None,
+                                        "At least one of stop.generations, stop.fitness and \
+                                         stop.seconds is required."
+                                            .to_string()));
+        }
+        let checkpoint_interval = entries.get("checkpoint_interval", 10)?;
+        entries.check(checkpoint_interval > 0,
+                      "checkpoint_interval",
+                      "The checkpoint interval must be at least 1.")?;
+        let log = match entries.get_raw("log") {
+            Some(path) => {
+                let path = base_dir.join(path);
+                let format = LogFormat::from_path(&path).ok_or_else(|| {
+                        entries.error("log",
+                                      "The log must have a .csv or .jsonl extension.".to_string())
+                    })?;
+                Some((path, format))
+            }
+            None => None,
+        };
+        let fill = match entries.get_raw("seed_bots.fill") {
+            Some(name) => {
+                FillStrategy::from_name(name).ok_or_else(|| {
+                        entries.error("seed_bots.fill",
+                                      format!("Unknown fill strategy '{}', expected mutants, \
+                                               grammar or classic.",
+                                              name))
+                    })?
+            }
+            None => FillStrategy::Mutants,
+        };
+        let seed_bots = entries.get_raw("seed_bots").map(|dir| (base_dir.join(dir), fill));
+        entries.check(seed_bots.is_some() || entries.get_raw("seed_bots.fill").is_none(),
+                      "seed_bots.fill",
+                      "'seed_bots.fill' has no effect without 'seed_bots'.")?;
+        let lineage = match entries.get_raw("lineage") {
+            Some(path) => {
+                let path = base_dir.join(path);
+                let format = LineageFormat::from_path(&path).ok_or_else(|| {
+                        entries.error("lineage",
+                                      "The lineage must have a .dot, .gv, .nwk or .newick \
+                                       extension."
+                                          .to_string())
+                    })?;
+                Some((path, format))
+            }
+            None => None,
+        };
+        Ok(RunConfig {
+            params,
+            opponents,
+            gauntlet,
+            seed: entries.get("seed", 0)?,
+            seed_bots,
+            checkpoint: entries.get_raw("checkpoint").map(|path| base_dir.join(path)),
+            checkpoint_interval,
+            log,
+            lineage,
+            lineage_depth: entries.get("lineage.depth", 20)?,
+            stop,
+        })
+    }
+}
+
+fn parse_selection(entries: &Entries) -> Result<Selection, ConfigError> {
+    let objectives = match entries.get_raw("objectives") {
+        Some(list) => {
+            split_list(list)
+                .map(|name| {
+                    parse_objective(name).ok_or_else(|| {
+                        entries.error("objectives", format!("Unknown objective '{}'.", name))
+                    })
+                })
+                .collect::<Result<Vec<Objective>, ConfigError>>()?
+        }
+        None => vec![],
+    };
+    match entries.get_raw("selection").unwrap_or("tournament") {
+        "tournament" => {
+            entries.check(objectives.is_empty(),
+                          "objectives",
+                          "Objectives only apply to pareto selection.")?;
+            Ok(Selection::Tournament)
+        }
+        "pareto" => {
+            entries.check(!objectives.is_empty(),
+                          "selection",
+                          "Pareto selection requires a list of objectives.")?;
+            Ok(Selection::Pareto(objectives))
+        }
+        other => {
+            Err(entries.error("selection",
+                              format!("Unknown selection '{}', expected tournament or pareto.",
+                                      other)))
+        }
+    }
+}
+
+fn parse_objective(name: &str) -> Option<Objective> {
+    match name {
+        "total_points" => Some(Objective::TotalPoints),
+        "worst_tape_length" => Some(Objective::WorstTapeLength),
+        "reversed_polarity_points" => Some(Objective::ReversedPolarityPoints),
+        "expanded_length" => Some(Objective::ExpandedLength),
+        "cycles_to_win" => Some(Objective::CyclesToWin),
+        _ => None,
+    }
+}
+
+fn parse_operator_rates(entries: &Entries) -> Result<OperatorRates, ConfigError> {
+    let defaults = OperatorRates::default();
+    let rates = OperatorRates {
+        crossover: entries.get_number("operators.crossover", defaults.crossover)?,
+        point_mutation: entries.get_number("operators.point_mutation", defaults.point_mutation)?,
+        insertion: entries.get_number("operators.insertion", defaults.insertion)?,
+        deletion: entries.get_number("operators.deletion", defaults.deletion)?,
+        wrap_loop: entries.get_number("operators.wrap_loop", defaults.wrap_loop)?,
+        unwrap_loop: entries.get_number("operators.unwrap_loop", defaults.unwrap_loop)?,
+        repeat_count: entries.get_number("operators.repeat_count", defaults.repeat_count)?,
+    };
+    // The operator keys are listed in the same order as the weights.
+    if let Some(key) = KEYS.iter()
+        .filter(|key| key.starts_with("operators."))
+        .zip(rates.weights().iter())
+        .find(|&(_, &rate)| rate < 0.0)
+        .map(|(key, _)| key) {
+        return Err(entries.error(key, "Operator rates can't be negative.".to_string()));
+    }
+    if rates.weights().iter().all(|&rate| rate == 0.0) {
+        return Err(ConfigError::new(None,
+                                    "At least one operator rate must be positive.".to_string()));
+    }
+    Ok(rates)
+}
+
+/// Builds the list of rounds from the rule variants: the range of tape lengths, the polarities
+/// and the maximum number of steps per round.
+fn parse_rounds(entries: &Entries) -> Result<Vec<RoundParams>, ConfigError> {
+    let min_tape_length = entries.get("rounds.min_tape_length", MIN_TAPE_LENGTH)?;
+    let max_tape_length = entries.get("rounds.max_tape_length", MAX_TAPE_LENGTH)?;
+    let max_steps = entries.get("rounds.max_steps", COMPLETE_GAME_MAX_STEPS)?;
+    entries.check(min_tape_length >= 2,
+                  "rounds.min_tape_length",
+                  "The tape must be at least 2 cells long, to hold both flags.")?;
+    entries.check(min_tape_length <= max_tape_length,
+                  "rounds.max_tape_length",
+                  "The maximum tape length can't be below the minimum tape length.")?;
+    entries.check(max_steps > 0, "rounds.max_steps", "Rounds must last at least 1 step.")?;
+    let polarities = match entries.get_raw("rounds.polarity").unwrap_or("both") {
+        "normal" => vec![false],
+        "reversed" => vec![true],
+        "both" => vec![false, true],
+        other => {
+            return Err(entries.error("rounds.polarity",
+                                     format!("Unknown polarity '{}', expected normal, reversed \
+                                              or both.",
+                                             other)))
+        }
+    };
+    Ok((min_tape_length..max_tape_length + 1)
+        .flat_map(|tape_length| {
+            polarities.iter().map(move |&invert_polarity| {
+                RoundParams {
+                    tape_length,
+                    invert_polarity,
+                    max_steps,
+                }
+            })
+        })
+        .collect())
+}
+
+fn parse_novelty(entries: &Entries) -> Result<Option<NoveltyParams>, ConfigError> {
+    if !entries.get("novelty", false)? {
+        return entries.reject_section("novelty").map(|_| None);
+    }
+    let defaults = NoveltyParams::default();
+    let archive_threshold =
+        entries.get_number("novelty.archive_threshold", defaults.archive_threshold)?;
+    entries.check(archive_threshold >= 0.0,
+                  "novelty.archive_threshold",
+                  "The archive threshold can't be negative.")?;
+    Ok(Some(NoveltyParams {
+        k: entries.get("novelty.k", defaults.k)?,
+        sample_interval: entries.get("novelty.sample_interval", defaults.sample_interval)?,
+        archive_threshold,
+        max_archive_size: entries.get("novelty.max_archive_size", defaults.max_archive_size)?,
+        fitness_weight: entries.get_number("novelty.fitness_weight", defaults.fitness_weight)?,
+        novelty_weight: entries.get_number("novelty.novelty_weight", defaults.novelty_weight)?,
+        ..defaults
+    }))
+}
+
+fn parse_speciation(entries: &Entries) -> Result<Option<SpeciationParams>, ConfigError> {
+    if !entries.get("speciation", false)? {
+        return entries.reject_section("speciation").map(|_| None);
+    }
+    let defaults = SpeciationParams::default();
+    let compatibility_threshold =
+        entries.get_number("speciation.compatibility_threshold", defaults.compatibility_threshold)?;
+    let interspecies_crossover_rate = entries.get_number("speciation.interspecies_crossover_rate",
+             defaults.interspecies_crossover_rate)?;
+    let youth_bonus = entries.get_number("speciation.youth_bonus", defaults.youth_bonus)?;
+    entries.check(compatibility_threshold >= 0.0,
+                  "speciation.compatibility_threshold",
+                  "The compatibility threshold can't be negative.")?;
+    entries.check((0.0..=1.0).contains(&interspecies_crossover_rate),
+                  "speciation.interspecies_crossover_rate",
+                  "The interspecies crossover rate must lie between 0 and 1.")?;
+    entries.check(youth_bonus >= 1.0,
+                  "speciation.youth_bonus",
+                  "The youth bonus can't be below 1.")?;
+    Ok(Some(SpeciationParams {
+        compatibility_threshold,
+        interspecies_crossover_rate,
+        youth_generations: entries.get("speciation.youth_generations",
+                                       defaults.youth_generations)?,
+        youth_bonus,
+    }))
+}
+
+fn parse_local_search(entries: &Entries) -> Result<Option<MemeticParams>, ConfigError> {
+    if !entries.get("local_search", false)? {
+        return entries.reject_section("local_search").map(|_| None);
+    }
+    let defaults = MemeticParams::default();
+    let annealing = match entries.get_optional_number("local_search.temperature")? {
+        Some(initial_temperature) => {
+            let cooling_rate = entries.get_number("local_search.cooling_rate",
+                                                  Annealing::default().cooling_rate)?;
+            entries.check(initial_temperature > 0.0,
+                          "local_search.temperature",
+                          "The temperature must be above 0.")?;
+            entries.check(cooling_rate > 0.0 && cooling_rate <= 1.0,
+                          "local_search.cooling_rate",
+                          "The cooling rate must be above 0 and at most 1.")?;
+            Some(Annealing {
+                initial_temperature,
+                cooling_rate,
+            })
+        }
+        None => None,
+    };
+    let rate = entries.get_number("local_search.rate", defaults.rate)?;
+    entries.check((0.0..=1.0).contains(&rate),
+                  "local_search.rate",
+                  "The local search rate must lie between 0 and 1.")?;
+    Ok(Some(MemeticParams {
+        rate,
+        search: LocalSearchParams {
+            iterations: entries.get("local_search.iterations", defaults.search.iterations)?,
+            annealing,
+        },
+    }))
+}
+
+fn parse_racing(entries: &Entries) -> Result<Option<RacingParams>, ConfigError> {
+    if !entries.get("racing", false)? {
+        return entries.reject_section("racing").map(|_| None);
+    }
+    let defaults = RacingParams::default();
+    let racing_params = RacingParams {
+        nr_stages: entries.get("racing.stages", defaults.nr_stages)?,
+        keep_fraction: entries.get_number("racing.keep_fraction", defaults.keep_fraction)?,
+        initial_opponents: entries.get("racing.opponents", defaults.initial_opponents)?,
+        initial_rounds: entries.get("racing.rounds", defaults.initial_rounds)?,
+        initial_max_steps: entries.get("racing.max_steps", defaults.initial_max_steps)?,
+    };
+    entries.check(racing_params.nr_stages > 0,
+                  "racing.stages",
+                  "A race needs at least 1 stage.")?;
+    entries.check(racing_params.keep_fraction > 0.0 && racing_params.keep_fraction <= 1.0,
+                  "racing.keep_fraction",
+                  "The fraction of candidates that is kept must be above 0 and at most 1.")?;
+    entries.check(racing_params.initial_opponents > 0,
+                  "racing.opponents",
+                  "The first stage needs at least 1 opponent.")?;
+    entries.check(racing_params.initial_rounds > 0,
+                  "racing.rounds",
+                  "The first stage needs at least 1 round.")?;
+    entries.check(racing_params.initial_max_steps > 0,
+                  "racing.max_steps",
+                  "Rounds in the first stage must last at least 1 step.")?;
+    Ok(Some(racing_params))
+}
+
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|item| !item.is_empty())
+}
+
+/// The `key = value` lines of a configuration file, together with their line numbers.
+struct Entries {
+    values: HashMap<String, (usize, String)>,
+}
+
+impl Entries {
+    fn parse(text: &str) -> Result<Entries, ConfigError> {
+        let mut values = HashMap::new();
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.find('=') {
+                Some(position) => (line[..position].trim(), line[position + 1..].trim()),
+                None => {
+                    return Err(ConfigError::new(Some(number),
+                                                "Expected a line of the form 'key = value'."
+                                                    .to_string()))
+                }
+            };
+            if !KEYS.contains(&key) {
+                return Err(ConfigError::new(Some(number), format!("Unknown key '{}'.", key)));
+            }
+            if let Some(&(first, _)) = values.get(key) {
+                return Err(ConfigError::new(Some(number),
+                                            format!("Key '{}' was already set on line {}.",
+                                                    key,
+                                                    first)));
+            }
+            values.insert(key.to_string(), (number, value.to_string()));
+        }
+        Ok(Entries { values })
+    }
+
+    fn get_raw(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|(_, value)| value.as_str())
+    }
+
+    fn get_optional<T: FromStr>(&self, key: &str) -> Result<Option<T>, ConfigError> {
+        match self.values.get(key) {
+            Some(&(line, ref value)) => {
+                value.parse().map(Some).map_err(|_| {
+                    ConfigError::new(Some(line),
+                                     format!("Invalid value '{}' for key '{}'.", value, key))
+                })
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn get<T: FromStr>(&self, key: &str, default: T) -> Result<T, ConfigError> {
+        self.get_optional(key).map(|value| value.unwrap_or(default))
+    }
+
+    /// Like `get_optional`, but also fails if the number is infinite or NaN.
+    fn get_optional_number(&self, key: &str) -> Result<Option<f64>, ConfigError> {
+        let number: Option<f64> = self.get_optional(key)?;
+        self.check(number.is_none_or(f64::is_finite),
+                   key,
+                   &format!("The value of '{}' must be a finite number.", key))?;
+        Ok(number)
+    }
+
+    fn get_number(&self, key: &str, default: f64) -> Result<f64, ConfigError> {
+        self.get_optional_number(key).map(|value| value.unwrap_or(default))
+    }
+
+    /// Returns an error about the given key, pointing at its line if it is present.
+    fn error(&self, key: &str, message: String) -> ConfigError {
+        ConfigError::new(self.values.get(key).map(|&(line, _)| line), message)
+    }
+
+    fn check(&self, condition: bool, key: &str, message: &str) -> Result<(), ConfigError> {
+        if condition {
+            Ok(())
+        } else {
+            Err(self.error(key, message.to_string()))
+        }
+    }
+
+    /// Fails if any setting of a section that hasn't been enabled is present.
+    fn reject_section(&self, section: &str) -> Result<(), ConfigError> {
+        let prefix = format!("{}.", section);
+        let mut keys: Vec<(&String, usize)> = self.values
+            .iter()
+            .filter(|&(key, _)| key.starts_with(&prefix))
+            .map(|(key, &(line, _))| (key, line))
+            .collect();
+        keys.sort_by_key(|&(_, line)| line);
+        match keys.first() {
+            Some(&(key, line)) => {
+                Err(ConfigError::new(Some(line),
+                                     format!("'{}' has no effect unless '{} = true'.",
+                                             key,
+                                             section)))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<RunConfig, ConfigError> {
+        RunConfig::parse(text, Path::new("experiments"))
+    }
+
+    fn error_line(text: &str) -> Option<usize> {
+        parse(text).unwrap_err().line
+    }
+
+    #[test]
+    fn parse_minimalConfig_usesDefaults() {
+        let config = parse("opponents = a.bf\nstop.generations = 10\n").unwrap();
+        assert_eq!(config.opponents, vec![PathBuf::from("experiments/a.bf")]);
+        assert_eq!(config.params.population_size, 50);
+        assert_eq!(config.params.rounds.len(), 42);
+        assert_eq!(config.params.selection, Selection::Tournament);
+        assert!(config.params.novelty.is_none());
+        assert_eq!(config.stop.generations, Some(10));
+        assert_eq!(config.checkpoint, None);
+    }
+
+    #[test]
+    fn parse_fullConfig_setsEveryField() {
+        let config = parse("# comment\n\
+                            opponents = a.bf, b.bf\n\
+                            population_size = 20\n\
+                            selection = pareto\n\
+                            objectives = total_points, cycles_to_win\n\
+                            operators.crossover = 0\n\
+                            rounds.min_tape_length = 10\n\
+                            rounds.max_tape_length = 12\n\
+                            rounds.polarity = reversed\n\
+                            rounds.max_steps = 500\n\
+                            rounds.sample = 2\n\
+                            local_search = true\n\
+                            local_search.temperature = 1.5\n\
+                            seed = 7\n\
+                            threads = 2\n\
+                            checkpoint = run.checkpoint\n\
+                            stop.seconds = 60\n")
+            .unwrap();
+        assert_eq!(config.opponents.len(), 2);
+        assert_eq!(config.params.population_size, 20);
+        assert_eq!(config.params.selection,
+                   Selection::Pareto(vec![Objective::TotalPoints, Objective::CyclesToWin]));
+        assert_eq!(config.params.operator_rates.crossover, 0.0);
+        assert_eq!(config.params.rounds.len(), 3);
+        assert!(config.params.rounds.iter().all(|round| round.invert_polarity));
+        assert!(config.params.rounds.iter().all(|round| round.max_steps == 500));
+        assert_eq!(config.params.round_sample, Some(2));
+        let local_search = config.params.local_search.unwrap();
+        assert_eq!(local_search.search.annealing.unwrap().initial_temperature, 1.5);
+        assert_eq!(config.seed, 7);
+        assert_eq!(config.params.threads, 2);
+        assert_eq!(config.checkpoint, Some(PathBuf::from("experiments/run.checkpoint")));
+        assert_eq!(config.stop.duration, Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn parse_unknownKey_reportsLine() {
+        assert_eq!(error_line("opponents = a.bf\npopulation = 3\n"), Some(2));
+    }
+
+    #[test]
+    fn parse_duplicateKey_reportsLine() {
+        assert_eq!(error_line("seed = 1\nopponents = a.bf\nseed = 2\n"), Some(3));
+    }
+
+    #[test]
+    fn parse_invalidNumber_reportsLine() {
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 10\nthreads = many\n"),
+                   Some(3));
+    }
+
+    #[test]
+    fn parse_nonFiniteNumber_reportsLine() {
+        for value in &["NaN", "inf", "-inf"] {
+            let text = format!("opponents = a.bf\nstop.generations = 1\noperators.crossover = {}\n",
+                               value);
+            assert_eq!(error_line(&text), Some(3));
+            let text = format!("opponents = a.bf\nstop.fitness = {}\n", value);
+            assert_eq!(error_line(&text), Some(2));
+        }
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 1\nnovelty = true\n\
+                               novelty.archive_threshold = NaN\n"),
+                   Some(4));
+    }
+
+    #[test]
+    fn parse_valuesOutOfRange_reportLine() {
+        let cases = [("local_search", "local_search.rate = 7"),
+                     ("local_search", "local_search.rate = -0.5"),
+                     ("local_search", "local_search.temperature = 0"),
+                     ("speciation", "speciation.compatibility_threshold = -1"),
+                     ("speciation", "speciation.interspecies_crossover_rate = 2"),
+                     ("racing", "racing.opponents = 0"),
+                     ("racing", "racing.rounds = 0"),
+                     ("racing", "racing.max_steps = 0")];
+        for &(section, setting) in &cases {
+            let text = format!("opponents = a.bf\nstop.generations = 1\n{} = true\n{}\n",
+                               section,
+                               setting);
+            assert_eq!(error_line(&text), Some(4), "{}", setting);
+        }
+    }
+
+    #[test]
+    fn parse_elitismExceedsPopulation_isRejected() {
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 1\npopulation_size = 2\n\
+                               elitism = 3\n"),
+                   Some(4));
+    }
+
+    #[test]
+    fn parse_gauntlet_replacesOrAddsToOpponents() {
+        let config = parse("gauntlet = rushers.gauntlet\nstop.generations = 1\n").unwrap();
+        assert_eq!(config.gauntlet, Some(PathBuf::from("experiments/rushers.gauntlet")));
+        assert!(config.opponents.is_empty());
+        let config = parse("opponents = a.bf\ngauntlet = g.gauntlet\nstop.generations = 1\n")
+            .unwrap();
+        assert_eq!(config.opponents.len(), 1);
+        assert!(parse("stop.generations = 1\n").is_err());
+    }
+
+    #[test]
+    fn parse_noStopCriterion_isRejected() {
+        assert!(parse("opponents = a.bf\n").is_err());
+    }
+
+    #[test]
+    fn parse_paretoWithoutObjectives_isRejected() {
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 1\nselection = pareto\n"),
+                   Some(3));
+    }
+
+    #[test]
+    fn parse_novelty_setsNoveltyParams() {
+        let config = parse("opponents = a.bf\nstop.generations = 1\nnovelty = true\n\
+                            novelty.k = 4\n")
+            .unwrap();
+        assert_eq!(config.params.novelty.unwrap().k, 4);
+    }
+
+    #[test]
+    fn parse_paretoWithSpeciation_isRejected() {
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 1\nselection = pareto\n\
+                               objectives = total_points\nspeciation = true\n"),
+                   Some(5));
+    }
+
+    #[test]
+    fn parse_sectionSettingWithoutSection_isRejected() {
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 1\nspeciation.\
+                               compatibility_threshold = 0.3\n"),
+                   Some(3));
+    }
+
+    #[test]
+    fn parse_youthBonusBelowOne_isRejected() {
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 1\nspeciation = true\n\
+                               speciation.youth_bonus = 0.5\n"),
+                   Some(4));
+    }
+
+    #[test]
+    fn parse_logAndLineage_formatFollowsExtension() {
+        let config = parse("opponents = a.bf\nstop.generations = 1\nlog = run.jsonl\n\
+                            lineage = champion.nwk\n")
+            .unwrap();
+        assert_eq!(config.log,
+                   Some((PathBuf::from("experiments/run.jsonl"), LogFormat::JsonLines)));
+        assert_eq!(config.lineage,
+                   Some((PathBuf::from("experiments/champion.nwk"), LineageFormat::Newick)));
+    }
+
+    #[test]
+    fn parse_logWithUnknownExtension_isRejected() {
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 1\nlog = run.txt\n"),
+                   Some(3));
+    }
+
+    #[test]
+    fn parse_bloat_setsLimits() {
+        let config = parse("opponents = a.bf\nstop.generations = 1\nbloat.parsimony = 0.01\n\
+                            bloat.max_expanded_length = 500\nbloat.prune = true\n")
+            .unwrap();
+        assert_eq!(config.params.bloat,
+                   BloatParams {
+                       parsimony: 0.01,
+                       max_length: None,
+                       max_expanded_length: Some(500),
+                       prune: true,
+                   });
+    }
+
+    #[test]
+    fn parse_maxLengthBelowInitialLength_isRejected() {
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 1\n\
+                               initial_program_length = 30\nbloat.max_length = 20\n"),
+                   Some(4));
+    }
+
+    #[test]
+    fn parse_racing_setsStages() {
+        let config = parse("opponents = a.bf\nstop.generations = 1\nracing = true\n\
+                            racing.stages = 4\nracing.keep_fraction = 0.25\n")
+            .unwrap();
+        let racing_params = config.params.racing.unwrap();
+        assert_eq!(racing_params.nr_stages, 4);
+        assert_eq!(racing_params.keep_fraction, 0.25);
+        assert_eq!(racing_params.initial_rounds, RacingParams::default().initial_rounds);
+    }
+
+    #[test]
+    fn parse_racingWithParetoSelection_isRejected() {
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 1\nselection = pareto\n\
+                               objectives = total_points\nracing = true\n"),
+                   Some(5));
+    }
+
+    #[test]
+    fn parse_seedBots_resolvesDirectory() {
+        let config = parse("opponents = a.bf\nstop.generations = 1\nseed_bots = ../bots\n\
+                            seed_bots.fill = grammar\n")
+            .unwrap();
+        assert_eq!(config.seed_bots,
+                   Some((PathBuf::from("experiments/../bots"), FillStrategy::Grammar)));
+    }
+
+    #[test]
+    fn parse_fillWithoutSeedBots_isRejected() {
+        assert_eq!(error_line("opponents = a.bf\nstop.generations = 1\nseed_bots.fill = classic\n"),
+                   Some(3));
+    }
+
+    #[test]
+    fn parse_allOperatorRatesZero_isRejected() {
+        let text = KEYS.iter()
+            .filter(|key| key.starts_with("operators."))
+            .map(|key| format!("{} = 0\n", key))
+            .collect::<String>() + "opponents = a.bf\nstop.generations = 1\n";
+        assert!(parse(&text).is_err());
+    }
+}
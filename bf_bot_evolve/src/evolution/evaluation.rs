@@ -0,0 +1,121 @@
+use bf_bot_core::bf::Bot;
+use bf_bot_core::simul_game::{self, game_record::{GameRecord, RoundRecord}};
+use bf_bot_core::simul_round::{self, RoundParams};
+
+/// Plays the bot against every opponent in a game consisting of the given rounds, and returns the
+/// sum of the points it scored.
+pub fn evaluate(bot: &Bot, opponents: &[Bot], rounds: &[RoundParams]) -> f64 {
+    evaluate_weighted(bot, opponents, &[], rounds)
+}
+
+/// Like `evaluate`, but multiplies the points scored against each opponent by its weight. See
+/// `opponent_weight`.
+pub fn evaluate_weighted(bot: &Bot,
+                         opponents: &[Bot],
+                         weights: &[f64],
+                         rounds: &[RoundParams])
+                         -> f64 {
+    opponents.iter()
+        .enumerate()
+        .map(|(index, opponent)| {
+            let game_result = simul_game::run(bot, opponent, rounds.iter().cloned());
+            opponent_weight(weights, index) * game_result.bot_a_points as f64
+        })
+        .sum()
+}
+
+/// Returns the weight of the opponent at the given index. Opponents beyond the end of the
+/// weights, and so all opponents if there are no weights, count once.
+pub fn opponent_weight(weights: &[f64], opponent: usize) -> f64 {
+    weights.get(opponent).cloned().unwrap_or(1.0)
+}
+
+/// Plays the bot against every opponent in a game consisting of the given rounds, and returns a
+/// record of each game, in the order of the opponents.
+pub fn evaluate_recorded(bot: &Bot, opponents: &[Bot], rounds: &[RoundParams]) -> Vec<GameRecord> {
+    opponents.iter()
+        .map(|opponent| simul_game::run_recorded(bot, opponent, rounds.iter().cloned()))
+        .collect()
+}
+
+/// Like `evaluate_recorded`, but also counts how often each instruction of the bot was executed,
+/// summed over all rounds against all opponents.
+pub fn evaluate_profiled(bot: &Bot,
+                         opponents: &[Bot],
+                         rounds: &[RoundParams])
+                         -> (Vec<GameRecord>, Vec<u32>) {
+    let mut counts = vec![0; bot.get_program().len()];
+    let records = opponents.iter()
+        .map(|opponent| {
+            GameRecord {
+                rounds: rounds.iter()
+                    .map(|round_params| {
+                        let profile = simul_round::profile(bot, opponent, round_params);
+                        for (count, added) in counts.iter_mut().zip(profile.start_bot_counts) {
+                            *count += added;
+                        }
+                        RoundRecord {
+                            round_params: round_params.clone(),
+                            result: profile.result,
+                            steps: profile.steps,
+                        }
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    (records, counts)
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use genome::Genome;
+    use bf_bot_core::simul_game::AllRounds;
+
+    #[test]
+    fn evaluate_suicidalBotAgainstTwoIdlers_losesEveryRound() {
+        let bot = Genome::from_source("<").unwrap().to_bot();
+        let idler = Genome::from_source(".").unwrap().to_bot();
+        let rounds: Vec<RoundParams> = AllRounds::new().collect();
+        assert_eq!(evaluate(&bot, &[idler.clone(), idler], &rounds), -84.0);
+    }
+
+    #[test]
+    fn evaluateWeighted_multipliesPointsPerOpponent() {
+        let bot = Genome::from_source("<").unwrap().to_bot();
+        let idler = Genome::from_source(".").unwrap().to_bot();
+        let rounds: Vec<RoundParams> = AllRounds::new().collect();
+        assert_eq!(evaluate_weighted(&bot, &[idler.clone(), idler], &[0.5], &rounds),
+                   -0.5 * 42.0 - 42.0);
+    }
+
+    #[test]
+    fn evaluateRecorded_recordsEveryRoundAgainstEveryOpponent() {
+        let bot = Genome::from_source(">").unwrap().to_bot();
+        let idler = Genome::from_source(".").unwrap().to_bot();
+        let rounds: Vec<RoundParams> = AllRounds::new().collect();
+        let records = evaluate_recorded(&bot, &[idler.clone(), idler], &rounds);
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|record| record.rounds.len() == 42));
+    }
+
+    #[test]
+    fn evaluateProfiled_recordsLikeEvaluateRecorded() {
+        let bot = Genome::from_source(">[-]+").unwrap().to_bot();
+        let idler = Genome::from_source(".").unwrap().to_bot();
+        let rounds: Vec<RoundParams> = AllRounds::new().collect();
+        let (records, counts) = evaluate_profiled(&bot, std::slice::from_ref(&idler), &rounds);
+        assert_eq!(records, evaluate_recorded(&bot, &[idler], &rounds));
+        assert_eq!(counts.len(), 5);
+        assert_eq!(counts[0], 42);
+    }
+
+    #[test]
+    fn evaluate_noOpponents_scoresZero() {
+        let bot = Genome::from_source(">").unwrap().to_bot();
+        let rounds: Vec<RoundParams> = AllRounds::new().collect();
+        assert_eq!(evaluate(&bot, &[], &rounds), 0.0);
+    }
+}
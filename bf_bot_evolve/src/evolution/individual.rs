@@ -0,0 +1,122 @@
+use bf_bot_core::bf::Bot;
+use bf_bot_core::simul_game::game_record::GameRecord;
+use genome::Genome;
+use novelty::Behaviour;
+use operators::Operator;
+
+/// A member of the population: a genome together with everything that has been learned about it.
+#[derive(Debug, Clone)]
+pub struct Individual {
+    /// Identifies the individual within its evolution run.
+    pub(crate) id: usize,
+    /// The generation the individual was created in.
+    pub(crate) generation: usize,
+    /// The ids of the parents, if any.
+    pub(crate) parents: Vec<usize>,
+    /// The operator that created the individual.
+    pub(crate) operator: Operator,
+    /// The highest fitness among the parents when the individual was created.
+    pub(crate) parent_fitness: Option<f64>,
+    pub(crate) genome: Genome,
+    pub(crate) bot: Bot,
+    /// None until the individual has been evaluated.
+    pub(crate) fitness: Option<f64>,
+    /// The games the individual played against each opponent during evaluation.
+    pub(crate) records: Vec<GameRecord>,
+    pub(crate) behaviour: Option<Behaviour>,
+    pub(crate) novelty: f64,
+    /// The id of the species the individual belongs to, if speciation is enabled.
+    pub(crate) species: Option<usize>,
+    /// The value that selection is based on. Higher is better.
+    pub(crate) score: f64,
+    /// The values of the objectives, if Pareto selection is used. Larger is better.
+    pub(crate) objectives: Vec<f64>,
+    /// The Pareto front the individual is in, starting at 0.
+    pub(crate) rank: usize,
+    pub(crate) crowding: f64,
+}
+
+impl Individual {
+    pub fn new(genome: Genome) -> Individual {
+        let bot = genome.to_bot();
+        Individual {
+            id: 0,
+            generation: 0,
+            parents: vec![],
+            operator: Operator::Random,
+            parent_fitness: None,
+            genome,
+            bot,
+            fitness: None,
+            records: vec![],
+            behaviour: None,
+            novelty: 0.0,
+            species: None,
+            score: 0.0,
+            objectives: vec![],
+            rank: 0,
+            crowding: 0.0,
+        }
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn get_parents(&self) -> &Vec<usize> {
+        &self.parents
+    }
+
+    pub fn get_operator(&self) -> Operator {
+        self.operator
+    }
+
+    pub fn get_genome(&self) -> &Genome {
+        &self.genome
+    }
+
+    pub fn get_bot(&self) -> &Bot {
+        &self.bot
+    }
+
+    /// Returns the fitness of the individual. Panics if it has not been evaluated yet.
+    pub fn get_fitness(&self) -> f64 {
+        self.fitness.expect("Individual has not been evaluated.")
+    }
+
+    pub fn get_records(&self) -> &Vec<GameRecord> {
+        &self.records
+    }
+
+    pub fn get_behaviour(&self) -> Option<&Behaviour> {
+        self.behaviour.as_ref()
+    }
+
+    pub fn get_novelty(&self) -> f64 {
+        self.novelty
+    }
+
+    pub fn get_species(&self) -> Option<usize> {
+        self.species
+    }
+
+    pub fn get_score(&self) -> f64 {
+        self.score
+    }
+
+    pub fn get_objectives(&self) -> &Vec<f64> {
+        &self.objectives
+    }
+
+    pub fn get_rank(&self) -> usize {
+        self.rank
+    }
+
+    pub fn get_crowding(&self) -> f64 {
+        self.crowding
+    }
+}
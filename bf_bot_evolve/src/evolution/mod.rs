@@ -0,0 +1,18 @@
+//! The evolution loop.
+//! A population of bots is evaluated against a fixed set of opponents. Each generation, the best
+//! bots survive unchanged and the rest of the population is replaced by offspring of bots that
+//! win tournament selection. Alternatively, Pareto selection keeps the best trade-offs between
+//! several objectives.
+
+pub use self::params::{EvolutionParams, OperatorRates, Selection};
+mod params;
+
+pub use self::individual::Individual;
+mod individual;
+
+pub use self::evaluation::{evaluate, evaluate_profiled, evaluate_recorded, evaluate_weighted,
+                           opponent_weight};
+mod evaluation;
+
+pub use self::run::Evolution;
+mod run;
@@ -0,0 +1,125 @@
+use bf_bot_core::simul_game::AllRounds;
+use bloat::BloatParams;
+use bf_bot_core::simul_round::RoundParams;
+use local_search::MemeticParams;
+use novelty::NoveltyParams;
+use pareto::Objective;
+use racing::RacingParams;
+use speciation::SpeciationParams;
+
+/// Settings of an evolution run.
+#[derive(Debug, Clone)]
+pub struct EvolutionParams {
+    /// Number of individuals in each generation.
+    pub population_size: usize,
+    /// Number of best individuals that are copied into the next generation unchanged.
+    pub elitism: usize,
+    /// Number of individuals that compete in each tournament selection.
+    pub tournament_size: usize,
+    /// Number of instructions in each program of the initial, random population.
+    pub initial_program_length: usize,
+    /// How parents and survivors are selected.
+    pub selection: Selection,
+    /// How often each operator is used to create offspring.
+    pub operator_rates: OperatorRates,
+    /// The rounds that each individual plays against every opponent to determine its fitness.
+    pub rounds: Vec<RoundParams>,
+    /// If set, each generation is evaluated on a random sample of this many of the rounds
+    /// instead of all of them. Every individual is then re-evaluated each generation, so that
+    /// fitness values within a generation stay comparable.
+    pub round_sample: Option<usize>,
+    /// Number of threads that evaluate individuals in parallel.
+    pub threads: usize,
+    /// If set, the selection score also takes the novelty of an individual's behaviour into
+    /// account. Not used with Pareto selection.
+    pub novelty: Option<NoveltyParams>,
+    /// If set, the population is divided into species and individuals share their selection
+    /// score with the other members of their species. Not used with Pareto selection.
+    pub speciation: Option<SpeciationParams>,
+    /// If set, some offspring are refined by a local search before they are evaluated.
+    pub local_search: Option<MemeticParams>,
+    /// Limits on the size of programs.
+    pub bloat: BloatParams,
+    /// If set, the population is raced through evaluation stages of increasing cost, so that
+    /// only the most promising individuals play full games against every opponent. This changes
+    /// the scale of the fitness; see `RacingParams`.
+    pub racing: Option<RacingParams>,
+    /// The weight of the points scored against each opponent, in the order of the opponents. If
+    /// empty, every opponent counts once. The weights also apply to racing, local search and the
+    /// Pareto objectives that count points.
+    pub opponent_weights: Vec<f64>,
+}
+
+impl Default for EvolutionParams {
+    fn default() -> EvolutionParams {
+        EvolutionParams {
+            population_size: 50,
+            elitism: 2,
+            tournament_size: 3,
+            initial_program_length: 20,
+            selection: Selection::Tournament,
+            operator_rates: OperatorRates::default(),
+            rounds: AllRounds::new().collect(),
+            round_sample: None,
+            threads: 1,
+            novelty: None,
+            speciation: None,
+            local_search: None,
+            bloat: BloatParams::default(),
+            racing: None,
+            opponent_weights: vec![],
+        }
+    }
+}
+
+/// The ways in which parents and survivors are selected.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Selection {
+    /// Parents win a tournament on their selection score, which is the fitness, possibly
+    /// adjusted by novelty and speciation. The best individuals survive unchanged.
+    Tournament,
+    /// NSGA-II selection on the given objectives. Parents and offspring compete for survival on
+    /// their Pareto front and crowding distance. Novelty and speciation don't affect this kind of
+    /// selection.
+    Pareto(Vec<Objective>),
+}
+
+/// Relative weights with which the variation operators are chosen to create an offspring. Only
+/// the ratios between the weights matter.
+#[derive(Debug, PartialEq, Clone)]
+pub struct OperatorRates {
+    pub crossover: f64,
+    pub point_mutation: f64,
+    pub insertion: f64,
+    pub deletion: f64,
+    pub wrap_loop: f64,
+    pub unwrap_loop: f64,
+    pub repeat_count: f64,
+}
+
+impl OperatorRates {
+    /// Returns the weights in the order of `operators::VARIATION_OPERATORS`.
+    pub fn weights(&self) -> [f64; 7] {
+        [self.crossover,
+         self.point_mutation,
+         self.insertion,
+         self.deletion,
+         self.wrap_loop,
+         self.unwrap_loop,
+         self.repeat_count]
+    }
+}
+
+impl Default for OperatorRates {
+    fn default() -> OperatorRates {
+        OperatorRates {
+            crossover: 0.3,
+            point_mutation: 0.25,
+            insertion: 0.15,
+            deletion: 0.15,
+            wrap_loop: 0.05,
+            unwrap_loop: 0.03,
+            repeat_count: 0.07,
+        }
+    }
+}
@@ -0,0 +1,1126 @@
+use std::cmp::Ordering;
+use std::thread;
+
+use bf_bot_core::bf::Bot;
+use bf_bot_core::simul_game::game_record::GameRecord;
+use bf_bot_core::simul_round::RoundParams;
+use bloat::{self, BloatStats};
+use checkpoint::Checkpoint;
+use evolution::{evaluate_profiled, evaluate_recorded, opponent_weight, EvolutionParams, Individual,
+                Selection};
+use genome::Genome;
+use lineage::{Lineage, LineageEntry};
+use local_search;
+use novelty::{Behaviour, NoveltyArchive};
+use operators::{self, VARIATION_OPERATORS};
+use pareto;
+use racing::{self, RacingParams};
+use rng::Rng;
+use speciation::{self, Species};
+
+/// Number of times an offspring that exceeds the size limits is bred again, before a copy of its
+/// parent is used instead.
+const MAX_BREEDING_ATTEMPTS: usize = 10;
+
+/// The outcome of evaluating a single individual.
+struct Evaluation {
+    fitness: f64,
+    records: Vec<GameRecord>,
+    /// How often each instruction was executed, if the individual is to be pruned.
+    counts: Option<Vec<u32>>,
+}
+
+/// A running evolution: the current population and everything needed to breed the next one.
+#[derive(Debug)]
+pub struct Evolution {
+    params: EvolutionParams,
+    opponents: Vec<Bot>,
+    rng: Rng,
+    generation: usize,
+    /// The rounds the current generation is evaluated on.
+    rounds: Vec<RoundParams>,
+    population: Vec<Individual>,
+    archive: NoveltyArchive,
+    species: Vec<Species>,
+    next_species_id: usize,
+    lineage: Lineage,
+    next_individual_id: usize,
+    /// The bloat removed in the current generation.
+    bloat_stats: BloatStats,
+}
+
+impl Evolution {
+    /// Starts an evolution from a population of random programs, and evaluates it.
+    pub fn new(params: EvolutionParams, opponents: Vec<Bot>, seed: u64) -> Evolution {
+        let mut rng = Rng::new(seed);
+        let genomes = (0..params.population_size)
+            .map(|_| operators::random_genome(params.initial_program_length, &mut rng))
+            .collect();
+        Evolution::from_genomes(params, opponents, genomes, rng)
+    }
+
+    /// Starts an evolution from the given genomes, and evaluates them.
+    pub fn from_genomes(params: EvolutionParams,
+                        opponents: Vec<Bot>,
+                        genomes: Vec<Genome>,
+                        rng: Rng)
+                        -> Evolution {
+        Evolution::start(params, opponents, genomes, rng, 0)
+    }
+
+    /// Continues an evolution from a checkpoint. The novelty archive, the species and the lineage
+    /// are not part of a checkpoint, so they start out empty.
+    pub fn resume(params: EvolutionParams,
+                  opponents: Vec<Bot>,
+                  checkpoint: Checkpoint)
+                  -> Evolution {
+        let rng = Rng::from_state(checkpoint.rng_state);
+        Evolution::start(params, opponents, checkpoint.genomes, rng, checkpoint.generation)
+    }
+
+    fn start(params: EvolutionParams,
+             opponents: Vec<Bot>,
+             genomes: Vec<Genome>,
+             rng: Rng,
+             generation: usize)
+             -> Evolution {
+        let population: Vec<Individual> = genomes.into_iter()
+            .enumerate()
+            .map(|(id, genome)| {
+                let mut individual = Individual::new(genome);
+                individual.id = id;
+                individual.generation = generation;
+                individual
+            })
+            .collect();
+        let mut evolution = Evolution {
+            params,
+            opponents,
+            rng,
+            generation,
+            rounds: vec![],
+            next_individual_id: population.len(),
+            population,
+            archive: NoveltyArchive::new(),
+            species: vec![],
+            next_species_id: 0,
+            lineage: Lineage::new(),
+            bloat_stats: BloatStats::default(),
+        };
+        evolution.sample_rounds();
+        evolution.evaluate_population();
+        evolution
+    }
+
+    /// Captures the state needed to continue this evolution later.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            generation: self.generation,
+            rng_state: self.rng.get_state(),
+            genomes: self.population.iter().map(|individual| individual.genome.clone()).collect(),
+        }
+    }
+
+    pub fn get_params(&self) -> &EvolutionParams {
+        &self.params
+    }
+
+    pub fn get_generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Returns the current population, ordered from the best to the worst according to the
+    /// selection scheme.
+    pub fn get_population(&self) -> &Vec<Individual> {
+        &self.population
+    }
+
+    pub fn get_archive(&self) -> &NoveltyArchive {
+        &self.archive
+    }
+
+    /// Returns the species of the current population. This is empty if speciation is disabled.
+    pub fn get_species(&self) -> &Vec<Species> {
+        &self.species
+    }
+
+    /// Returns every individual created during this run, with its parents.
+    pub fn get_lineage(&self) -> &Lineage {
+        &self.lineage
+    }
+
+    /// Returns how much bloat was removed while creating and evaluating the current generation.
+    pub fn get_bloat_stats(&self) -> &BloatStats {
+        &self.bloat_stats
+    }
+
+    /// Returns the individual with the highest fitness. Note that when selecting on novelty, this
+    /// need not be the individual with the highest selection score.
+    pub fn best(&self) -> &Individual {
+        self.population
+            .iter()
+            .max_by(|a, b| compare(a.get_fitness(), b.get_fitness()))
+            .expect("The population is empty.")
+    }
+
+    /// Returns the individuals on the first Pareto front. Without Pareto selection, this is just
+    /// the best individual.
+    pub fn pareto_front(&self) -> Vec<&Individual> {
+        match self.params.selection {
+            Selection::Pareto(_) => {
+                self.population.iter().filter(|individual| individual.rank == 0).collect()
+            }
+            Selection::Tournament => vec![self.best()],
+        }
+    }
+
+    /// Replaces the population by the next generation and evaluates it.
+    pub fn next_generation(&mut self) {
+        // Tournament selection replaces everyone but the elites by offspring. Pareto selection
+        // lets the whole population compete with a full generation of offspring.
+        let (nr_survivors, nr_offspring) = match self.params.selection {
+            Selection::Tournament => {
+                let nr_elites = self.params.elitism.min(self.population.len());
+                (nr_elites, self.params.population_size.saturating_sub(nr_elites))
+            }
+            Selection::Pareto(_) => (self.population.len(), self.params.population_size),
+        };
+        self.generation += 1;
+        self.bloat_stats = BloatStats::default();
+        let offspring: Vec<Individual> = (0..nr_offspring).map(|_| self.breed()).collect();
+        if self.uses_speciation() && nr_survivors > 0 {
+            self.keep_champion(nr_survivors);
+        }
+        self.population.truncate(nr_survivors);
+        self.population.extend(offspring);
+        self.sample_rounds();
+        self.refine_offspring();
+        self.evaluate_population();
+        self.population.truncate(self.params.population_size);
+    }
+
+    /// Makes sure that the individual with the highest fitness is among the given number of
+    /// survivors. Elites are chosen on their shared score, which could otherwise lose the
+    /// champion of a large species.
+    fn keep_champion(&mut self, nr_survivors: usize) {
+        let champion = (0..self.population.len())
+            .max_by(|&a, &b| {
+                compare(self.population[a].get_fitness(), self.population[b].get_fitness())
+            })
+            .expect("The population is empty.");
+        if champion >= nr_survivors {
+            self.population.swap(nr_survivors - 1, champion);
+        }
+    }
+
+    /// Creates an offspring. Offspring that exceed the size limits are bred again, and after too
+    /// many attempts an unchanged copy of the parent is used instead.
+    fn breed(&mut self) -> Individual {
+        let weights = self.params.operator_rates.weights();
+        let mut attempt = 1;
+        let (operator, parents, genome) = loop {
+            let operator = match self.rng.weighted_index(&weights) {
+                Some(index) => VARIATION_OPERATORS[index],
+                None => operators::Operator::PointMutation,
+            };
+            let parent = self.tournament_select();
+            let other_parent = self.select_mate(parent);
+            let genome = operator.apply(&self.population[parent].genome,
+                                        &self.population[other_parent].genome,
+                                        &mut self.rng);
+            if self.params.bloat.allows(&genome) {
+                let parents = if operator.is_binary() {
+                    vec![parent, other_parent]
+                } else {
+                    vec![parent]
+                };
+                break (operator, parents, genome);
+            }
+            self.bloat_stats.rejected_offspring += 1;
+            if attempt == MAX_BREEDING_ATTEMPTS {
+                break (operator, vec![parent], self.population[parent].genome.clone());
+            }
+            attempt += 1;
+        };
+        let mut individual = Individual::new(genome);
+        individual.id = self.next_individual_id;
+        individual.generation = self.generation;
+        individual.parent_fitness = parents.iter()
+            .filter_map(|&parent| self.population[parent].fitness)
+            .max_by(|a, b| compare(*a, *b));
+        individual.parents = parents.iter().map(|&parent| self.population[parent].id).collect();
+        individual.operator = operator;
+        self.next_individual_id += 1;
+        individual
+    }
+
+    /// Returns the index of the individual with the highest score out of a random sample.
+    fn tournament_select(&mut self) -> usize {
+        let everyone: Vec<usize> = (0..self.population.len()).collect();
+        self.tournament_select_from(&everyone)
+    }
+
+    /// Returns the index of the individual with the highest score out of a random sample of the
+    /// given candidates.
+    fn tournament_select_from(&mut self, candidates: &[usize]) -> usize {
+        let mut winner = *self.rng.choose(candidates).unwrap();
+        for _ in 1..self.params.tournament_size {
+            let contender = *self.rng.choose(candidates).unwrap();
+            if self.compare_individuals(contender, winner) == Ordering::Greater {
+                winner = contender;
+            }
+        }
+        winner
+    }
+
+    /// Returns `Ordering::Greater` if the first individual is better than the second.
+    fn compare_individuals(&self, a: usize, b: usize) -> Ordering {
+        let (a, b) = (&self.population[a], &self.population[b]);
+        match self.params.selection {
+            Selection::Tournament => compare(a.score, b.score),
+            Selection::Pareto(_) => pareto::crowded_compare(a.rank, a.crowding, b.rank, b.crowding),
+        }
+    }
+
+    /// Returns whether selection is based on the selection score. Novelty and speciation only
+    /// adjust that score, so they are skipped when it isn't used.
+    fn selects_on_score(&self) -> bool {
+        self.params.selection == Selection::Tournament
+    }
+
+    fn uses_speciation(&self) -> bool {
+        self.params.speciation.is_some() && self.selects_on_score()
+    }
+
+    /// Selects the second parent for crossover. With speciation, this is usually a member of the
+    /// same species as the first parent.
+    fn select_mate(&mut self, parent: usize) -> usize {
+        let interspecies_rate = match self.params.speciation {
+            Some(ref speciation_params) if self.uses_speciation() => {
+                speciation_params.interspecies_crossover_rate
+            }
+            _ => return self.tournament_select(),
+        };
+        if self.rng.chance(interspecies_rate) {
+            return self.tournament_select();
+        }
+        let members = self.species
+            .iter()
+            .find(|species| Some(species.get_id()) == self.population[parent].species)
+            .map(|species| species.get_members().clone())
+            .unwrap_or_else(|| vec![parent]);
+        self.tournament_select_from(&members)
+    }
+
+    /// Replaces some of the offspring of this generation by the result of a local search around
+    /// them. The refined genome is passed on to the offspring's own offspring.
+    fn refine_offspring(&mut self) {
+        let memetic_params = match self.params.local_search {
+            Some(ref memetic_params) => memetic_params.clone(),
+            None => return,
+        };
+        for index in 0..self.population.len() {
+            if self.population[index].generation != self.generation ||
+               !self.rng.chance(memetic_params.rate) {
+                continue;
+            }
+            let refinement = local_search::refine(&self.population[index].genome,
+                                                  &self.opponents,
+                                                  &self.params.opponent_weights,
+                                                  &self.rounds,
+                                                  &memetic_params.search,
+                                                  &mut self.rng);
+            if !self.params.bloat.allows(&refinement.genome) {
+                self.bloat_stats.rejected_offspring += 1;
+                continue;
+            }
+            let individual = &mut self.population[index];
+            individual.bot = refinement.genome.to_bot();
+            individual.genome = refinement.genome;
+        }
+    }
+
+    /// Picks the rounds this generation is evaluated on. When sampling, the fitness of every
+    /// individual is discarded, because it was determined on other rounds.
+    fn sample_rounds(&mut self) {
+        match self.params.round_sample {
+            Some(sample_size) if sample_size < self.params.rounds.len() => {
+                let mut remaining = self.params.rounds.clone();
+                self.rounds = (0..sample_size)
+                    .map(|_| {
+                        let index = self.rng.below(remaining.len());
+                        remaining.swap_remove(index)
+                    })
+                    .collect();
+                for individual in &mut self.population {
+                    individual.fitness = None;
+                }
+            }
+            _ => self.rounds = self.params.rounds.clone(),
+        }
+    }
+
+    /// Evaluates all individuals that don't have a fitness yet, updates the selection scores and
+    /// sorts the population on them. With racing, the whole population is raced again.
+    fn evaluate_population(&mut self) {
+        let evaluations = match self.params.racing.clone() {
+            Some(racing_params) => self.race(&racing_params),
+            None => {
+                let unevaluated: Vec<usize> = (0..self.population.len())
+                    .filter(|&index| self.population[index].fitness.is_none())
+                    .collect();
+                let results = self.evaluate_in_parallel(&unevaluated,
+                                                        &self.opponents,
+                                                        &self.rounds,
+                                                        self.prunes_this_generation());
+                unevaluated.into_iter()
+                    .zip(results)
+                    .map(|(index, (records, counts))| {
+                        let fitness = records.iter()
+                            .enumerate()
+                            .map(|(opponent, record)| {
+                                opponent_weight(&self.params.opponent_weights, opponent) *
+                                record.to_result().bot_a_points as f64
+                            })
+                            .sum();
+                        let evaluation = Evaluation {
+                            fitness,
+                            records,
+                            counts,
+                        };
+                        (index, evaluation)
+                    })
+                    .collect()
+            }
+        };
+        for (index, evaluation) in evaluations {
+            let individual = &mut self.population[index];
+            individual.fitness = Some(evaluation.fitness);
+            individual.records = evaluation.records;
+            if let Some(counts) = evaluation.counts {
+                // The counts cover complete games on every round, and removing code that never
+                // ran in any of them doesn't change their outcome, so the fitness still holds.
+                let pruned = bloat::prune(&individual.genome, &counts);
+                let nr_pruned = individual.genome.len() - pruned.len();
+                if nr_pruned > 0 {
+                    self.bloat_stats.pruned_programs += 1;
+                    self.bloat_stats.pruned_tokens += nr_pruned;
+                    individual.bot = pruned.to_bot();
+                    individual.genome = pruned;
+                }
+            }
+        }
+        self.record_lineage();
+        let selects_on_score = self.selects_on_score();
+        match self.params.novelty {
+            Some(ref novelty_params) if selects_on_score => {
+                // Only behaviours that are new to this generation are candidates for the archive;
+                // survivors were considered when they were first characterised.
+                let mut newly_characterised = vec![false; self.population.len()];
+                for (index, individual) in self.population.iter_mut().enumerate() {
+                    if individual.behaviour.is_none() {
+                        individual.behaviour =
+                            Some(Behaviour::characterise(&individual.bot,
+                                                         &self.opponents,
+                                                         novelty_params));
+                        newly_characterised[index] = true;
+                    }
+                }
+                let novelties = {
+                    let behaviours: Vec<&Behaviour> = self.population
+                        .iter()
+                        .map(|individual| individual.behaviour.as_ref().unwrap())
+                        .collect();
+                    self.archive.novelty_of_population(&behaviours, novelty_params.k)
+                };
+                for (index, (individual, novelty)) in
+                    self.population.iter_mut().zip(novelties).enumerate() {
+                    individual.novelty = novelty;
+                    individual.score = novelty_params.fitness_weight * individual.get_fitness() +
+                                       novelty_params.novelty_weight * novelty;
+                    if newly_characterised[index] {
+                        self.archive.consider(individual.behaviour.as_ref().unwrap(),
+                                              novelty,
+                                              novelty_params);
+                    }
+                }
+            }
+            _ => {
+                for individual in &mut self.population {
+                    individual.score = individual.get_fitness();
+                }
+            }
+        }
+        if self.params.bloat.parsimony != 0.0 {
+            for individual in &mut self.population {
+                individual.score -= self.params.bloat.penalty(&individual.genome);
+            }
+        }
+        if self.uses_speciation() {
+            self.share_scores_within_species();
+        }
+        if let Selection::Pareto(ref objectives) = self.params.selection {
+            assign_pareto_ranks(&mut self.population, objectives, &self.params.opponent_weights);
+            self.population.sort_by(|a, b| {
+                pareto::crowded_compare(b.rank, b.crowding, a.rank, a.crowding)
+            });
+        } else {
+            self.population.sort_by(|a, b| compare(b.score, a.score));
+        }
+        self.update_species_members();
+    }
+
+    /// Adds the individuals that were evaluated for the first time to the lineage.
+    fn record_lineage(&mut self) {
+        for individual in &self.population {
+            if !self.lineage.contains(individual.id) {
+                self.lineage.record(LineageEntry {
+                    id: individual.id,
+                    generation: individual.generation,
+                    parents: individual.parents.clone(),
+                    operator: individual.operator,
+                    fitness: individual.get_fitness(),
+                    parent_fitness: individual.parent_fitness,
+                    source_code: individual.genome.to_source(),
+                });
+            }
+        }
+    }
+
+    /// Returns whether the individuals evaluated in this generation are pruned. Pruning is only
+    /// safe after complete games on every round, so it is skipped when the rounds are sampled.
+    fn prunes_this_generation(&self) -> bool {
+        self.params.bloat.prune && self.rounds.len() == self.params.rounds.len()
+    }
+
+    /// Evaluates every individual in successive stages, each with only the best of the previous
+    /// stage. Returns the evaluation of every individual in the last stage it reached. Only the
+    /// individuals that reached the last stage, which plays complete games, get execution counts.
+    fn race(&mut self, racing_params: &RacingParams) -> Vec<(usize, Evaluation)> {
+        let stages = racing_params.plan(self.opponents.len(), &self.rounds, &mut self.rng);
+        let mut evaluations: Vec<Option<Evaluation>> =
+            (0..self.population.len()).map(|_| None).collect();
+        let mut candidates: Vec<usize> = (0..self.population.len()).collect();
+        for (stage_index, stage) in stages.iter().enumerate() {
+            if stage_index > 0 {
+                candidates.sort_by(|&a, &b| {
+                    let fitness = |index: usize| evaluations[index].as_ref().unwrap().fitness;
+                    compare(fitness(b), fitness(a))
+                });
+                candidates.truncate(racing_params.nr_survivors(candidates.len()));
+            }
+            let opponents: Vec<Bot> =
+                stage.opponents.iter().map(|&opponent| self.opponents[opponent].clone()).collect();
+            let weights: Vec<f64> = stage.opponents
+                .iter()
+                .map(|&opponent| opponent_weight(&self.params.opponent_weights, opponent))
+                .collect();
+            let profile = self.prunes_this_generation() && stage_index + 1 == stages.len();
+            let results =
+                self.evaluate_in_parallel(&candidates, &opponents, &stage.rounds, profile);
+            for (&index, (records, counts)) in candidates.iter().zip(results) {
+                evaluations[index] = Some(Evaluation {
+                    fitness: racing::stage_fitness(stage_index, &records, &weights),
+                    records,
+                    counts,
+                });
+            }
+        }
+        evaluations.into_iter()
+            .enumerate()
+            .map(|(index, evaluation)| (index, evaluation.unwrap()))
+            .collect()
+    }
+
+    /// Evaluates the individuals at the given indices against the opponents, divided over the
+    /// configured number of threads. Returns their game records in the same order, together with
+    /// the execution counts of their instructions if `profile` is set.
+    fn evaluate_in_parallel(&self,
+                            indices: &[usize],
+                            opponents: &[Bot],
+                            rounds: &[RoundParams],
+                            profile: bool)
+                            -> Vec<(Vec<GameRecord>, Option<Vec<u32>>)> {
+        let nr_threads = self.params.threads.max(1);
+        if nr_threads == 1 || indices.len() < 2 {
+            return indices.iter()
+                .map(|&index| self.evaluate_individual(index, opponents, rounds, profile))
+                .collect();
+        }
+        let chunk_size = indices.len().div_ceil(nr_threads);
+        thread::scope(|scope| {
+            let handles: Vec<_> = indices.chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk.iter()
+                            .map(|&index| {
+                                self.evaluate_individual(index, opponents, rounds, profile)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles.into_iter()
+                .flat_map(|handle| handle.join().expect("An evaluation thread panicked."))
+                .collect()
+        })
+    }
+
+    fn evaluate_individual(&self,
+                           index: usize,
+                           opponents: &[Bot],
+                           rounds: &[RoundParams],
+                           profile: bool)
+                           -> (Vec<GameRecord>, Option<Vec<u32>>) {
+        let bot = &self.population[index].bot;
+        if profile {
+            let (records, counts) = evaluate_profiled(bot, opponents, rounds);
+            (records, Some(counts))
+        } else {
+            (evaluate_recorded(bot, opponents, rounds), None)
+        }
+    }
+
+    fn share_scores_within_species(&mut self) {
+        let speciation_params = self.params.speciation.as_ref().unwrap();
+        self.species = {
+            let genomes: Vec<&Genome> = self.population
+                .iter()
+                .map(|individual| &individual.genome)
+                .collect();
+            speciation::speciate(&genomes,
+                                 &self.species,
+                                 &mut self.next_species_id,
+                                 speciation_params)
+        };
+        let scores: Vec<f64> = self.population.iter().map(|individual| individual.score).collect();
+        let shared_scores = speciation::share_scores(&scores, &self.species, speciation_params);
+        for (index, individual) in self.population.iter_mut().enumerate() {
+            individual.score = shared_scores[index];
+            individual.species = self.species
+                .iter()
+                .find(|species| species.get_members().contains(&index))
+                .map(|species| species.get_id());
+        }
+    }
+
+    /// Points the member lists of the species back at the right individuals, after the population
+    /// has been reordered.
+    fn update_species_members(&mut self) {
+        for species in &mut self.species {
+            let members = self.population
+                .iter()
+                .enumerate()
+                .filter(|&(_, individual)| individual.species == Some(species.get_id()))
+                .map(|(index, _)| index)
+                .collect();
+            species.set_members(members);
+        }
+    }
+}
+
+/// Measures the objectives of every individual, and determines their Pareto front and crowding
+/// distance. The selection score becomes the negated front number.
+fn assign_pareto_ranks(population: &mut [Individual],
+                       objectives: &[pareto::Objective],
+                       opponent_weights: &[f64]) {
+    for individual in population.iter_mut() {
+        individual.objectives = objectives.iter()
+            .map(|objective| {
+                objective.measure(&individual.genome, &individual.records, opponent_weights)
+            })
+            .collect();
+    }
+    let values: Vec<Vec<f64>> =
+        population.iter().map(|individual| individual.objectives.clone()).collect();
+    for (rank, front) in pareto::non_dominated_sort(&values).iter().enumerate() {
+        let crowding = pareto::crowding_distances(&values, front);
+        for (&index, crowding) in front.iter().zip(crowding) {
+            population[index].rank = rank;
+            population[index].crowding = crowding;
+            population[index].score = -(rank as f64);
+        }
+    }
+}
+
+fn compare(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf_bot_core::simul_round::RoundParams;
+    use bloat::BloatParams;
+    use evolution::{evaluate, OperatorRates};
+    use local_search::{LocalSearchParams, MemeticParams};
+    use novelty::NoveltyParams;
+    use pareto::{self, Objective};
+    use racing::STAGE_BONUS;
+    use speciation::SpeciationParams;
+
+    fn make_params() -> EvolutionParams {
+        EvolutionParams {
+            population_size: 8,
+            initial_program_length: 6,
+            rounds: vec![RoundParams {
+                             tape_length: 10,
+                             invert_polarity: false,
+                             max_steps: 200,
+                         }],
+            ..EvolutionParams::default()
+        }
+    }
+
+    fn make_opponents() -> Vec<Bot> {
+        vec![Genome::from_source("[-]").unwrap().to_bot()]
+    }
+
+    #[test]
+    fn new_createsEvaluatedPopulationOfRequestedSize() {
+        let evolution = Evolution::new(make_params(), make_opponents(), 1);
+        assert_eq!(evolution.get_population().len(), 8);
+        assert!(evolution.get_population().iter().all(|individual| individual.fitness.is_some()));
+    }
+
+    #[test]
+    fn nextGeneration_keepsElitesAndPopulationSize() {
+        let mut evolution = Evolution::new(make_params(), make_opponents(), 1);
+        let best_fitness = evolution.best().get_fitness();
+        evolution.next_generation();
+        assert_eq!(evolution.get_generation(), 1);
+        assert_eq!(evolution.get_population().len(), 8);
+        assert!(evolution.best().get_fitness() >= best_fitness);
+    }
+
+    #[test]
+    fn new_withOpponentWeights_scalesPointsPerOpponent() {
+        let params = EvolutionParams { opponent_weights: vec![2.5], ..make_params() };
+        let weighted = Evolution::new(params, make_opponents(), 4);
+        let plain = Evolution::new(make_params(), make_opponents(), 4);
+        for (weighted, plain) in weighted.get_population().iter().zip(plain.get_population()) {
+            assert_eq!(weighted.get_fitness(), 2.5 * plain.get_fitness());
+        }
+    }
+
+    #[test]
+    fn nextGeneration_sameSeed_sameResult() {
+        let mut evolution_a = Evolution::new(make_params(), make_opponents(), 5);
+        let mut evolution_b = Evolution::new(make_params(), make_opponents(), 5);
+        evolution_a.next_generation();
+        evolution_b.next_generation();
+        assert_eq!(evolution_a.best().get_genome(), evolution_b.best().get_genome());
+    }
+
+    #[test]
+    fn nextGeneration_withNovelty_scoresNovelty() {
+        let params = EvolutionParams {
+            novelty: Some(NoveltyParams {
+                reference_rounds: vec![RoundParams {
+                                           tape_length: 12,
+                                           invert_polarity: false,
+                                           max_steps: 100,
+                                       }],
+                sample_interval: 10,
+                fitness_weight: 0.0,
+                ..NoveltyParams::default()
+            }),
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 1);
+        evolution.next_generation();
+        let population = evolution.get_population();
+        assert!(population.iter().all(|individual| individual.get_behaviour().is_some()));
+        assert!(population.iter()
+            .all(|individual| individual.get_score() == individual.get_novelty()));
+    }
+
+    #[test]
+    fn nextGeneration_withNovelty_doesNotArchiveSurvivorsAgain() {
+        let params = EvolutionParams {
+            elitism: 8,
+            novelty: Some(NoveltyParams {
+                reference_rounds: vec![RoundParams {
+                                           tape_length: 12,
+                                           invert_polarity: false,
+                                           max_steps: 100,
+                                       }],
+                sample_interval: 10,
+                archive_threshold: -1.0,
+                ..NoveltyParams::default()
+            }),
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 1);
+        assert_eq!(evolution.get_archive().len(), 8);
+        evolution.next_generation();
+        evolution.next_generation();
+        assert_eq!(evolution.get_archive().len(), 8);
+    }
+
+    #[test]
+    fn nextGeneration_withSpeciation_assignsEveryIndividualToASpecies() {
+        let params = EvolutionParams {
+            speciation: Some(SpeciationParams::default()),
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 1);
+        evolution.next_generation();
+        let population = evolution.get_population();
+        assert!(population.iter().all(|individual| individual.get_species().is_some()));
+        let nr_members: usize =
+            evolution.get_species().iter().map(|species| species.get_members().len()).sum();
+        assert_eq!(nr_members, population.len());
+        for species in evolution.get_species() {
+            assert!(species.get_members()
+                .iter()
+                .all(|&member| population[member].get_species() == Some(species.get_id())));
+        }
+    }
+
+    #[test]
+    fn nextGeneration_withSpeciation_keepsChampionOnRawFitness() {
+        let params = EvolutionParams {
+            elitism: 1,
+            speciation: Some(SpeciationParams::default()),
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 3);
+        let champion = evolution.best().get_id();
+        let index = evolution.population.iter().position(|individual| individual.id == champion);
+        let last = evolution.population.len() - 1;
+        evolution.population.swap(index.unwrap(), last);
+        evolution.next_generation();
+        assert!(evolution.get_population().iter().any(|individual| individual.id == champion));
+    }
+
+    #[test]
+    fn nextGeneration_withParetoSelection_frontIsNonDominated() {
+        let params = EvolutionParams {
+            selection: Selection::Pareto(vec![Objective::TotalPoints,
+                                              Objective::ExpandedLength]),
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 1);
+        evolution.next_generation();
+        assert_eq!(evolution.get_population().len(), 8);
+        let front = evolution.pareto_front();
+        assert!(!front.is_empty());
+        for individual in &front {
+            assert!(evolution.get_population()
+                .iter()
+                .all(|other| !pareto::dominates(other.get_objectives(),
+                                                individual.get_objectives())));
+        }
+    }
+
+    #[test]
+    fn new_withParetoSelectionAndOpponentWeights_weighsTotalPoints() {
+        let params = EvolutionParams {
+            selection: Selection::Pareto(vec![Objective::TotalPoints]),
+            opponent_weights: vec![2.5],
+            ..make_params()
+        };
+        let evolution = Evolution::new(params, make_opponents(), 1);
+        for individual in evolution.get_population() {
+            let points = individual.get_records()[0].to_result().bot_a_points as f64;
+            assert_eq!(individual.get_objectives(), &vec![2.5 * points]);
+        }
+    }
+
+    #[test]
+    fn nextGeneration_withParetoSelection_ignoresNoveltyAndSpeciation() {
+        let params = EvolutionParams {
+            selection: Selection::Pareto(vec![Objective::TotalPoints,
+                                              Objective::ExpandedLength]),
+            novelty: Some(NoveltyParams::default()),
+            speciation: Some(SpeciationParams::default()),
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 1);
+        for _ in 0..3 {
+            evolution.next_generation();
+        }
+        assert_eq!(evolution.get_population().len(), 8);
+        assert!(evolution.get_species().is_empty());
+        assert!(evolution.get_archive().is_empty());
+        assert!(evolution.get_population()
+            .iter()
+            .all(|individual| individual.get_behaviour().is_none()));
+    }
+
+    #[test]
+    fn nextGeneration_multipleThreads_sameResultAsSingleThread() {
+        let mut single = Evolution::new(make_params(), make_opponents(), 9);
+        let mut parallel =
+            Evolution::new(EvolutionParams { threads: 3, ..make_params() }, make_opponents(), 9);
+        single.next_generation();
+        parallel.next_generation();
+        assert_eq!(single.best().get_genome(), parallel.best().get_genome());
+        assert_eq!(single.best().get_fitness(), parallel.best().get_fitness());
+    }
+
+    #[test]
+    fn nextGeneration_withRoundSample_evaluatesOnSample() {
+        let params = EvolutionParams {
+            rounds: vec![RoundParams {
+                             tape_length: 10,
+                             invert_polarity: false,
+                             max_steps: 100,
+                         };
+                         5],
+            round_sample: Some(2),
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 1);
+        evolution.next_generation();
+        assert!(evolution.get_population()
+            .iter()
+            .all(|individual| individual.get_records()[0].rounds.len() == 2));
+    }
+
+    #[test]
+    fn nextGeneration_recordsLineageOfOffspring() {
+        let mut evolution = Evolution::new(make_params(), make_opponents(), 2);
+        evolution.next_generation();
+        assert_eq!(evolution.get_lineage().len(), 8 + 6);
+        for individual in evolution.get_population() {
+            let entry = evolution.get_lineage().get(individual.get_id()).unwrap();
+            assert_eq!(&entry.parents, individual.get_parents());
+            assert_eq!(entry.parents.is_empty(), individual.get_generation() == 0);
+            assert!(entry.parents.iter().all(|&parent| evolution.get_lineage().contains(parent)));
+        }
+    }
+
+    #[test]
+    fn nextGeneration_withLocalSearch_refinesOffspring() {
+        let params = EvolutionParams {
+            local_search: Some(MemeticParams {
+                rate: 1.0,
+                search: LocalSearchParams {
+                    iterations: 5,
+                    annealing: None,
+                },
+            }),
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 6);
+        let mut plain = Evolution::new(make_params(), make_opponents(), 6);
+        evolution.next_generation();
+        plain.next_generation();
+        assert_eq!(evolution.get_population().len(), 8);
+        let genomes = |evolution: &Evolution| -> Vec<Genome> {
+            evolution.get_population().iter().map(|individual| individual.genome.clone()).collect()
+        };
+        assert_ne!(genomes(&evolution), genomes(&plain));
+    }
+
+    #[test]
+    fn nextGeneration_withPruning_keepsFitnessAndReportsRemovedCode() {
+        let params = EvolutionParams {
+            initial_program_length: 30,
+            bloat: BloatParams {
+                prune: true,
+                ..BloatParams::default()
+            },
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 3);
+        evolution.next_generation();
+        let stats = *evolution.get_bloat_stats();
+        assert!(stats.pruned_tokens >= stats.pruned_programs);
+        for individual in evolution.get_population() {
+            assert_eq!(evaluate(individual.get_bot(),
+                                &make_opponents(),
+                                &evolution.get_params().rounds),
+                       individual.get_fitness());
+        }
+    }
+
+    #[test]
+    fn nextGeneration_withPruningAndRoundSample_doesNotPrune() {
+        let params = EvolutionParams {
+            initial_program_length: 30,
+            rounds: vec![RoundParams {
+                             tape_length: 10,
+                             invert_polarity: false,
+                             max_steps: 200,
+                         },
+                         RoundParams {
+                             tape_length: 20,
+                             invert_polarity: true,
+                             max_steps: 200,
+                         }],
+            round_sample: Some(1),
+            bloat: BloatParams {
+                prune: true,
+                ..BloatParams::default()
+            },
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 3);
+        assert_eq!(evolution.get_bloat_stats().pruned_programs, 0);
+        evolution.next_generation();
+        assert_eq!(evolution.get_bloat_stats().pruned_programs, 0);
+    }
+
+    #[test]
+    fn nextGeneration_withPruningAndRacing_prunesOnlyFinalists() {
+        let params = EvolutionParams {
+            initial_program_length: 30,
+            rounds: vec![RoundParams {
+                             tape_length: 10,
+                             invert_polarity: false,
+                             max_steps: 200,
+                         };
+                         4],
+            racing: Some(RacingParams {
+                nr_stages: 2,
+                keep_fraction: 0.5,
+                initial_opponents: 1,
+                initial_rounds: 2,
+                initial_max_steps: 5,
+            }),
+            bloat: BloatParams {
+                prune: true,
+                ..BloatParams::default()
+            },
+            ..make_params()
+        };
+        let mut rng = Rng::new(3);
+        let originals: Vec<Genome> =
+            (0..8).map(|_| operators::random_genome(30, &mut rng)).collect();
+        let evolution = Evolution::new(params, make_opponents(), 3);
+        let eliminated = evolution.get_population()
+            .iter()
+            .filter(|individual| individual.get_fitness() < STAGE_BONUS - 42.0);
+        for individual in eliminated {
+            assert_eq!(individual.get_genome(), &originals[individual.get_id()]);
+        }
+    }
+
+    #[test]
+    fn new_withRacingAndOpponentWeights_weighsStageFitness() {
+        let params = EvolutionParams {
+            racing: Some(RacingParams {
+                nr_stages: 1,
+                ..RacingParams::default()
+            }),
+            opponent_weights: vec![1.0, 4.0],
+            ..make_params()
+        };
+        let opponents: Vec<Bot> = ["<", "[-]"]
+            .iter()
+            .map(|source_code| Genome::from_source(source_code).unwrap().to_bot())
+            .collect();
+        let evolution = Evolution::new(params, opponents, 2);
+        for individual in evolution.get_population() {
+            let points: Vec<f64> = individual.get_records()
+                .iter()
+                .map(|record| record.to_result().bot_a_points as f64)
+                .collect();
+            // Every game has the single round of make_params.
+            assert_eq!(individual.get_fitness(), 42.0 * (points[0] + 4.0 * points[1]) / 5.0);
+        }
+    }
+
+    #[test]
+    fn nextGeneration_withLocalSearchAndMaxLength_refinedOffspringStayWithinLimit() {
+        let params = EvolutionParams {
+            local_search: Some(MemeticParams {
+                rate: 1.0,
+                search: LocalSearchParams {
+                    iterations: 20,
+                    annealing: None,
+                },
+            }),
+            bloat: BloatParams {
+                max_length: Some(6),
+                ..BloatParams::default()
+            },
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 2);
+        for _ in 0..3 {
+            evolution.next_generation();
+        }
+        assert!(evolution.get_population()
+            .iter()
+            .all(|individual| individual.get_genome().len() <= 6));
+    }
+
+    #[test]
+    fn nextGeneration_withMaxLength_offspringStayWithinLimit() {
+        let params = EvolutionParams {
+            operator_rates: OperatorRates {
+                crossover: 0.0,
+                point_mutation: 0.0,
+                insertion: 1.0,
+                deletion: 0.0,
+                wrap_loop: 0.0,
+                unwrap_loop: 0.0,
+                repeat_count: 0.0,
+            },
+            bloat: BloatParams {
+                max_length: Some(7),
+                ..BloatParams::default()
+            },
+            ..make_params()
+        };
+        let mut evolution = Evolution::new(params, make_opponents(), 1);
+        for _ in 0..3 {
+            evolution.next_generation();
+        }
+        assert!(evolution.get_population()
+            .iter()
+            .all(|individual| individual.get_genome().len() <= 7));
+        assert!(evolution.get_bloat_stats().rejected_offspring > 0);
+    }
+
+    #[test]
+    fn nextGeneration_withRacing_onlyBestPlayEveryOpponent() {
+        let params = EvolutionParams {
+            rounds: vec![RoundParams {
+                             tape_length: 10,
+                             invert_polarity: false,
+                             max_steps: 200,
+                         };
+                         4],
+            racing: Some(RacingParams {
+                nr_stages: 2,
+                keep_fraction: 0.5,
+                initial_opponents: 1,
+                initial_rounds: 2,
+                initial_max_steps: 50,
+            }),
+            ..make_params()
+        };
+        let opponents: Vec<Bot> = ["[-]", ">", "+", "<"]
+            .iter()
+            .map(|source_code| Genome::from_source(source_code).unwrap().to_bot())
+            .collect();
+        let mut evolution = Evolution::new(params, opponents, 1);
+        evolution.next_generation();
+        let (finalists, eliminated): (Vec<&Individual>, Vec<&Individual>) = evolution
+            .get_population()
+            .iter()
+            .partition(|individual| individual.get_fitness() >= STAGE_BONUS - 42.0);
+        assert_eq!(finalists.len(), 4);
+        assert!(finalists.iter().all(|individual| individual.get_records().len() == 4));
+        assert!(eliminated.iter().all(|individual| individual.get_records().len() == 1));
+        assert!(eliminated.iter()
+            .all(|individual| individual.get_records()[0].rounds.len() == 2));
+    }
+
+    #[test]
+    fn resume_fromCheckpoint_continuesLikeOriginal() {
+        let mut original = Evolution::new(make_params(), make_opponents(), 4);
+        original.next_generation();
+        let mut resumed = Evolution::resume(make_params(), make_opponents(), original.checkpoint());
+        assert_eq!(resumed.get_generation(), 1);
+        original.next_generation();
+        resumed.next_generation();
+        assert_eq!(original.best().get_genome(), resumed.best().get_genome());
+    }
+}
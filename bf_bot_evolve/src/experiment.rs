@@ -0,0 +1,403 @@
+//! Running an evolution as described by a configuration file.
+//! The opponents are read from their source files and the gauntlet, if any, and if the configured
+//! checkpoint file exists, the run continues where it left off. Otherwise, the population starts
+//! with the configured seed bots, if any. Checkpoints are saved at the configured interval and
+//! once a stop criterion is met. Optionally, the statistics of every generation are logged, and the
+//! ancestry of the best individual is exported at the end.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use bf_bot_compiler::loader::{self, LoadError};
+use bf_bot_compiler::parser::ParseError;
+use bf_bot_core::bf::Bot;
+use bf_bot_tournament::gauntlet::{Gauntlet, GauntletError};
+use checkpoint::{Checkpoint, CheckpointError};
+use config::RunConfig;
+use evolution::{Evolution, EvolutionParams};
+use genome::Genome;
+use lineage::LineageFormat;
+use rng::Rng;
+use seeding;
+use stats::{GenerationStats, StatsLog};
+
+/// Reasons why an experiment can't be run.
+#[derive(Debug)]
+pub enum ExperimentError {
+    /// A file could not be read or written.
+    Io(PathBuf, io::Error),
+    /// The source code of an opponent is invalid.
+    InvalidOpponent(PathBuf, ParseError),
+    /// The checkpoint to resume from is invalid.
+    InvalidCheckpoint(PathBuf, CheckpointError),
+    /// The gauntlet can't be read.
+    InvalidGauntlet(PathBuf, GauntletError),
+}
+
+impl fmt::Display for ExperimentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExperimentError::Io(ref path, ref error) => {
+                write!(f, "{}: {}", path.display(), error)
+            }
+            ExperimentError::InvalidOpponent(ref path, ref error) => {
+                write!(f, "{}: {}", path.display(), error)
+            }
+            ExperimentError::InvalidCheckpoint(ref path, ref error) => {
+                write!(f, "{}: {}", path.display(), error)
+            }
+            ExperimentError::InvalidGauntlet(ref path, ref error) => {
+                write!(f, "{}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+/// Receives reports on the progress of an experiment.
+pub trait Observer {
+    /// Called after every generation, including the initial one.
+    fn on_generation(&mut self, evolution: &Evolution);
+
+    /// Called for every file in the seed directory that could not be loaded. The file is left out
+    /// of the population, and the run continues.
+    fn on_seed_error(&mut self, _path: &Path, _error: &LoadError) {}
+
+    /// Called for every seed bot that exceeds the size limits of the run. The bot is left out of
+    /// the population, and the run continues.
+    fn on_seed_rejected(&mut self, _path: &Path) {}
+}
+
+/// An observer that only passes the generations on to a callback.
+struct Generations<F>(F);
+
+impl<F> Observer for Generations<F>
+    where F: FnMut(&Evolution)
+{
+    fn on_generation(&mut self, evolution: &Evolution) {
+        (self.0)(evolution)
+    }
+}
+
+/// Reads the source files of the opponents.
+pub fn load_opponents(paths: &[PathBuf]) -> Result<Vec<Bot>, ExperimentError> {
+    paths.iter()
+        .map(|path| {
+            let source_code = fs::read_to_string(path)
+                .map_err(|error| ExperimentError::Io(path.clone(), error))?;
+            let genome = Genome::from_source(&source_code)
+                .map_err(|error| ExperimentError::InvalidOpponent(path.clone(), error))?;
+            Ok(genome.to_bot())
+        })
+        .collect()
+}
+
+/// Runs an experiment until one of its stop criteria is met, and returns the final state of the
+/// evolution. The callback is invoked after every generation, including the initial one. Seed
+/// bots that fail to load are skipped silently; use `run_observed` to hear about them.
+pub fn run<F>(config: &RunConfig, on_generation: F) -> Result<Evolution, ExperimentError>
+    where F: FnMut(&Evolution)
+{
+    run_observed(config, &mut Generations(on_generation))
+}
+
+/// Runs an experiment like `run`, reporting its progress to the observer.
+pub fn run_observed<O>(config: &RunConfig, observer: &mut O) -> Result<Evolution, ExperimentError>
+    where O: Observer
+{
+    let start = Instant::now();
+    let mut opponents = load_opponents(&config.opponents)?;
+    let mut params = config.params.clone();
+    if let Some(ref path) = config.gauntlet {
+        let gauntlet = Gauntlet::load(path)
+            .map_err(|error| ExperimentError::InvalidGauntlet(path.clone(), error))?;
+        params.opponent_weights = vec![1.0; opponents.len()];
+        params.opponent_weights.extend(gauntlet.weights());
+        opponents.extend(gauntlet.bots());
+    }
+    let (mut evolution, resumed) = match config.checkpoint {
+        Some(ref path) if path.exists() => {
+            let checkpoint = Checkpoint::load(path)
+                .map_err(|error| ExperimentError::InvalidCheckpoint(path.clone(), error))?;
+            (Evolution::resume(params, opponents, checkpoint), true)
+        }
+        _ => (start_evolution(config, params, opponents, observer)?, false),
+    };
+    let mut log = match config.log {
+        Some((ref path, format)) => {
+            Some(StatsLog::open(path, format, resumed)
+                .map_err(|error| ExperimentError::Io(path.clone(), error))?)
+        }
+        None => None,
+    };
+    // A resumed generation was logged before its checkpoint was saved.
+    if !resumed {
+        log_generation(config, &evolution, &mut log)?;
+    }
+    observer.on_generation(&evolution);
+    while !config.stop.is_met(&evolution, start.elapsed()) {
+        evolution.next_generation();
+        log_generation(config, &evolution, &mut log)?;
+        observer.on_generation(&evolution);
+        if evolution.get_generation() % config.checkpoint_interval == 0 {
+            save_checkpoint(config, &evolution)?;
+        }
+    }
+    save_checkpoint(config, &evolution)?;
+    save_lineage(config, &evolution)?;
+    Ok(evolution)
+}
+
+/// Creates the initial population, from the seed bots if there are any.
+fn start_evolution<O: Observer>(config: &RunConfig,
+                                params: EvolutionParams,
+                                opponents: Vec<Bot>,
+                                observer: &mut O)
+                                -> Result<Evolution, ExperimentError> {
+    let (dir, fill) = match config.seed_bots {
+        Some((ref dir, fill)) => (dir, fill),
+        None => return Ok(Evolution::new(params, opponents, config.seed)),
+    };
+    let loaded = loader::load_dir(dir).map_err(|error| ExperimentError::Io(dir.clone(), error))?;
+    for (path, error) in &loaded.failures {
+        observer.on_seed_error(path, error);
+    }
+    let mut seeds = vec![];
+    for loaded in &loaded.bots {
+        let genome = Genome::from_bot(&loaded.bot);
+        if params.bloat.allows(&genome) {
+            seeds.push(genome);
+        } else {
+            observer.on_seed_rejected(&loaded.path);
+        }
+    }
+    let mut rng = Rng::new(config.seed);
+    let genomes = seeding::seed_population(&seeds,
+                                           params.population_size,
+                                           fill,
+                                           params.initial_program_length,
+                                           &params.bloat,
+                                           &mut rng);
+    Ok(Evolution::from_genomes(params, opponents, genomes, rng))
+}
+
+fn log_generation(config: &RunConfig,
+                  evolution: &Evolution,
+                  log: &mut Option<StatsLog>)
+                  -> Result<(), ExperimentError> {
+    match (log.as_mut(), config.log.as_ref()) {
+        (Some(log), Some((path, _))) => {
+            log.write(&GenerationStats::collect(evolution))
+                .map_err(|error| ExperimentError::Io(path.clone(), error))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Writes the ancestry of the best individual.
+fn save_lineage(config: &RunConfig, evolution: &Evolution) -> Result<(), ExperimentError> {
+    let (path, format) = match config.lineage {
+        Some((ref path, format)) => (path, format),
+        None => return Ok(()),
+    };
+    let lineage = evolution.get_lineage();
+    let best = evolution.best().get_id();
+    let contents = match format {
+        LineageFormat::Dot => lineage.to_dot(best),
+        LineageFormat::Newick => lineage.to_newick(best, config.lineage_depth),
+    };
+    fs::write(path, contents).map_err(|error| ExperimentError::Io(path.clone(), error))
+}
+
+fn save_checkpoint(config: &RunConfig, evolution: &Evolution) -> Result<(), ExperimentError> {
+    match config.checkpoint {
+        Some(ref path) => save_to(path, &evolution.checkpoint()),
+        None => Ok(()),
+    }
+}
+
+fn save_to(path: &Path, checkpoint: &Checkpoint) -> Result<(), ExperimentError> {
+    checkpoint.save(path).map_err(|error| ExperimentError::Io(path.to_path_buf(), error))
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::Path;
+
+    fn write_temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("bf_bot_evolve_{}_{}", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn make_config(opponent: &Path, extra: &str) -> RunConfig {
+        let text = format!("opponents = {}\npopulation_size = 4\ninitial_program_length = 4\n\
+                            rounds.max_tape_length = 11\nrounds.max_steps = 100\n{}",
+                           opponent.display(),
+                           extra);
+        RunConfig::parse(&text, Path::new("")).unwrap()
+    }
+
+    #[test]
+    fn loadOpponents_invalidSource_reportsFile() {
+        let path = write_temp_file("invalid_opponent.bf", "[[");
+        match load_opponents(std::slice::from_ref(&path)) {
+            Err(ExperimentError::InvalidOpponent(ref error_path, _)) => {
+                assert_eq!(error_path, &path)
+            }
+            other => panic!("Expected an invalid opponent, got {:?}", other),
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn run_stopsAfterConfiguredGenerations() {
+        let opponent = write_temp_file("stop_opponent.bf", "[-]");
+        let config = make_config(&opponent, "stop.generations = 3\n");
+        let mut nr_callbacks = 0;
+        let evolution = run(&config, |_| nr_callbacks += 1).unwrap();
+        assert_eq!(evolution.get_generation(), 3);
+        assert_eq!(nr_callbacks, 4);
+        fs::remove_file(opponent).unwrap();
+    }
+
+    #[test]
+    fn run_withGauntlet_addsWeightedOpponents() {
+        let opponent = write_temp_file("gauntlet_opponent.bf", "[-]");
+        let gauntlet = write_temp_file("run.gauntlet", "idle.source = .\nidle.weight = 2\n");
+        let config = make_config(&opponent,
+                                 &format!("gauntlet = {}\nstop.generations = 1\n",
+                                          gauntlet.display()));
+        let evolution = run(&config, |_| {}).unwrap();
+        assert_eq!(evolution.get_params().opponent_weights, vec![1.0, 2.0]);
+        fs::remove_file(opponent).unwrap();
+        fs::remove_file(gauntlet).unwrap();
+    }
+
+    #[test]
+    fn run_withLogAndLineage_writesBoth() {
+        let opponent = write_temp_file("log_opponent.bf", "[-]");
+        let log = env::temp_dir().join(format!("bf_bot_evolve_log_{}.csv", std::process::id()));
+        let lineage =
+            env::temp_dir().join(format!("bf_bot_evolve_lineage_{}.dot", std::process::id()));
+        let extra = format!("log = {}\nlineage = {}\nstop.generations = 2\n",
+                            log.display(),
+                            lineage.display());
+        run(&make_config(&opponent, &extra), |_| {}).unwrap();
+        assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 4);
+        assert!(fs::read_to_string(&lineage).unwrap().starts_with("digraph lineage {"));
+        fs::remove_file(opponent).unwrap();
+        fs::remove_file(log).unwrap();
+        fs::remove_file(lineage).unwrap();
+    }
+
+    /// Records the generations and seed errors it is told about.
+    #[derive(Default)]
+    struct Recorder {
+        generations: Vec<usize>,
+        seed_errors: Vec<PathBuf>,
+        rejected_seeds: Vec<PathBuf>,
+    }
+
+    impl Observer for Recorder {
+        fn on_generation(&mut self, evolution: &Evolution) {
+            self.generations.push(evolution.get_generation());
+        }
+
+        fn on_seed_error(&mut self, path: &Path, _error: &LoadError) {
+            self.seed_errors.push(path.to_path_buf());
+        }
+
+        fn on_seed_rejected(&mut self, path: &Path) {
+            self.rejected_seeds.push(path.to_path_buf());
+        }
+    }
+
+    #[test]
+    fn run_withSeedBots_startsFromSeedsAndReportsBrokenFiles() {
+        let opponent = write_temp_file("seed_opponent.bf", "[-]");
+        let dir = env::temp_dir().join(format!("bf_bot_evolve_seeds_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("rush.bf"), "(>)*8([-]>)*21").unwrap();
+        fs::write(dir.join("broken.bf"), "(>)*").unwrap();
+        let extra = format!("seed_bots = {}\nseed_bots.fill = classic\nstop.generations = 0\n",
+                            dir.display());
+        let mut recorder = Recorder::default();
+        let evolution = run_observed(&make_config(&opponent, &extra), &mut recorder).unwrap();
+        assert_eq!(recorder.generations, vec![0]);
+        assert_eq!(recorder.seed_errors, vec![dir.join("broken.bf")]);
+        let rush = Genome::from_source("(>)*8([-]>)*21").unwrap();
+        assert!(evolution.get_population()
+            .iter()
+            .any(|individual| individual.get_genome() == &rush));
+        fs::remove_file(opponent).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn run_withSeedBotsAndMaxLength_leavesOutSeedsThatAreTooLong() {
+        let opponent = write_temp_file("long_seed_opponent.bf", "[-]");
+        let dir = env::temp_dir().join(format!("bf_bot_evolve_long_seeds_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("rush.bf"), "(>)*8([-]>)*21").unwrap();
+        fs::write(dir.join("long.bf"), ">>>>>>>>>>>>([-]>)*21").unwrap();
+        let extra = format!("seed_bots = {}\nbloat.max_length = 10\nstop.generations = 0\n",
+                            dir.display());
+        let mut recorder = Recorder::default();
+        let evolution = run_observed(&make_config(&opponent, &extra), &mut recorder).unwrap();
+        assert_eq!(recorder.rejected_seeds, vec![dir.join("long.bf")]);
+        assert!(evolution.get_population()
+            .iter()
+            .all(|individual| individual.get_genome().len() <= 10));
+        fs::remove_file(opponent).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn run_withCheckpoint_resumesWhereItStopped() {
+        let opponent = write_temp_file("resume_opponent.bf", "[-]");
+        let checkpoint = env::temp_dir()
+            .join(format!("bf_bot_evolve_resume_{}.checkpoint", std::process::id()));
+        let extra = format!("checkpoint = {}\n", checkpoint.display());
+        let first = make_config(&opponent, &(extra.clone() + "stop.generations = 2\n"));
+        run(&first, |_| {}).unwrap();
+        let second = make_config(&opponent, &(extra + "stop.generations = 3\n"));
+        let mut generations = vec![];
+        run(&second, |evolution| generations.push(evolution.get_generation())).unwrap();
+        assert_eq!(generations, vec![2, 3]);
+        fs::remove_file(opponent).unwrap();
+        fs::remove_file(checkpoint).unwrap();
+    }
+
+    #[test]
+    fn run_withCheckpointAndLog_logsEveryGenerationOnce() {
+        let opponent = write_temp_file("resume_log_opponent.bf", "[-]");
+        let checkpoint = env::temp_dir()
+            .join(format!("bf_bot_evolve_resume_log_{}.checkpoint", std::process::id()));
+        let log =
+            env::temp_dir().join(format!("bf_bot_evolve_resume_log_{}.csv", std::process::id()));
+        let extra = format!("checkpoint = {}
+log = {}
+", checkpoint.display(), log.display());
+        run(&make_config(&opponent, &(extra.clone() + "stop.generations = 3
+")), |_| {})
+            .unwrap();
+        run(&make_config(&opponent, &(extra + "stop.generations = 5
+")), |_| {}).unwrap();
+        let generations: Vec<String> = fs::read_to_string(&log)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap().to_string())
+            .collect();
+        assert_eq!(generations, vec!["0", "1", "2", "3", "4", "5"]);
+        fs::remove_file(opponent).unwrap();
+        fs::remove_file(checkpoint).unwrap();
+        fs::remove_file(log).unwrap();
+    }
+}
@@ -0,0 +1,321 @@
+//! The genetic representation of a bot.
+//! A `Genome` is the program of a bot as a flat stream of tokens, without comments. Unlike a
+//! compiled program, it contains no jump targets, which makes it easy to cut, splice and mutate.
+//! Every genome is kept well-formed: its brackets always match.
+
+use std::fmt;
+
+use bf_bot_compiler::parser::{self, ParseError};
+use bf_bot_core::bf::{Bot, Instruction};
+
+/// A single instruction of a genome. These correspond one-to-one with the `Instruction`s that the
+/// parser produces, but loops don't know where their matching bracket is.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Token {
+    MoveBack,
+    MoveForward,
+    Increment,
+    Decrement,
+    SkipExecution,
+    StartWhileNotZero,
+    EndWhileNotZero,
+    StartFor,
+    EndFor { nr_iterations: usize },
+}
+
+/// All tokens that are not part of a loop construct.
+pub const SIMPLE_TOKENS: [Token; 5] = [Token::MoveBack,
+                                       Token::MoveForward,
+                                       Token::Increment,
+                                       Token::Decrement,
+                                       Token::SkipExecution];
+
+impl Token {
+    pub fn is_simple(&self) -> bool {
+        SIMPLE_TOKENS.contains(self)
+    }
+
+    pub fn is_opening(&self) -> bool {
+        *self == Token::StartWhileNotZero || *self == Token::StartFor
+    }
+
+    pub fn is_closing(&self) -> bool {
+        matches!(*self, Token::EndWhileNotZero | Token::EndFor { .. })
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Token::MoveBack => write!(f, "<"),
+            Token::MoveForward => write!(f, ">"),
+            Token::Increment => write!(f, "+"),
+            Token::Decrement => write!(f, "-"),
+            Token::SkipExecution => write!(f, "."),
+            Token::StartWhileNotZero => write!(f, "["),
+            Token::EndWhileNotZero => write!(f, "]"),
+            Token::StartFor => write!(f, "("),
+            Token::EndFor { nr_iterations } => write!(f, ")*{}", nr_iterations),
+        }
+    }
+}
+
+/// The program of a bot as a stream of tokens with matching brackets.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Genome {
+    tokens: Vec<Token>,
+}
+
+impl Genome {
+    /// Creates a genome from a list of tokens. Panics if the brackets don't match.
+    pub fn new(tokens: Vec<Token>) -> Genome {
+        assert!(is_balanced(&tokens), "The brackets of a genome must match.");
+        Genome { tokens }
+    }
+
+    /// Reads a genome from BrainFuck Joust source code. Comments are dropped.
+    pub fn from_source(source_code: &str) -> Result<Genome, ParseError> {
+        parser::try_parse_bot(source_code).map(|program| Genome::from_program(&program))
+    }
+
+    /// Reads the genome of a compiled bot.
+    pub fn from_bot(bot: &Bot) -> Genome {
+        Genome::from_program(bot.get_program())
+    }
+
+    fn from_program(program: &[Instruction]) -> Genome {
+        let tokens = program.iter()
+            .map(|instruction| match *instruction {
+                Instruction::MoveBack => Token::MoveBack,
+                Instruction::MoveForward => Token::MoveForward,
+                Instruction::Increment => Token::Increment,
+                Instruction::Decrement => Token::Decrement,
+                Instruction::SkipExecution => Token::SkipExecution,
+                Instruction::StartWhileNotZero { .. } => Token::StartWhileNotZero,
+                Instruction::EndWhileNotZero { .. } => Token::EndWhileNotZero,
+                Instruction::StartFor { .. } => Token::StartFor,
+                Instruction::EndFor { nr_iterations, .. } => Token::EndFor { nr_iterations },
+            })
+            .collect();
+        Genome::new(tokens)
+    }
+
+    /// Writes the genome back as BrainFuck Joust source code.
+    pub fn to_source(&self) -> String {
+        self.tokens.iter().map(|token| token.to_string()).collect()
+    }
+
+    /// Compiles the genome into a bot.
+    pub fn to_bot(&self) -> Bot {
+        Bot::new(parser::parse_bot(self.to_source()))
+    }
+
+    pub fn get_tokens(&self) -> &Vec<Token> {
+        &self.tokens
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Returns the index of the bracket that matches the bracket at the given index. Returns None
+    /// if there is no bracket at that index.
+    pub fn matching_bracket(&self, index: usize) -> Option<usize> {
+        let token = self.tokens[index];
+        if token.is_opening() {
+            let mut depth = 0;
+            for (offset, other) in self.tokens[index..].iter().enumerate() {
+                depth += depth_change(other);
+                if depth == 0 {
+                    return Some(index + offset);
+                }
+            }
+        } else if token.is_closing() {
+            let mut depth = 0;
+            for position in (0..index + 1).rev() {
+                depth += depth_change(&self.tokens[position]);
+                if depth == 0 {
+                    return Some(position);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns every range `[start, end)` that starts at the given index and contains a
+    /// well-formed piece of program. The empty range is always included.
+    pub fn balanced_ranges_from(&self, start: usize) -> Vec<(usize, usize)> {
+        let mut ranges = vec![(start, start)];
+        let mut depth = 0;
+        for (offset, token) in self.tokens[start..].iter().enumerate() {
+            depth += depth_change(token);
+            if depth < 0 {
+                break;
+            }
+            if depth == 0 {
+                ranges.push((start, start + offset + 1));
+            }
+        }
+        ranges
+    }
+
+    /// Replaces the tokens in the range `[start, end)` with the given tokens. Panics if this
+    /// would leave the genome with mismatched brackets.
+    pub fn splice(&mut self, start: usize, end: usize, replacement: &[Token]) {
+        let mut tokens = self.tokens[..start].to_vec();
+        tokens.extend_from_slice(replacement);
+        tokens.extend_from_slice(&self.tokens[end..]);
+        *self = Genome::new(tokens);
+    }
+
+    /// Returns the number of instructions a bot would have if every for loop were written out in
+    /// full. Saturates at `usize::MAX`.
+    pub fn expanded_len(&self) -> usize {
+        let mut stack: Vec<usize> = vec![0];
+        for token in &self.tokens {
+            match *token {
+                Token::StartFor => stack.push(0),
+                Token::EndFor { nr_iterations } => {
+                    let body = stack.pop().unwrap();
+                    let outer = stack.last_mut().unwrap();
+                    *outer = outer.saturating_add(body.saturating_mul(nr_iterations));
+                }
+                _ => {
+                    let outer = stack.last_mut().unwrap();
+                    *outer = outer.saturating_add(1);
+                }
+            }
+        }
+        stack[0]
+    }
+}
+
+impl fmt::Display for Genome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_source())
+    }
+}
+
+fn depth_change(token: &Token) -> i32 {
+    if token.is_opening() {
+        1
+    } else if token.is_closing() {
+        -1
+    } else {
+        0
+    }
+}
+
+/// Returns true if every bracket in the list of tokens is matched by a bracket of the same kind.
+fn is_balanced(tokens: &[Token]) -> bool {
+    let mut stack = vec![];
+    for token in tokens {
+        let expected_opening = match *token {
+            Token::EndWhileNotZero => Token::StartWhileNotZero,
+            Token::EndFor { .. } => Token::StartFor,
+            _ => {
+                if token.is_opening() {
+                    stack.push(*token);
+                }
+                continue;
+            }
+        };
+        if stack.pop() != Some(expected_opening) {
+            return false;
+        }
+    }
+    stack.is_empty()
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fromSource_dropsComments() {
+        let genome = Genome::from_source("a>b+c").unwrap();
+        assert_eq!(genome.get_tokens(), &vec![Token::MoveForward, Token::Increment]);
+    }
+
+    #[test]
+    fn fromBot_reversesToBot() {
+        let genome = Genome::from_source("(>[-]+)*7.<").unwrap();
+        assert_eq!(Genome::from_bot(&genome.to_bot()), genome);
+    }
+
+    #[test]
+    fn fromSource_readsForLoopIterations() {
+        let genome = Genome::from_source("(>)*12").unwrap();
+        assert_eq!(genome.get_tokens(),
+                   &vec![Token::StartFor, Token::MoveForward, Token::EndFor { nr_iterations: 12 }]);
+    }
+
+    #[test]
+    fn fromSource_unmatchedOpeningBracket_returnsError() {
+        assert_eq!(Genome::from_source("+[-"),
+                   Err(ParseError::UnmatchedOpeningBracket(1, 2)));
+    }
+
+    #[test]
+    fn fromSource_wrongKindOfClosingBracket_returnsError() {
+        assert_eq!(Genome::from_source("(-]"),
+                   Err(ParseError::UnmatchedClosingBracket(1, 3)));
+    }
+
+    #[test]
+    fn fromSource_forLoopWithoutCount_returnsError() {
+        assert_eq!(Genome::from_source("(-)"),
+                   Err(ParseError::MissingIterationCount(1, 3)));
+    }
+
+    #[test]
+    fn toSource_roundTripsThroughFromSource() {
+        let source = ">(+)*3[-.]<";
+        assert_eq!(Genome::from_source(source).unwrap().to_source(), source);
+    }
+
+    #[test]
+    fn toBot_hasOneInstructionPerToken() {
+        let genome = Genome::from_source(">(+)*3[-.]<").unwrap();
+        assert_eq!(genome.to_bot().get_program().len(), genome.len());
+    }
+
+    #[test]
+    fn matchingBracket_findsPartnerInBothDirections() {
+        let genome = Genome::from_source("[>[-]]").unwrap();
+        assert_eq!(genome.matching_bracket(0), Some(5));
+        assert_eq!(genome.matching_bracket(5), Some(0));
+        assert_eq!(genome.matching_bracket(2), Some(4));
+        assert_eq!(genome.matching_bracket(1), None);
+    }
+
+    #[test]
+    fn balancedRangesFrom_stopsAtEnclosingLoop() {
+        let genome = Genome::from_source("[>[-]]").unwrap();
+        assert_eq!(genome.balanced_ranges_from(1), vec![(1, 1), (1, 2), (1, 5)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn splice_breakingBrackets_shouldPanic() {
+        let mut genome = Genome::from_source("[-]").unwrap();
+        genome.splice(0, 1, &[]);
+    }
+
+    #[test]
+    fn expandedLen_multipliesNestedForLoops() {
+        let genome = Genome::from_source("+((>)*3-)*2").unwrap();
+        assert_eq!(genome.expanded_len(), 1 + 2 * (3 + 1));
+    }
+
+    #[test]
+    fn expandedLen_hugeIterationCounts_saturates() {
+        let genome = Genome::from_source("((+)*100000000000)*100000000000").unwrap();
+        assert_eq!(genome.expanded_len(), usize::MAX);
+    }
+}
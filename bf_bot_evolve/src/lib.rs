@@ -0,0 +1,30 @@
+//! Evolves BrainFuck Joust bots.
+//! Bots are represented by their `Genome`, which the variation operators cut, splice and mutate.
+//! The `Evolution` loop breeds a population of genomes and selects on their fitness against a set
+//! of opponents, optionally combined with the novelty of their behaviour, and optionally shared
+//! within species of similar programs; `bloat` keeps the programs from growing without limit.
+//! Runs are described by configuration files, can start from a directory of existing bots, and
+//! can be saved to and resumed from checkpoints.
+//! Hand-written bots with numeric holes can be tuned with `template` and `tuning`.
+extern crate bf_bot_core;
+extern crate bf_bot_compiler;
+extern crate bf_bot_tournament;
+
+pub mod rng;
+pub mod genome;
+pub mod operators;
+pub mod evolution;
+pub mod novelty;
+pub mod speciation;
+pub mod pareto;
+pub mod checkpoint;
+pub mod config;
+pub mod experiment;
+pub mod lineage;
+pub mod stats;
+pub mod template;
+pub mod tuning;
+pub mod local_search;
+pub mod bloat;
+pub mod racing;
+pub mod seeding;
@@ -0,0 +1,226 @@
+//! The genealogy of an evolution run.
+//! Every individual is recorded together with its parents and the operator that created it, so
+//! the ancestry of a champion can be traced back to the initial population. Ancestries can be
+//! exported as a Graphviz DOT graph, or as a Newick tree rooted at the champion.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::Path;
+
+use operators::Operator;
+
+/// The file formats a lineage can be exported to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LineageFormat {
+    Dot,
+    Newick,
+}
+
+impl LineageFormat {
+    /// Determines the format from the extension of a file: `.dot` or `.gv` for DOT, `.nwk` or
+    /// `.newick` for Newick.
+    pub fn from_path(path: &Path) -> Option<LineageFormat> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("dot") | Some("gv") => Some(LineageFormat::Dot),
+            Some("nwk") | Some("newick") => Some(LineageFormat::Newick),
+            _ => None,
+        }
+    }
+}
+
+/// What is known about an individual at the moment it was evaluated for the first time.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LineageEntry {
+    pub id: usize,
+    /// The generation the individual was created in.
+    pub generation: usize,
+    /// The ids of its parents. Empty for the initial population, two parents for crossover.
+    pub parents: Vec<usize>,
+    pub operator: Operator,
+    pub fitness: f64,
+    /// The highest fitness among the parents when the individual was created.
+    pub parent_fitness: Option<f64>,
+    pub source_code: String,
+}
+
+impl LineageEntry {
+    /// Returns true if the individual is fitter than the fittest of its parents.
+    pub fn is_improvement(&self) -> bool {
+        self.parent_fitness.is_some_and(|parent_fitness| self.fitness > parent_fitness)
+    }
+}
+
+/// All individuals of a run, by id.
+#[derive(Debug, Clone, Default)]
+pub struct Lineage {
+    entries: BTreeMap<usize, LineageEntry>,
+}
+
+impl Lineage {
+    pub fn new() -> Lineage {
+        Lineage { entries: BTreeMap::new() }
+    }
+
+    pub fn record(&mut self, entry: LineageEntry) {
+        self.entries.insert(entry.id, entry);
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    pub fn get(&self, id: usize) -> Option<&LineageEntry> {
+        self.entries.get(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the individuals that were created in the given generation, ordered by id.
+    pub fn born_in(&self, generation: usize) -> Vec<&LineageEntry> {
+        self.entries.values().filter(|entry| entry.generation == generation).collect()
+    }
+
+    /// Returns the individual with the given id and all of its recorded ancestors, ordered by id.
+    pub fn ancestry(&self, id: usize) -> Vec<&LineageEntry> {
+        let mut visited = BTreeSet::new();
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            if let Some(entry) = self.entries.get(&id) {
+                if visited.insert(id) {
+                    pending.extend(entry.parents.iter().cloned());
+                }
+            }
+        }
+        visited.iter().map(|id| &self.entries[id]).collect()
+    }
+
+    /// Writes the ancestry of an individual as a Graphviz DOT graph, with an arrow from every
+    /// parent to its offspring.
+    pub fn to_dot(&self, id: usize) -> String {
+        let ancestry = self.ancestry(id);
+        let mut dot = String::from("digraph lineage {\n    node [shape=box];\n");
+        for entry in &ancestry {
+            writeln!(dot,
+                     "    {} [label=\"#{} (generation {})\\n{}, fitness {}\\n{}\"];",
+                     entry.id,
+                     entry.id,
+                     entry.generation,
+                     entry.operator.name(),
+                     entry.fitness,
+                     escape_dot(&entry.source_code))
+                .unwrap();
+        }
+        for entry in &ancestry {
+            for parent in entry.parents.iter().filter(|parent| self.contains(**parent)) {
+                writeln!(dot, "    {} -> {};", parent, entry.id).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes the ancestry of an individual as a Newick tree, with the individual at the root and
+    /// its parents as children. Ancestors shared through crossover appear once for every path to
+    /// them, so only the given number of generations of ancestors is included.
+    pub fn to_newick(&self, id: usize, max_depth: usize) -> String {
+        let mut newick = String::new();
+        self.write_newick(id, max_depth, &mut newick);
+        newick.push(';');
+        newick
+    }
+
+    fn write_newick(&self, id: usize, depth: usize, newick: &mut String) {
+        let entry = match self.entries.get(&id) {
+            Some(entry) => entry,
+            None => return,
+        };
+        let parents: Vec<usize> =
+            entry.parents.iter().cloned().filter(|&parent| self.contains(parent)).collect();
+        if depth > 0 && !parents.is_empty() {
+            newick.push('(');
+            for (index, &parent) in parents.iter().enumerate() {
+                if index > 0 {
+                    newick.push(',');
+                }
+                self.write_newick(parent, depth - 1, newick);
+            }
+            newick.push(')');
+        }
+        write!(newick, "{}_{}", entry.operator.name(), entry.id).unwrap();
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    fn make_entry(id: usize, parents: Vec<usize>, operator: Operator) -> LineageEntry {
+        LineageEntry {
+            id,
+            generation: if parents.is_empty() { 0 } else { 1 },
+            parents,
+            operator,
+            fitness: id as f64,
+            parent_fitness: None,
+            source_code: ">".to_string(),
+        }
+    }
+
+    fn make_lineage() -> Lineage {
+        let mut lineage = Lineage::new();
+        lineage.record(make_entry(0, vec![], Operator::Random));
+        lineage.record(make_entry(1, vec![], Operator::Random));
+        lineage.record(make_entry(2, vec![], Operator::Random));
+        lineage.record(make_entry(3, vec![0, 1], Operator::Crossover));
+        lineage
+    }
+
+    #[test]
+    fn ancestry_followsAllParents() {
+        let ids: Vec<usize> = make_lineage().ancestry(3).iter().map(|entry| entry.id).collect();
+        assert_eq!(ids, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn bornIn_filtersOnGeneration() {
+        assert_eq!(make_lineage().born_in(1).len(), 1);
+    }
+
+    #[test]
+    fn toDot_hasEdgeFromEveryParent() {
+        let dot = make_lineage().to_dot(3);
+        assert!(dot.contains("    0 -> 3;"));
+        assert!(dot.contains("    1 -> 3;"));
+        assert!(!dot.contains("    2 "));
+    }
+
+    #[test]
+    fn toNewick_parentsAreChildren() {
+        assert_eq!(make_lineage().to_newick(3, 10), "(random_0,random_1)crossover_3;");
+    }
+
+    #[test]
+    fn toNewick_maxDepthZero_onlyRoot() {
+        assert_eq!(make_lineage().to_newick(3, 0), "crossover_3;");
+    }
+
+    #[test]
+    fn isImprovement_comparesWithFittestParent() {
+        let mut entry = make_entry(3, vec![0, 1], Operator::Crossover);
+        entry.parent_fitness = Some(2.0);
+        assert!(entry.is_improvement());
+        entry.parent_fitness = Some(3.0);
+        assert!(!entry.is_improvement());
+    }
+}
@@ -0,0 +1,228 @@
+//! Local search around a single program.
+//! Starting from a seed bot, small edits are tried one at a time: changing the iteration count of
+//! a for loop, replacing, inserting or removing a single instruction, and inserting a `.` to
+//! shift the timing of everything after it. Edits that don't make the bot worse are kept. With
+//! simulated annealing, worse edits are sometimes kept as well, less and less often as the search
+//! cools down, which lets the search walk out of a local optimum.
+//!
+//! The search can polish a hand-written bot on its own, or refine offspring as a memetic step
+//! inside an `Evolution`.
+
+use bf_bot_core::bf::Bot;
+use bf_bot_core::simul_round::RoundParams;
+use evolution::evaluate_weighted;
+use genome::{Genome, Token};
+use operators::Operator;
+use rng::Rng;
+
+/// Settings for a local search.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LocalSearchParams {
+    /// Number of edits that are tried.
+    pub iterations: usize,
+    /// If set, worse edits are sometimes accepted.
+    pub annealing: Option<Annealing>,
+}
+
+impl Default for LocalSearchParams {
+    fn default() -> LocalSearchParams {
+        LocalSearchParams {
+            iterations: 100,
+            annealing: None,
+        }
+    }
+}
+
+/// The cooling schedule of simulated annealing. An edit that loses `d` points is accepted with
+/// probability `exp(-d / temperature)`, and the temperature is multiplied by the cooling rate
+/// after every edit.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Annealing {
+    pub initial_temperature: f64,
+    pub cooling_rate: f64,
+}
+
+impl Default for Annealing {
+    fn default() -> Annealing {
+        Annealing {
+            initial_temperature: 2.0,
+            cooling_rate: 0.97,
+        }
+    }
+}
+
+/// Settings for refining offspring with a local search during evolution.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemeticParams {
+    /// Probability that an offspring is refined before it is evaluated.
+    pub rate: f64,
+    pub search: LocalSearchParams,
+}
+
+impl Default for MemeticParams {
+    fn default() -> MemeticParams {
+        MemeticParams {
+            rate: 0.1,
+            search: LocalSearchParams {
+                iterations: 20,
+                annealing: None,
+            },
+        }
+    }
+}
+
+/// The outcome of a local search.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Refinement {
+    /// The best program found. If no edit improved on the seed, this is the last accepted
+    /// program that is as fit as the seed.
+    pub genome: Genome,
+    pub fitness: f64,
+    /// The fitness of the seed program.
+    pub initial_fitness: f64,
+    /// Number of edits that were accepted.
+    pub nr_accepted: usize,
+}
+
+/// The edits that a local search tries, with their relative weights.
+const EDITS: [(Edit, f64); 5] = [(Edit::Operator(Operator::RepeatCount), 0.3),
+                                 (Edit::Operator(Operator::PointMutation), 0.3),
+                                 (Edit::InsertWait, 0.2),
+                                 (Edit::Operator(Operator::Insertion), 0.1),
+                                 (Edit::Operator(Operator::Deletion), 0.1)];
+
+#[derive(Debug, Clone, Copy)]
+enum Edit {
+    Operator(Operator),
+    /// Inserts a `.` at a random position.
+    InsertWait,
+}
+
+impl Edit {
+    fn apply(&self, genome: &Genome, rng: &mut Rng) -> Genome {
+        match *self {
+            Edit::Operator(operator) => operator.apply(genome, genome, rng),
+            Edit::InsertWait => {
+                let mut edited = genome.clone();
+                let index = rng.below(genome.len() + 1);
+                edited.splice(index, index, &[Token::SkipExecution]);
+                edited
+            }
+        }
+    }
+}
+
+/// Searches the neighbourhood of a program for one that scores more points against the
+/// opponents, in a game of the given rounds. The points against each opponent are multiplied by
+/// its weight, as in `evolution::evaluate_weighted`.
+pub fn refine(seed: &Genome,
+              opponents: &[Bot],
+              opponent_weights: &[f64],
+              rounds: &[RoundParams],
+              params: &LocalSearchParams,
+              rng: &mut Rng)
+              -> Refinement {
+    let initial_fitness = evaluate_weighted(&seed.to_bot(), opponents, opponent_weights, rounds);
+    let mut current = (seed.clone(), initial_fitness);
+    let mut best = current.clone();
+    let mut temperature =
+        params.annealing.as_ref().map_or(0.0, |annealing| annealing.initial_temperature);
+    let weights: Vec<f64> = EDITS.iter().map(|&(_, weight)| weight).collect();
+    let mut nr_accepted = 0;
+    for _ in 0..params.iterations {
+        let edit = EDITS[rng.weighted_index(&weights).unwrap()].0;
+        let candidate = edit.apply(&current.0, rng);
+        if candidate == current.0 {
+            continue;
+        }
+        let fitness = evaluate_weighted(&candidate.to_bot(), opponents, opponent_weights, rounds);
+        let accept = fitness >= current.1 ||
+                     (temperature > 0.0 && rng.chance(((fitness - current.1) / temperature).exp()));
+        if accept {
+            current = (candidate, fitness);
+            nr_accepted += 1;
+            // Among equally fit programs, prefer the one found last, so that neutral edits
+            // aren't lost.
+            if current.1 >= best.1 {
+                best = current.clone();
+            }
+        }
+        if let Some(ref annealing) = params.annealing {
+            temperature *= annealing.cooling_rate;
+        }
+    }
+    Refinement {
+        genome: best.0,
+        fitness: best.1,
+        initial_fitness,
+        nr_accepted,
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    fn make_rounds() -> Vec<RoundParams> {
+        vec![RoundParams {
+                 tape_length: 10,
+                 invert_polarity: false,
+                 max_steps: 1_000,
+             }]
+    }
+
+    fn make_opponents() -> Vec<Bot> {
+        vec![Genome::from_source(".").unwrap().to_bot()]
+    }
+
+    #[test]
+    fn refine_neverReturnsWorseThanSeed() {
+        let seed = Genome::from_source("(>)*5[-]").unwrap();
+        let mut rng = Rng::new(3);
+        let params = LocalSearchParams::default();
+        let refinement = refine(&seed, &make_opponents(), &[], &make_rounds(), &params, &mut rng);
+        assert!(refinement.fitness >= refinement.initial_fitness);
+    }
+
+    #[test]
+    fn refine_nearlyRightRepeatCount_findsWinner() {
+        // The enemy flag is 9 cells away, so the seed stops one cell short.
+        let seed = Genome::from_source("(>)*8[-]").unwrap();
+        let params = LocalSearchParams {
+            iterations: 200,
+            annealing: None,
+        };
+        let mut rng = Rng::new(1);
+        let refinement = refine(&seed, &make_opponents(), &[], &make_rounds(), &params, &mut rng);
+        assert_eq!(refinement.initial_fitness, 0.0);
+        assert_eq!(refinement.fitness, 1.0);
+    }
+
+    #[test]
+    fn refine_withOpponentWeights_weighsFitness() {
+        let seed = Genome::from_source("<").unwrap();
+        let params = LocalSearchParams {
+            iterations: 0,
+            annealing: None,
+        };
+        let refinement =
+            refine(&seed, &make_opponents(), &[3.0], &make_rounds(), &params, &mut Rng::new(1));
+        assert_eq!(refinement.initial_fitness, -3.0);
+    }
+
+    #[test]
+    fn refine_withAnnealing_keepsBestFound() {
+        let seed = Genome::from_source("(>)*9[-]").unwrap();
+        let params = LocalSearchParams {
+            iterations: 50,
+            annealing: Some(Annealing {
+                initial_temperature: 100.0,
+                cooling_rate: 0.9,
+            }),
+        };
+        let mut rng = Rng::new(1);
+        let refinement = refine(&seed, &make_opponents(), &[], &make_rounds(), &params, &mut rng);
+        assert_eq!(refinement.fitness, 1.0);
+    }
+}
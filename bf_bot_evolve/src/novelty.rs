@@ -0,0 +1,249 @@
+//! Novelty search.
+//! Rather than rewarding bots for winning, novelty search rewards them for behaving differently
+//! from every bot seen before. A bot's behaviour is characterised by tracing it through a set of
+//! reference rounds: where it is on the tape over time, and which cells it modifies. Its novelty
+//! is the average distance to its k nearest neighbours among the current population and an
+//! archive of earlier novel behaviours. This helps evolution escape deceptive local optima, such
+//! as the trivial rushers that plain fitness tends to converge on.
+
+use bf_bot_core::bf::Bot;
+use bf_bot_core::simul_round::{self, RoundParams, RoundTrace};
+
+/// Settings for novelty search.
+#[derive(Debug, Clone)]
+pub struct NoveltyParams {
+    /// The rounds that each bot plays against every opponent to characterise its behaviour.
+    pub reference_rounds: Vec<RoundParams>,
+    /// The position of a bot is sampled once every this many steps.
+    pub sample_interval: u32,
+    /// Number of nearest neighbours that the novelty of a behaviour is averaged over.
+    pub k: usize,
+    /// Behaviours with a novelty above this threshold are added to the archive.
+    pub archive_threshold: f64,
+    /// Maximum number of behaviours in the archive. The oldest are dropped first.
+    pub max_archive_size: usize,
+    /// Weight of the plain fitness in the selection score. Set to zero to select on novelty
+    /// alone.
+    pub fitness_weight: f64,
+    /// Weight of the novelty in the selection score.
+    pub novelty_weight: f64,
+}
+
+impl Default for NoveltyParams {
+    fn default() -> NoveltyParams {
+        NoveltyParams {
+            reference_rounds: [10, 20, 30]
+                .iter()
+                .flat_map(|&tape_length| {
+                    vec![false, true].into_iter().map(move |invert_polarity| {
+                        RoundParams {
+                            tape_length,
+                            invert_polarity,
+                            max_steps: 1_000,
+                        }
+                    })
+                })
+                .collect(),
+            sample_interval: 50,
+            k: 15,
+            archive_threshold: 2.0,
+            max_archive_size: 1_000,
+            fitness_weight: 1.0,
+            novelty_weight: 1.0,
+        }
+    }
+}
+
+/// A point in behaviour space.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Behaviour {
+    features: Vec<f64>,
+}
+
+impl Behaviour {
+    /// Characterises the behaviour of a bot by tracing it, as the start bot, through every
+    /// reference round against every opponent.
+    pub fn characterise(bot: &Bot, opponents: &[Bot], params: &NoveltyParams) -> Behaviour {
+        let mut features = vec![];
+        for opponent in opponents {
+            for round_params in &params.reference_rounds {
+                let round_trace = simul_round::trace(bot, opponent, round_params);
+                features.extend(position_features(&round_trace, round_params, params));
+                features.extend(modified_cell_features(&round_trace));
+            }
+        }
+        Behaviour { features }
+    }
+
+    pub fn from_features(features: Vec<f64>) -> Behaviour {
+        Behaviour { features }
+    }
+
+    pub fn get_features(&self) -> &Vec<f64> {
+        &self.features
+    }
+
+    /// The Euclidean distance between two behaviours.
+    pub fn distance(&self, other: &Behaviour) -> f64 {
+        self.features
+            .iter()
+            .zip(other.features.iter())
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+/// The position of the bot relative to the length of the tape, sampled at fixed intervals. After
+/// the round has ended, the last known position is repeated.
+fn position_features(round_trace: &RoundTrace,
+                     round_params: &RoundParams,
+                     params: &NoveltyParams)
+                     -> Vec<f64> {
+    let interval = params.sample_interval.max(1) as usize;
+    let nr_samples = round_params.max_steps as usize / interval;
+    let furthest_cell = (round_params.tape_length - 1) as f64;
+    (1..nr_samples + 1)
+        .map(|sample| {
+            let step = (sample * interval).min(round_trace.steps.len());
+            match step {
+                0 => 0.0,
+                _ => round_trace.steps[step - 1].start_bot_pos as f64 / furthest_cell,
+            }
+        })
+        .collect()
+}
+
+/// For every cell, counted from the bot's own flag, whether the bot modified it at any time.
+fn modified_cell_features(round_trace: &RoundTrace) -> Vec<f64> {
+    let mut modified = vec![0.0; round_trace.initial_tape.len()];
+    for mutation in round_trace.steps.iter().filter_map(|state| state.start_bot_mutation) {
+        modified[mutation.get_index()] = 1.0;
+    }
+    modified
+}
+
+/// A collection of behaviours that were novel at the time they were encountered.
+#[derive(Debug, Clone, Default)]
+pub struct NoveltyArchive {
+    behaviours: Vec<Behaviour>,
+}
+
+impl NoveltyArchive {
+    pub fn new() -> NoveltyArchive {
+        NoveltyArchive { behaviours: vec![] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.behaviours.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.behaviours.is_empty()
+    }
+
+    /// Calculates the novelty of every behaviour in a population: the average distance to its k
+    /// nearest neighbours among the rest of the population and the archive.
+    pub fn novelty_of_population(&self, population: &[&Behaviour], k: usize) -> Vec<f64> {
+        population.iter()
+            .enumerate()
+            .map(|(index, behaviour)| {
+                let mut distances: Vec<f64> = population.iter()
+                    .enumerate()
+                    .filter(|&(other_index, _)| other_index != index)
+                    .map(|(_, other)| behaviour.distance(other))
+                    .chain(self.behaviours.iter().map(|other| behaviour.distance(other)))
+                    .collect();
+                mean_of_smallest(&mut distances, k)
+            })
+            .collect()
+    }
+
+    /// Adds a behaviour to the archive if its novelty exceeds the threshold, dropping the oldest
+    /// behaviour if the archive is full.
+    pub fn consider(&mut self, behaviour: &Behaviour, novelty: f64, params: &NoveltyParams) {
+        if novelty > params.archive_threshold && params.max_archive_size > 0 {
+            if self.behaviours.len() >= params.max_archive_size {
+                self.behaviours.remove(0);
+            }
+            self.behaviours.push(behaviour.clone());
+        }
+    }
+}
+
+fn mean_of_smallest(values: &mut [f64], k: usize) -> f64 {
+    if values.is_empty() || k == 0 {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let nearest = &values[..k.min(values.len())];
+    nearest.iter().sum::<f64>() / nearest.len() as f64
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use genome::Genome;
+
+    fn behaviour(features: Vec<f64>) -> Behaviour {
+        Behaviour::from_features(features)
+    }
+
+    #[test]
+    fn distance_isEuclidean() {
+        assert_eq!(behaviour(vec![0.0, 0.0]).distance(&behaviour(vec![3.0, 4.0])), 5.0);
+    }
+
+    #[test]
+    fn noveltyOfPopulation_averagesOverKNearestNeighbours() {
+        let archive = NoveltyArchive::new();
+        let a = behaviour(vec![0.0]);
+        let b = behaviour(vec![1.0]);
+        let c = behaviour(vec![3.0]);
+        let novelty = archive.novelty_of_population(&[&a, &b, &c], 1);
+        assert_eq!(novelty, vec![1.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn noveltyOfPopulation_includesArchive() {
+        let mut archive = NoveltyArchive::new();
+        let params = NoveltyParams { archive_threshold: 0.0, ..NoveltyParams::default() };
+        archive.consider(&behaviour(vec![10.0]), 1.0, &params);
+        let a = behaviour(vec![0.0]);
+        assert_eq!(archive.novelty_of_population(&[&a], 5), vec![10.0]);
+    }
+
+    #[test]
+    fn consider_belowThreshold_isNotArchived() {
+        let mut archive = NoveltyArchive::new();
+        let params = NoveltyParams { archive_threshold: 1.0, ..NoveltyParams::default() };
+        archive.consider(&behaviour(vec![0.0]), 0.5, &params);
+        assert!(archive.is_empty());
+    }
+
+    #[test]
+    fn consider_archiveFull_dropsOldest() {
+        let mut archive = NoveltyArchive::new();
+        let params = NoveltyParams {
+            archive_threshold: 0.0,
+            max_archive_size: 1,
+            ..NoveltyParams::default()
+        };
+        archive.consider(&behaviour(vec![0.0]), 1.0, &params);
+        archive.consider(&behaviour(vec![5.0]), 1.0, &params);
+        assert_eq!(archive.len(), 1);
+        assert_eq!(archive.novelty_of_population(&[&behaviour(vec![0.0])], 1), vec![5.0]);
+    }
+
+    #[test]
+    fn characterise_rusherAndIdler_differ() {
+        let params = NoveltyParams::default();
+        let opponents = vec![Genome::from_source(".").unwrap().to_bot()];
+        let rusher = Genome::from_source(">>>>>>>>").unwrap().to_bot();
+        let idler = Genome::from_source("........").unwrap().to_bot();
+        let rusher_behaviour = Behaviour::characterise(&rusher, &opponents, &params);
+        let idler_behaviour = Behaviour::characterise(&idler, &opponents, &params);
+        assert!(rusher_behaviour.distance(&idler_behaviour) > 0.0);
+    }
+}
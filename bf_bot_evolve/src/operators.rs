@@ -0,0 +1,226 @@
+//! Variation operators that create new genomes out of existing ones.
+//! Every operator keeps the brackets of the genome matched, so each offspring compiles into a
+//! valid bot.
+
+use genome::{Genome, Token, SIMPLE_TOKENS};
+use rng::Rng;
+
+/// The largest iteration count that operators will give a for loop.
+pub const MAX_NR_ITERATIONS: usize = 30;
+
+/// The ways in which an individual can come into existence.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Operator {
+    /// A genome generated from scratch.
+    Random,
+    /// A piece of the second parent replaces a piece of the first parent.
+    Crossover,
+    /// A single instruction is replaced by another one.
+    PointMutation,
+    /// A single instruction is inserted.
+    Insertion,
+    /// A single instruction is removed.
+    Deletion,
+    /// A piece of the program is wrapped in a new loop.
+    WrapLoop,
+    /// A loop is removed, leaving its body in place.
+    UnwrapLoop,
+    /// The iteration count of a for loop is changed.
+    RepeatCount,
+}
+
+/// All operators that produce offspring out of one or two parents, in a fixed order.
+pub const VARIATION_OPERATORS: [Operator; 7] = [Operator::Crossover,
+                                                Operator::PointMutation,
+                                                Operator::Insertion,
+                                                Operator::Deletion,
+                                                Operator::WrapLoop,
+                                                Operator::UnwrapLoop,
+                                                Operator::RepeatCount];
+
+impl Operator {
+    /// Returns true if this operator needs a second parent.
+    pub fn is_binary(&self) -> bool {
+        *self == Operator::Crossover
+    }
+
+    /// A short, lower case name for use in logs and file formats.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Operator::Random => "random",
+            Operator::Crossover => "crossover",
+            Operator::PointMutation => "point_mutation",
+            Operator::Insertion => "insertion",
+            Operator::Deletion => "deletion",
+            Operator::WrapLoop => "wrap_loop",
+            Operator::UnwrapLoop => "unwrap_loop",
+            Operator::RepeatCount => "repeat_count",
+        }
+    }
+
+    /// Applies the operator. The second parent is only used by binary operators. If the operator
+    /// can't be applied to the parent, for example a deletion on an empty genome, the result is
+    /// an unchanged copy.
+    pub fn apply(&self, parent: &Genome, other_parent: &Genome, rng: &mut Rng) -> Genome {
+        let mut genome = parent.clone();
+        match *self {
+            Operator::Random => genome = random_genome(parent.len().max(1), rng),
+            Operator::Crossover => crossover(&mut genome, other_parent, rng),
+            Operator::PointMutation => point_mutation(&mut genome, rng),
+            Operator::Insertion => insertion(&mut genome, rng),
+            Operator::Deletion => deletion(&mut genome, rng),
+            Operator::WrapLoop => wrap_loop(&mut genome, rng),
+            Operator::UnwrapLoop => unwrap_loop(&mut genome, rng),
+            Operator::RepeatCount => repeat_count(&mut genome, rng),
+        }
+        genome
+    }
+}
+
+/// Generates a genome of roughly the given length out of random instructions, with a few loops
+/// sprinkled in.
+pub fn random_genome(length: usize, rng: &mut Rng) -> Genome {
+    let tokens = (0..length).map(|_| random_simple_token(rng)).collect();
+    let mut genome = Genome::new(tokens);
+    for _ in 0..length / 8 {
+        wrap_loop(&mut genome, rng);
+    }
+    genome
+}
+
+pub fn random_simple_token(rng: &mut Rng) -> Token {
+    *rng.choose(&SIMPLE_TOKENS).unwrap()
+}
+
+fn indices_where<F>(genome: &Genome, predicate: F) -> Vec<usize>
+    where F: Fn(&Token) -> bool
+{
+    genome.get_tokens()
+        .iter()
+        .enumerate()
+        .filter(|&(_, token)| predicate(token))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn crossover(genome: &mut Genome, other_parent: &Genome, rng: &mut Rng) {
+    let (start, end) = random_balanced_range(genome, rng);
+    let (donor_start, donor_end) = random_balanced_range(other_parent, rng);
+    let donation = other_parent.get_tokens()[donor_start..donor_end].to_vec();
+    genome.splice(start, end, &donation);
+}
+
+fn random_balanced_range(genome: &Genome, rng: &mut Rng) -> (usize, usize) {
+    let start = rng.below(genome.len() + 1);
+    *rng.choose(&genome.balanced_ranges_from(start)).unwrap()
+}
+
+fn point_mutation(genome: &mut Genome, rng: &mut Rng) {
+    if let Some(&index) = rng.choose(&indices_where(genome, Token::is_simple)) {
+        let token = random_simple_token(rng);
+        genome.splice(index, index + 1, &[token]);
+    }
+}
+
+fn insertion(genome: &mut Genome, rng: &mut Rng) {
+    let index = rng.below(genome.len() + 1);
+    let token = random_simple_token(rng);
+    genome.splice(index, index, &[token]);
+}
+
+fn deletion(genome: &mut Genome, rng: &mut Rng) {
+    if let Some(&index) = rng.choose(&indices_where(genome, Token::is_simple)) {
+        genome.splice(index, index + 1, &[]);
+    }
+}
+
+fn wrap_loop(genome: &mut Genome, rng: &mut Rng) {
+    let (start, end) = random_balanced_range(genome, rng);
+    let (opening, closing) = if rng.chance(0.5) {
+        (Token::StartWhileNotZero, Token::EndWhileNotZero)
+    } else {
+        (Token::StartFor, Token::EndFor { nr_iterations: rng.in_range(2, MAX_NR_ITERATIONS) })
+    };
+    let mut body = vec![opening];
+    body.extend_from_slice(&genome.get_tokens()[start..end]);
+    body.push(closing);
+    genome.splice(start, end, &body);
+}
+
+fn unwrap_loop(genome: &mut Genome, rng: &mut Rng) {
+    if let Some(&opening) = rng.choose(&indices_where(genome, Token::is_opening)) {
+        let closing = genome.matching_bracket(opening).unwrap();
+        let body = genome.get_tokens()[opening + 1..closing].to_vec();
+        genome.splice(opening, closing + 1, &body);
+    }
+}
+
+fn repeat_count(genome: &mut Genome, rng: &mut Rng) {
+    let is_end_for = |token: &Token| matches!(*token, Token::EndFor { .. });
+    if let Some(&index) = rng.choose(&indices_where(genome, is_end_for)) {
+        if let Token::EndFor { nr_iterations } = genome.get_tokens()[index] {
+            let nr_iterations = adjust_count(nr_iterations, rng);
+            genome.splice(index, index + 1, &[Token::EndFor { nr_iterations }]);
+        }
+    }
+}
+
+/// Nudges an iteration count up or down by a small amount, staying within
+/// `[1, MAX_NR_ITERATIONS]`.
+pub fn adjust_count(nr_iterations: usize, rng: &mut Rng) -> usize {
+    let delta = rng.in_range(1, 3);
+    if rng.chance(0.5) {
+        (nr_iterations + delta).min(MAX_NR_ITERATIONS)
+    } else {
+        nr_iterations.saturating_sub(delta).max(1)
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use genome::Genome;
+    use rng::Rng;
+
+    #[test]
+    fn apply_anyOperator_producesCompilableGenomes() {
+        let mut rng = Rng::new(3);
+        let mut genome = Genome::from_source("+[>(-)*4]<").unwrap();
+        let other = Genome::from_source("[[.]>]").unwrap();
+        for _ in 0..500 {
+            let operator = *rng.choose(&VARIATION_OPERATORS).unwrap();
+            genome = operator.apply(&genome, &other, &mut rng);
+            assert_eq!(Genome::from_source(&genome.to_source()), Ok(genome.clone()));
+        }
+    }
+
+    #[test]
+    fn apply_deletionOnEmptyGenome_returnsEmptyGenome() {
+        let mut rng = Rng::new(3);
+        let genome = Genome::default();
+        assert!(Operator::Deletion.apply(&genome, &genome, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn apply_unwrapLoop_keepsLoopBody() {
+        let mut rng = Rng::new(3);
+        let genome = Genome::from_source("[+>]").unwrap();
+        let offspring = Operator::UnwrapLoop.apply(&genome, &genome, &mut rng);
+        assert_eq!(offspring.to_source(), "+>");
+    }
+
+    #[test]
+    fn adjustCount_staysWithinBounds() {
+        let mut rng = Rng::new(3);
+        assert!((0..100).all(|_| adjust_count(1, &mut rng) >= 1));
+        assert!((0..100).all(|_| adjust_count(MAX_NR_ITERATIONS, &mut rng) <= MAX_NR_ITERATIONS));
+    }
+
+    #[test]
+    fn randomGenome_hasRequestedNumberOfSimpleInstructions() {
+        let mut rng = Rng::new(3);
+        let genome = random_genome(16, &mut rng);
+        assert_eq!(genome.get_tokens().iter().filter(|token| token.is_simple()).count(), 16);
+    }
+}
@@ -0,0 +1,255 @@
+//! Multi-objective selection in the style of NSGA-II.
+//! Instead of collapsing everything into one fitness value, every individual is scored on several
+//! objectives. Individuals are ranked into fronts of mutually non-dominated solutions, and within
+//! a front, individuals in sparsely populated regions are preferred. The result of a run is the
+//! first front: the bots that offer the best available trade-offs, for example between
+//! robustness and aggression.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use bf_bot_core::simul_game::game_record::GameRecord;
+use evolution::opponent_weight;
+use genome::Genome;
+
+/// A quantity that Pareto selection takes into account.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Objective {
+    /// Total points against all opponents. Maximised.
+    TotalPoints,
+    /// The points scored at the tape length where the bot did worst. Maximised.
+    WorstTapeLength,
+    /// Points scored in the rounds where the opponent has reversed polarity. Maximised.
+    ReversedPolarityPoints,
+    /// Length of the program with all for loops written out. Minimised.
+    ExpandedLength,
+    /// Average number of steps the bot needs to win a round. Minimised.
+    CyclesToWin,
+}
+
+impl Objective {
+    pub fn is_minimised(&self) -> bool {
+        *self == Objective::ExpandedLength || *self == Objective::CyclesToWin
+    }
+
+    /// Measures the objective for a genome, given the records of its games against every
+    /// opponent and the weights of those opponents, as in `evolution::opponent_weight`. The
+    /// weights only apply to the objectives that count points. Minimised objectives are negated,
+    /// so that a larger value is always better.
+    pub fn measure(&self, genome: &Genome, records: &[GameRecord], weights: &[f64]) -> f64 {
+        let rounds = || records.iter().flat_map(|record| record.rounds.iter());
+        let weighted_rounds = || {
+            records.iter().enumerate().flat_map(|(opponent, record)| {
+                let weight = opponent_weight(weights, opponent);
+                record.rounds.iter().map(move |round| (weight, round))
+            })
+        };
+        let value = match *self {
+            Objective::TotalPoints => {
+                weighted_rounds().map(|(weight, round)| weight * round.bot_a_points() as f64).sum()
+            }
+            Objective::WorstTapeLength => {
+                let mut points_per_length = BTreeMap::new();
+                for (weight, round) in weighted_rounds() {
+                    *points_per_length.entry(round.round_params.tape_length).or_insert(0.0) +=
+                        weight * round.bot_a_points() as f64;
+                }
+                points_per_length.values().cloned().reduce(f64::min).unwrap_or(0.0)
+            }
+            Objective::ReversedPolarityPoints => {
+                weighted_rounds()
+                    .filter(|(_, round)| round.round_params.invert_polarity)
+                    .map(|(weight, round)| weight * round.bot_a_points() as f64)
+                    .sum()
+            }
+            Objective::ExpandedLength => genome.expanded_len() as f64,
+            Objective::CyclesToWin => {
+                // Rounds that weren't won count as taking the maximum number of steps.
+                let steps: Vec<f64> = rounds()
+                    .map(|round| if round.bot_a_points() > 0 {
+                        round.steps as f64
+                    } else {
+                        round.round_params.max_steps as f64
+                    })
+                    .collect();
+                if steps.is_empty() {
+                    0.0
+                } else {
+                    steps.iter().sum::<f64>() / steps.len() as f64
+                }
+            }
+        };
+        if self.is_minimised() { -value } else { value }
+    }
+}
+
+/// Returns true if solution a is at least as good as solution b on every objective, and better
+/// on at least one. Larger values are better.
+pub fn dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b.iter()).all(|(x, y)| x >= y) && a.iter().zip(b.iter()).any(|(x, y)| x > y)
+}
+
+/// Sorts solutions into fronts. The first front contains all solutions that no other solution
+/// dominates, the second front those that are only dominated by the first front, and so on.
+/// Returns the indices of the solutions in each front.
+pub fn non_dominated_sort(values: &[Vec<f64>]) -> Vec<Vec<usize>> {
+    let mut dominated_by_count = vec![0; values.len()];
+    let mut dominates_list: Vec<Vec<usize>> = vec![vec![]; values.len()];
+    for i in 0..values.len() {
+        for j in 0..values.len() {
+            if dominates(&values[i], &values[j]) {
+                dominates_list[i].push(j);
+            } else if dominates(&values[j], &values[i]) {
+                dominated_by_count[i] += 1;
+            }
+        }
+    }
+    let mut fronts = vec![];
+    let mut current: Vec<usize> =
+        (0..values.len()).filter(|&index| dominated_by_count[index] == 0).collect();
+    while !current.is_empty() {
+        let mut next = vec![];
+        for &index in &current {
+            for &dominated in &dominates_list[index] {
+                dominated_by_count[dominated] -= 1;
+                if dominated_by_count[dominated] == 0 {
+                    next.push(dominated);
+                }
+            }
+        }
+        next.sort();
+        fronts.push(current);
+        current = next;
+    }
+    fronts
+}
+
+/// Calculates the crowding distance of each solution in a front: how much room there is around
+/// it along every objective. The solutions at the extremes of an objective get an infinite
+/// distance, so they are always preserved. The result is in the same order as the front.
+pub fn crowding_distances(values: &[Vec<f64>], front: &[usize]) -> Vec<f64> {
+    let mut distances = vec![0.0; front.len()];
+    if front.is_empty() {
+        return distances;
+    }
+    let nr_objectives = values[front[0]].len();
+    let columns = (0..nr_objectives)
+        .map(|objective| front.iter().map(|&index| values[index][objective]).collect::<Vec<_>>());
+    for column in columns {
+        let mut order: Vec<usize> = (0..front.len()).collect();
+        order.sort_by(|&a, &b| column[a].partial_cmp(&column[b]).unwrap_or(Ordering::Equal));
+        let lowest = column[order[0]];
+        let highest = column[order[order.len() - 1]];
+        distances[order[0]] = f64::INFINITY;
+        distances[order[order.len() - 1]] = f64::INFINITY;
+        if highest == lowest {
+            continue;
+        }
+        for position in 1..order.len().saturating_sub(1) {
+            let gap = column[order[position + 1]] - column[order[position - 1]];
+            distances[order[position]] += gap / (highest - lowest);
+        }
+    }
+    distances
+}
+
+/// The crowded-comparison operator of NSGA-II: a lower front wins, and within a front, the larger
+/// crowding distance wins. Returns `Ordering::Greater` if a is better than b.
+pub fn crowded_compare(rank_a: usize, crowding_a: f64, rank_b: usize, crowding_b: f64) -> Ordering {
+    rank_b.cmp(&rank_a)
+        .then(crowding_a.partial_cmp(&crowding_b).unwrap_or(Ordering::Equal))
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf_bot_core::simul_game::game_record::{GameRecord, RoundRecord};
+    use bf_bot_core::simul_round::{RoundParams, RoundResult};
+    use genome::Genome;
+
+    fn make_record(tape_length: u32, invert_polarity: bool, result: RoundResult) -> RoundRecord {
+        RoundRecord {
+            round_params: RoundParams {
+                tape_length,
+                invert_polarity,
+                max_steps: 100,
+            },
+            result,
+            steps: 10,
+        }
+    }
+
+    fn make_game_record() -> GameRecord {
+        GameRecord {
+            rounds: vec![make_record(10, false, RoundResult::start_bot_wins()),
+                         make_record(10, true, RoundResult::start_bot_wins()),
+                         make_record(11, false, RoundResult::end_bot_wins()),
+                         make_record(11, true, RoundResult::draw())],
+        }
+    }
+
+    #[test]
+    fn dominates_betterOnOneEqualOnOther_dominates() {
+        assert!(dominates(&[2.0, 1.0], &[1.0, 1.0]));
+        assert!(!dominates(&[1.0, 1.0], &[1.0, 1.0]));
+        assert!(!dominates(&[2.0, 0.0], &[1.0, 1.0]));
+    }
+
+    #[test]
+    fn nonDominatedSort_splitsIntoFronts() {
+        let values = vec![vec![1.0, 1.0], vec![2.0, 2.0], vec![3.0, 0.0], vec![0.0, 0.0]];
+        assert_eq!(non_dominated_sort(&values), vec![vec![1, 2], vec![0], vec![3]]);
+    }
+
+    #[test]
+    fn crowdingDistances_extremesAreInfinite() {
+        let values = vec![vec![0.0, 4.0], vec![1.0, 3.0], vec![4.0, 0.0]];
+        let distances = crowding_distances(&values, &[0, 1, 2]);
+        assert!(distances[0].is_infinite());
+        assert!(distances[2].is_infinite());
+        assert_eq!(distances[1], 2.0);
+    }
+
+    #[test]
+    fn crowdedCompare_lowerRankWins() {
+        assert_eq!(crowded_compare(0, 0.0, 1, 5.0), Ordering::Greater);
+        assert_eq!(crowded_compare(1, 5.0, 1, 1.0), Ordering::Greater);
+    }
+
+    #[test]
+    fn measure_worstTapeLength_takesMinimumOverLengths() {
+        let genome = Genome::default();
+        let value = Objective::WorstTapeLength.measure(&genome, &[make_game_record()], &[]);
+        assert_eq!(value, -1.0);
+    }
+
+    #[test]
+    fn measure_withWeights_weighsPointsPerOpponent() {
+        let genome = Genome::default();
+        let records = [make_game_record(), make_game_record()];
+        assert_eq!(Objective::TotalPoints.measure(&genome, &records, &[2.0, 0.5]), 2.0 + 0.5);
+        assert_eq!(Objective::WorstTapeLength.measure(&genome, &records, &[2.0, 0.5]),
+                   -2.0 - 0.5);
+    }
+
+    #[test]
+    fn measure_reversedPolarityPoints_onlyCountsReversedRounds() {
+        let genome = Genome::default();
+        let value = Objective::ReversedPolarityPoints.measure(&genome, &[make_game_record()], &[]);
+        assert_eq!(value, 1.0);
+    }
+
+    #[test]
+    fn measure_cyclesToWin_countsLossesAsMaxSteps() {
+        let genome = Genome::default();
+        let value = Objective::CyclesToWin.measure(&genome, &[make_game_record()], &[]);
+        assert_eq!(value, -(10.0 + 10.0 + 100.0 + 100.0) / 4.0);
+    }
+
+    #[test]
+    fn measure_expandedLength_isNegated() {
+        let genome = Genome::from_source("(>)*5").unwrap();
+        assert_eq!(Objective::ExpandedLength.measure(&genome, &[], &[]), -5.0);
+    }
+}
@@ -0,0 +1,175 @@
+//! Racing candidates through evaluation stages of increasing cost.
+//! Playing complete games against every opponent is by far the most expensive part of an
+//! evolution run, and most offspring turn out to be weak after just a few rounds. Successive
+//! halving evaluates all candidates cheaply first, against a few opponents on a few short rounds,
+//! and only the best fraction moves on to the next stage, which has twice as many opponents and
+//! rounds. The last stage plays the full games against every opponent.
+
+use bf_bot_core::simul_game::game_record::GameRecord;
+use bf_bot_core::simul_round::RoundParams;
+use rng::Rng;
+
+/// The fitness that separates consecutive stages. It exceeds the difference between winning and
+/// losing a complete game of 42 rounds, so a candidate that reached a later stage is always
+/// fitter than one that was eliminated earlier.
+pub const STAGE_BONUS: f64 = 85.0;
+
+/// Settings for racing. With racing, the fitness of a candidate is the number of points it
+/// scored per game in the last stage it reached, scaled to 42 rounds and averaged over the
+/// opponents of that stage with their weights, plus `STAGE_BONUS` for every stage before it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RacingParams {
+    /// Number of stages, including the final one.
+    pub nr_stages: usize,
+    /// The fraction of candidates that moves on to the next stage.
+    pub keep_fraction: f64,
+    /// Number of opponents in the first stage.
+    pub initial_opponents: usize,
+    /// Number of rounds in the first stage.
+    pub initial_rounds: usize,
+    /// The maximum number of steps per round in the first stage.
+    pub initial_max_steps: u32,
+}
+
+impl Default for RacingParams {
+    fn default() -> RacingParams {
+        RacingParams {
+            nr_stages: 3,
+            keep_fraction: 0.5,
+            initial_opponents: 4,
+            initial_rounds: 6,
+            initial_max_steps: 1_000,
+        }
+    }
+}
+
+/// A single stage of a race.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Stage {
+    /// Indices of the opponents that are played in this stage.
+    pub opponents: Vec<usize>,
+    pub rounds: Vec<RoundParams>,
+}
+
+impl RacingParams {
+    /// Draws the opponents and rounds of every stage. Each stage doubles the number of opponents,
+    /// rounds and steps of the one before it, and includes all of its opponents and rounds. The
+    /// last stage has all opponents and rounds, with their own maximum number of steps.
+    pub fn plan(&self, nr_opponents: usize, rounds: &[RoundParams], rng: &mut Rng) -> Vec<Stage> {
+        let mut opponent_order: Vec<usize> = (0..nr_opponents).collect();
+        rng.shuffle(&mut opponent_order);
+        let mut round_order = rounds.to_vec();
+        rng.shuffle(&mut round_order);
+        let nr_stages = self.nr_stages.max(1);
+        (0..nr_stages)
+            .map(|stage| {
+                if stage + 1 == nr_stages {
+                    return Stage {
+                        opponents: (0..nr_opponents).collect(),
+                        rounds: rounds.to_vec(),
+                    };
+                }
+                let growth = 1 << stage.min(31);
+                let nr_stage_opponents =
+                    self.initial_opponents.saturating_mul(growth).max(1).min(nr_opponents);
+                let nr_stage_rounds =
+                    self.initial_rounds.saturating_mul(growth).max(1).min(rounds.len());
+                let max_steps = self.initial_max_steps.saturating_mul(growth as u32);
+                let mut stage_opponents = opponent_order[..nr_stage_opponents].to_vec();
+                stage_opponents.sort();
+                Stage {
+                    opponents: stage_opponents,
+                    rounds: round_order[..nr_stage_rounds]
+                        .iter()
+                        .map(|round_params| {
+                            RoundParams {
+                                max_steps: round_params.max_steps.min(max_steps),
+                                ..round_params.clone()
+                            }
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Number of the given candidates that move on to the next stage. At least one always does.
+    pub fn nr_survivors(&self, nr_candidates: usize) -> usize {
+        ((nr_candidates as f64 * self.keep_fraction).ceil() as usize).clamp(1, nr_candidates.max(1))
+    }
+}
+
+/// The fitness of a candidate that played the given games in the given stage, counted from 0.
+/// `weights` holds the weight of the opponent of each game; a weighted average stays within the
+/// range of a plain one, so the stages remain separated.
+pub fn stage_fitness(stage: usize, records: &[GameRecord], weights: &[f64]) -> f64 {
+    let nr_rounds: f64 = records.iter()
+        .zip(weights)
+        .map(|(record, weight)| weight * record.rounds.len() as f64)
+        .sum();
+    let points: f64 = records.iter()
+        .zip(weights)
+        .map(|(record, weight)| weight * record.to_result().bot_a_points as f64)
+        .sum();
+    let points_per_game = if nr_rounds == 0.0 {
+        0.0
+    } else {
+        42.0 * points / nr_rounds
+    };
+    stage as f64 * STAGE_BONUS + points_per_game
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf_bot_core::simul_game::AllRounds;
+    use bf_bot_core::simul_game::game_record::RoundRecord;
+    use bf_bot_core::simul_round::RoundResult;
+
+    #[test]
+    fn plan_stagesGrowAndEndWithEverything() {
+        let rounds: Vec<RoundParams> = AllRounds::new().collect();
+        let stages = RacingParams::default().plan(20, &rounds, &mut Rng::new(1));
+        assert_eq!(stages.len(), 3);
+        assert_eq!((stages[0].opponents.len(), stages[0].rounds.len()), (4, 6));
+        assert_eq!((stages[1].opponents.len(), stages[1].rounds.len()), (8, 12));
+        assert!(stages[0].opponents.iter().all(|opponent| stages[1].opponents.contains(opponent)));
+        assert!(stages[0].rounds.iter().all(|round| round.max_steps <= 1_000));
+        assert_eq!(stages[2].opponents, (0..20).collect::<Vec<usize>>());
+        assert_eq!(stages[2].rounds, rounds);
+    }
+
+    #[test]
+    fn plan_fewOpponents_stagesAreCapped() {
+        let rounds: Vec<RoundParams> = AllRounds::new().take(3).collect();
+        let stages = RacingParams::default().plan(2, &rounds, &mut Rng::new(1));
+        assert_eq!((stages[0].opponents.len(), stages[0].rounds.len()), (2, 3));
+    }
+
+    #[test]
+    fn nrSurvivors_roundsUpAndKeepsOne() {
+        let params = RacingParams::default();
+        assert_eq!(params.nr_survivors(9), 5);
+        assert_eq!(params.nr_survivors(1), 1);
+    }
+
+    #[test]
+    fn stageFitness_laterStageAlwaysWins() {
+        let round = |result| {
+            RoundRecord {
+                round_params: AllRounds::new().next().unwrap(),
+                result,
+                steps: 1,
+            }
+        };
+        let lost = GameRecord { rounds: vec![round(RoundResult::end_bot_wins()); 4] };
+        let won = GameRecord { rounds: vec![round(RoundResult::start_bot_wins()); 2] };
+        assert_eq!(stage_fitness(0, std::slice::from_ref(&won), &[1.0]), 42.0);
+        assert_eq!(stage_fitness(1, std::slice::from_ref(&lost), &[1.0]), STAGE_BONUS - 42.0);
+        assert!(stage_fitness(1, &[], &[]) > stage_fitness(0, std::slice::from_ref(&won), &[1.0]));
+        // Weighing the won game three times as heavily as the lost one, which has twice as many
+        // rounds: (3 * 2 - 4) / (3 * 2 + 4) of the rounds are won on balance.
+        assert_eq!(stage_fitness(0, &[won, lost], &[3.0, 1.0]), 42.0 * 2.0 / 10.0);
+    }
+}
@@ -0,0 +1,165 @@
+//! A small, seedable pseudo random number generator.
+//! Evolution runs have to be reproducible from their seed, so all randomness in this crate is
+//! drawn from an `Rng` that is passed around explicitly.
+
+/// Xorshift64* generator. Not suitable for cryptography, but fast and good enough to drive
+/// evolutionary search.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a new generator. Equal seeds produce equal sequences of numbers.
+    pub fn new(seed: u64) -> Rng {
+        // The state of a xorshift generator must never be zero. Scrambling the seed also keeps
+        // generators with similar seeds from producing similar sequences.
+        let mut state = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        state ^= state >> 31;
+        Rng { state: if state == 0 { 1 } else { state } }
+    }
+
+    /// Recreates a generator from a state previously returned by `get_state`, so that it
+    /// continues the exact same sequence.
+    pub fn from_state(state: u64) -> Rng {
+        Rng { state: if state == 0 { 1 } else { state } }
+    }
+
+    pub fn get_state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number in the range `[0, bound)`. The bound must be larger than zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "The bound of a random number must be larger than zero.");
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Returns a number in the inclusive range `[min, max]`.
+    pub fn in_range(&mut self, min: usize, max: usize) -> usize {
+        assert!(min <= max, "Empty range for random number.");
+        min + self.below(max - min + 1)
+    }
+
+    /// Returns a number in the range `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns true with the given probability.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    /// Picks a random element of the given slice, or None if it is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.below(items.len())])
+        }
+    }
+
+    /// Picks a random index into a list of non-negative weights, with a probability proportional
+    /// to the weight. Returns None if all weights are zero.
+    pub fn weighted_index(&mut self, weights: &[f64]) -> Option<usize> {
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut remaining = self.next_f64() * total;
+        for (index, weight) in weights.iter().enumerate() {
+            if remaining < *weight {
+                return Some(index);
+            }
+            remaining -= *weight;
+        }
+        weights.iter().rposition(|weight| *weight > 0.0)
+    }
+
+    /// Puts the elements of the slice in a random order.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            let other = self.below(index + 1);
+            items.swap(index, other);
+        }
+    }
+
+    /// Derives a new, independent generator from this one.
+    pub fn split(&mut self) -> Rng {
+        Rng::new(self.next_u64())
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nextU64_sameSeed_sameSequence() {
+        let mut rng_a = Rng::new(42);
+        let mut rng_b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(rng_a.next_u64(), rng_b.next_u64());
+        }
+    }
+
+    #[test]
+    fn nextU64_differentSeed_differentSequence() {
+        let mut rng_a = Rng::new(1);
+        let mut rng_b = Rng::new(2);
+        assert_ne!(rng_a.next_u64(), rng_b.next_u64());
+    }
+
+    #[test]
+    fn fromState_continuesSequence() {
+        let mut rng = Rng::new(42);
+        rng.next_u64();
+        let mut copy = Rng::from_state(rng.get_state());
+        assert_eq!(rng.next_u64(), copy.next_u64());
+    }
+
+    #[test]
+    fn below_staysWithinBound() {
+        let mut rng = Rng::new(7);
+        assert!((0..1000).all(|_| rng.below(3) < 3));
+    }
+
+    #[test]
+    fn nextF64_staysWithinUnitInterval() {
+        let mut rng = Rng::new(7);
+        assert!((0..1000).map(|_| rng.next_f64()).all(|value| (0.0..1.0).contains(&value)));
+    }
+
+    #[test]
+    fn shuffle_keepsAllElements() {
+        let mut rng = Rng::new(7);
+        let mut items: Vec<usize> = (0..20).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<usize>>());
+        items.sort();
+        assert_eq!(items, (0..20).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn weightedIndex_onlyOneNonZeroWeight_alwaysPicksIt() {
+        let mut rng = Rng::new(7);
+        assert!((0..100).all(|_| rng.weighted_index(&[0.0, 2.0, 0.0]) == Some(1)));
+    }
+
+    #[test]
+    fn weightedIndex_allWeightsZero_returnsNone() {
+        let mut rng = Rng::new(7);
+        assert_eq!(rng.weighted_index(&[0.0, 0.0]), None);
+    }
+}
@@ -0,0 +1,209 @@
+//! Building an initial population out of existing bots.
+//! Starting from random noise wastes most of a run on rediscovering the basics, such as running
+//! to the enemy flag and clearing it. Seeding starts the population with known bots instead. The
+//! population is then filled up with one of several kinds of programs: mutants of the seeds,
+//! random programs that follow the grammar of the language, or instances of classic strategies.
+
+use bloat::BloatParams;
+use genome::{Genome, Token, SIMPLE_TOKENS};
+use operators::{self, Operator};
+use rng::Rng;
+use template::Template;
+
+/// The ways to fill up a population that has fewer seeds than members.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FillStrategy {
+    /// Copies of random seeds with a few mutations each. Falls back to random programs if there
+    /// are no seeds.
+    Mutants,
+    /// Random programs with nested loops, generated from the grammar of the language.
+    Grammar,
+    /// Classic strategies, such as rushing to the enemy flag or setting up decoys first, with
+    /// random parameters.
+    Classic,
+}
+
+impl FillStrategy {
+    pub fn from_name(name: &str) -> Option<FillStrategy> {
+        match name {
+            "mutants" => Some(FillStrategy::Mutants),
+            "grammar" => Some(FillStrategy::Grammar),
+            "classic" => Some(FillStrategy::Classic),
+            _ => None,
+        }
+    }
+}
+
+/// Templates of classic strategies. Each clears the cells from the enemy's side of the tape
+/// onwards, after first skipping the cells that can't hold the enemy flag.
+const CLASSIC_TEMPLATES: [&str; 4] = [
+    // A plain rush.
+    "$skip = 2..12\n(>)*$skip([-]>)*21",
+    // Decoys slow down enemies that clear from the front.
+    "$decoys = 1..6\n$size = 1..30\n$skip = 0..8\n(>(+)*$size)*$decoys(>)*$skip([-]>)*21",
+    // Waiting lets the enemy set up its decoys first.
+    "$wait = 1..60\n$skip = 2..12\n(.)*$wait(>)*$skip([-]>)*21",
+    // An offset clear gets through small decoys quicker.
+    "$skip = 2..12\n$offset = 1..16\n(>)*$skip([(-)*$offset[+]]>)*21",
+];
+
+/// The operators that turn a seed into a mutant.
+const MUTATIONS: [Operator; 4] = [Operator::PointMutation,
+                                  Operator::Insertion,
+                                  Operator::Deletion,
+                                  Operator::RepeatCount];
+
+/// Number of times a program to fill the population with is generated again when it exceeds the
+/// size limits, before a program of only simple instructions is used instead.
+const MAX_FILL_ATTEMPTS: usize = 10;
+
+/// Returns a population of the given size, starting with the seeds. Surplus seeds are dropped.
+/// `program_length` is the length of random and grammar-generated programs. The seeds are taken
+/// as they are, but the programs the population is filled up with stay within the size limits,
+/// provided that `program_length` does.
+pub fn seed_population(seeds: &[Genome],
+                       population_size: usize,
+                       fill: FillStrategy,
+                       program_length: usize,
+                       bloat: &BloatParams,
+                       rng: &mut Rng)
+                       -> Vec<Genome> {
+    let mut population: Vec<Genome> = seeds.iter().take(population_size).cloned().collect();
+    while population.len() < population_size {
+        let mut genome = None;
+        for _ in 0..MAX_FILL_ATTEMPTS {
+            let candidate = match fill {
+                FillStrategy::Mutants if !seeds.is_empty() => mutant(seeds, rng),
+                FillStrategy::Mutants => operators::random_genome(program_length, rng),
+                FillStrategy::Grammar => grammar_genome(program_length, rng),
+                FillStrategy::Classic => classic_genome(rng),
+            };
+            if bloat.allows(&candidate) {
+                genome = Some(candidate);
+                break;
+            }
+        }
+        population.push(genome.unwrap_or_else(|| {
+            Genome::new((0..program_length).map(|_| operators::random_simple_token(rng)).collect())
+        }));
+    }
+    population
+}
+
+/// A random seed with one to three mutations.
+fn mutant(seeds: &[Genome], rng: &mut Rng) -> Genome {
+    let mut genome = rng.choose(seeds).unwrap().clone();
+    for _ in 0..rng.in_range(1, 3) {
+        genome = rng.choose(&MUTATIONS).unwrap().apply(&genome, &genome, rng);
+    }
+    genome
+}
+
+/// Generates a random program of the given length. Every position holds either a simple
+/// instruction, a while loop or a for loop, and loops contain programs of their own, up to a
+/// limited depth.
+pub fn grammar_genome(length: usize, rng: &mut Rng) -> Genome {
+    let mut tokens = vec![];
+    generate_block(length, 0, &mut tokens, rng);
+    Genome::new(tokens)
+}
+
+const MAX_LOOP_DEPTH: usize = 3;
+
+fn generate_block(length: usize, depth: usize, tokens: &mut Vec<Token>, rng: &mut Rng) {
+    let end = tokens.len() + length;
+    while tokens.len() < end {
+        let remaining = end - tokens.len();
+        if depth >= MAX_LOOP_DEPTH || remaining < 3 || rng.chance(0.7) {
+            tokens.push(*rng.choose(&SIMPLE_TOKENS).unwrap());
+            continue;
+        }
+        let body_length = rng.in_range(1, (remaining - 2).min(8));
+        if rng.chance(0.5) {
+            tokens.push(Token::StartWhileNotZero);
+            generate_block(body_length, depth + 1, tokens, rng);
+            tokens.push(Token::EndWhileNotZero);
+        } else {
+            tokens.push(Token::StartFor);
+            generate_block(body_length, depth + 1, tokens, rng);
+            tokens.push(Token::EndFor { nr_iterations: rng.in_range(1, 20) });
+        }
+    }
+}
+
+/// Instantiates a random classic strategy with random parameters.
+pub fn classic_genome(rng: &mut Rng) -> Genome {
+    let template = Template::parse(rng.choose(&CLASSIC_TEMPLATES).unwrap())
+        .expect("A classic template is invalid.");
+    let values: Vec<usize> =
+        template.get_holes().iter().map(|hole| rng.in_range(hole.min, hole.max)).collect();
+    template.instantiate_genome(&values).expect("A classic template is invalid.")
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seedPopulation_keepsSeedsFirst() {
+        let seeds = vec![Genome::from_source("[-]").unwrap(), Genome::from_source(">>").unwrap()];
+        let population = seed_population(&seeds,
+                                         5,
+                                         FillStrategy::Mutants,
+                                         10,
+                                         &BloatParams::default(),
+                                         &mut Rng::new(1));
+        assert_eq!(population.len(), 5);
+        assert_eq!(&population[..2], &seeds[..]);
+    }
+
+    #[test]
+    fn seedPopulation_moreSeedsThanMembers_dropsSurplus() {
+        let seeds = vec![Genome::from_source("+").unwrap(); 4];
+        let population = seed_population(&seeds,
+                                         3,
+                                         FillStrategy::Grammar,
+                                         10,
+                                         &BloatParams::default(),
+                                         &mut Rng::new(1));
+        assert_eq!(population.len(), 3);
+    }
+
+    #[test]
+    fn seedPopulation_mutantsWithoutSeeds_fallsBackToRandomPrograms() {
+        let population = seed_population(&[],
+                                         4,
+                                         FillStrategy::Mutants,
+                                         10,
+                                         &BloatParams::default(),
+                                         &mut Rng::new(1));
+        assert_eq!(population.len(), 4);
+    }
+
+    #[test]
+    fn seedPopulation_withMaxLength_fillStaysWithinLimit() {
+        let bloat = BloatParams {
+            max_length: Some(10),
+            ..BloatParams::default()
+        };
+        let population =
+            seed_population(&[], 20, FillStrategy::Classic, 10, &bloat, &mut Rng::new(1));
+        assert!(population.iter().all(|genome| bloat.allows(genome)));
+    }
+
+    #[test]
+    fn grammarGenome_hasRequestedLength() {
+        let mut rng = Rng::new(3);
+        assert!((0..50).all(|_| grammar_genome(20, &mut rng).len() == 20));
+    }
+
+    #[test]
+    fn classicGenome_everyTemplateIsValid() {
+        for source in &CLASSIC_TEMPLATES {
+            assert!(Template::parse(source).is_ok());
+        }
+        let mut rng = Rng::new(5);
+        assert!((0..20).all(|_| classic_genome(&mut rng).len() > 5));
+    }
+}
@@ -0,0 +1,260 @@
+//! Speciation, in the style of NEAT.
+//! The population is divided into species of structurally similar programs. Individuals share
+//! their fitness with the other members of their species, so a large species can't take over the
+//! whole population. A freshly introduced structure such as a new loop founds a young species,
+//! whose scores get a bonus for a few generations, so it can tune its surroundings before it has
+//! to compete with established programs.
+
+use genome::{Genome, Token};
+
+/// Settings for speciation.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpeciationParams {
+    /// Individuals whose distance to the representative of a species is at most this value
+    /// belong to that species.
+    pub compatibility_threshold: f64,
+    /// Probability that crossover takes its second parent from another species.
+    pub interspecies_crossover_rate: f64,
+    /// Number of generations that a new species is protected for.
+    pub youth_generations: usize,
+    /// The shared scores of the members of a protected species are multiplied by this factor.
+    pub youth_bonus: f64,
+}
+
+impl Default for SpeciationParams {
+    fn default() -> SpeciationParams {
+        SpeciationParams {
+            compatibility_threshold: 0.4,
+            interspecies_crossover_rate: 0.05,
+            youth_generations: 3,
+            youth_bonus: 1.5,
+        }
+    }
+}
+
+/// A group of structurally similar individuals.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Species {
+    id: usize,
+    /// New individuals are compared to this genome to determine whether they belong to the
+    /// species.
+    representative: Genome,
+    /// Indices into the population of the individuals that belong to this species.
+    members: Vec<usize>,
+    /// Number of generations this species has existed.
+    age: usize,
+}
+
+impl Species {
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_representative(&self) -> &Genome {
+        &self.representative
+    }
+
+    pub fn get_members(&self) -> &Vec<usize> {
+        &self.members
+    }
+
+    pub fn get_age(&self) -> usize {
+        self.age
+    }
+
+    pub(crate) fn set_members(&mut self, members: Vec<usize>) {
+        self.members = members;
+    }
+}
+
+/// Divides a population into species. Species of the previous generation are kept if any
+/// individual still resembles their representative; individuals that resemble none of them found
+/// a new species. Every species that survives takes its first member as the new representative.
+pub fn speciate(genomes: &[&Genome],
+                previous: &[Species],
+                next_id: &mut usize,
+                params: &SpeciationParams)
+                -> Vec<Species> {
+    let mut species: Vec<Species> = previous.iter()
+        .map(|species| {
+            Species {
+                id: species.id,
+                representative: species.representative.clone(),
+                members: vec![],
+                age: species.age + 1,
+            }
+        })
+        .collect();
+    for (index, genome) in genomes.iter().enumerate() {
+        let existing = species.iter_mut().find(|species| {
+            distance(genome, &species.representative) <= params.compatibility_threshold
+        });
+        match existing {
+            Some(species) => species.members.push(index),
+            None => {
+                species.push(Species {
+                    id: *next_id,
+                    representative: (*genome).clone(),
+                    members: vec![index],
+                    age: 0,
+                });
+                *next_id += 1;
+            }
+        }
+    }
+    species.retain(|species| !species.members.is_empty());
+    for species in &mut species {
+        species.representative = genomes[species.members[0]].clone();
+    }
+    species
+}
+
+/// Divides every score by the size of its species, and gives the members of young species their
+/// bonus. Scores are first shifted so that the lowest score in the population becomes slightly
+/// positive, otherwise sharing a negative score would reward large species instead of punishing
+/// them.
+pub fn share_scores(scores: &[f64], species: &[Species], params: &SpeciationParams) -> Vec<f64> {
+    let lowest = scores.iter().cloned().fold(0.0, f64::min);
+    let mut shared: Vec<f64> = scores.iter().map(|score| score - lowest + 1.0).collect();
+    for species in species {
+        let mut factor = 1.0 / species.members.len() as f64;
+        if species.age < params.youth_generations {
+            factor *= params.youth_bonus;
+        }
+        for &member in &species.members {
+            shared[member] *= factor;
+        }
+    }
+    shared
+}
+
+/// The structural distance between two programs: the edit distance between their token streams,
+/// divided by the length of the longer one. The result lies between 0 for equal programs and 1
+/// for programs that have nothing in common.
+pub fn distance(a: &Genome, b: &Genome) -> f64 {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        0.0
+    } else {
+        edit_distance(a.get_tokens(), b.get_tokens()) / longest as f64
+    }
+}
+
+/// Levenshtein distance between two token streams. Two for loops that only differ in their
+/// iteration count count as half a substitution.
+fn edit_distance(a: &[Token], b: &[Token]) -> f64 {
+    let mut previous_row: Vec<f64> = (0..b.len() + 1).map(|index| index as f64).collect();
+    for (i, token_a) in a.iter().enumerate() {
+        let mut row = vec![(i + 1) as f64];
+        for (j, token_b) in b.iter().enumerate() {
+            let substitution = previous_row[j] + substitution_cost(token_a, token_b);
+            let deletion = previous_row[j + 1] + 1.0;
+            let insertion = row[j] + 1.0;
+            row.push(substitution.min(deletion).min(insertion));
+        }
+        previous_row = row;
+    }
+    previous_row[b.len()]
+}
+
+fn substitution_cost(a: &Token, b: &Token) -> f64 {
+    match (*a, *b) {
+        _ if a == b => 0.0,
+        (Token::EndFor { .. }, Token::EndFor { .. }) => 0.5,
+        _ => 1.0,
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use genome::Genome;
+
+    fn genome(source: &str) -> Genome {
+        Genome::from_source(source).unwrap()
+    }
+
+    #[test]
+    fn distance_equalPrograms_isZero() {
+        assert_eq!(distance(&genome("[-]>"), &genome("[-]>")), 0.0);
+    }
+
+    #[test]
+    fn distance_nothingInCommon_isOne() {
+        assert_eq!(distance(&genome("++"), &genome("--")), 1.0);
+    }
+
+    #[test]
+    fn distance_oneInsertion_isNormalisedByLongerProgram() {
+        assert_eq!(distance(&genome("+>-"), &genome("+>>-")), 0.25);
+    }
+
+    #[test]
+    fn distance_onlyIterationCountDiffers_isHalfASubstitution() {
+        assert_eq!(distance(&genome("(>)*3"), &genome("(>)*4")), 0.5 / 3.0);
+    }
+
+    #[test]
+    fn speciate_similarProgramsShareSpecies() {
+        let genomes = [genome(">>>>[-]"), genome("--++"), genome(">>>>[+]")];
+        let references: Vec<&Genome> = genomes.iter().collect();
+        let mut next_id = 0;
+        let species = speciate(&references, &[], &mut next_id, &SpeciationParams::default());
+        assert_eq!(species.len(), 2);
+        assert_eq!(species[0].get_members(), &vec![0, 2]);
+        assert_eq!(species[1].get_members(), &vec![1]);
+        assert_eq!(next_id, 2);
+    }
+
+    #[test]
+    fn speciate_keepsIdOfPreviousSpecies() {
+        let genomes = [genome(">>>>[-]")];
+        let references: Vec<&Genome> = genomes.iter().collect();
+        let mut next_id = 0;
+        let params = SpeciationParams::default();
+        let first = speciate(&references, &[], &mut next_id, &params);
+        let second = speciate(&references, &first, &mut next_id, &params);
+        assert_eq!(second[0].get_id(), first[0].get_id());
+        assert_eq!(second[0].get_age(), 1);
+    }
+
+    #[test]
+    fn speciate_emptySpeciesAreDropped() {
+        let mut next_id = 0;
+        let params = SpeciationParams::default();
+        let old_genomes = [genome("++++")];
+        let old_references: Vec<&Genome> = old_genomes.iter().collect();
+        let first = speciate(&old_references, &[], &mut next_id, &params);
+        let new_genomes = [genome("<<<<")];
+        let new_references: Vec<&Genome> = new_genomes.iter().collect();
+        let second = speciate(&new_references, &first, &mut next_id, &params);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].get_id(), 1);
+    }
+
+    #[test]
+    fn shareScores_dividesByShiftedSpeciesSize() {
+        let genomes = [genome("++++"), genome("++++"), genome("<<<<")];
+        let references: Vec<&Genome> = genomes.iter().collect();
+        let mut next_id = 0;
+        let params = SpeciationParams { youth_generations: 0, ..SpeciationParams::default() };
+        let species = speciate(&references, &[], &mut next_id, &params);
+        assert_eq!(share_scores(&[3.0, 3.0, 3.0], &species, &params), vec![2.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn shareScores_youngSpecies_getBonusUntilTheyAge() {
+        let genomes = [genome("++++"), genome("<<<<")];
+        let references: Vec<&Genome> = genomes.iter().collect();
+        let mut next_id = 0;
+        let params = SpeciationParams {
+            youth_generations: 1,
+            youth_bonus: 2.0,
+            ..SpeciationParams::default()
+        };
+        let first = speciate(&references[..1], &[], &mut next_id, &params);
+        let second = speciate(&references, &first, &mut next_id, &params);
+        assert_eq!(share_scores(&[3.0, 3.0], &second, &params), vec![4.0, 8.0]);
+    }
+}
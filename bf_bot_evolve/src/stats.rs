@@ -0,0 +1,318 @@
+//! Statistics on the progress of an evolution run.
+//! After every generation, a line of statistics can be appended to a log, either as CSV for
+//! spreadsheets or as JSON lines for scripts.
+
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use bloat::BloatStats;
+use evolution::Evolution;
+use genome::Genome;
+use operators::{Operator, VARIATION_OPERATORS};
+use speciation;
+
+/// How often an operator was used in a generation, and how often it produced an offspring that
+/// was fitter than its parents.
+#[derive(Debug, PartialEq, Clone)]
+pub struct OperatorStats {
+    pub operator: Operator,
+    pub created: usize,
+    pub improved: usize,
+}
+
+impl OperatorStats {
+    /// The fraction of offspring that improved on their parents, or 0 if the operator wasn't
+    /// used.
+    pub fn success_rate(&self) -> f64 {
+        if self.created == 0 {
+            0.0
+        } else {
+            self.improved as f64 / self.created as f64
+        }
+    }
+}
+
+/// A summary of a single generation.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub best_fitness: f64,
+    pub mean_fitness: f64,
+    pub median_fitness: f64,
+    /// Number of different programs in the population.
+    pub unique_programs: usize,
+    /// Average structural distance between two members of the population, between 0 and 1.
+    pub mean_distance: f64,
+    /// Number of species, or 0 if speciation is disabled.
+    pub nr_species: usize,
+    /// Average number of instructions per program.
+    pub mean_length: f64,
+    /// Average number of instructions per program, with for loops written out.
+    pub mean_expanded_length: f64,
+    /// The bloat removed while creating and evaluating this generation.
+    pub bloat: BloatStats,
+    /// Usage of every variation operator in this generation, in the order of
+    /// `VARIATION_OPERATORS`.
+    pub operators: Vec<OperatorStats>,
+}
+
+impl GenerationStats {
+    /// Summarises the current generation of an evolution.
+    pub fn collect(evolution: &Evolution) -> GenerationStats {
+        let population = evolution.get_population();
+        let mut fitnesses: Vec<f64> =
+            population.iter().map(|individual| individual.get_fitness()).collect();
+        fitnesses.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let genomes: Vec<&Genome> =
+            population.iter().map(|individual| individual.get_genome()).collect();
+        let born = evolution.get_lineage().born_in(evolution.get_generation());
+        let operators = VARIATION_OPERATORS.iter()
+            .map(|&operator| {
+                let offspring = born.iter().filter(|entry| entry.operator == operator);
+                OperatorStats {
+                    operator,
+                    created: offspring.clone().count(),
+                    improved: offspring.filter(|entry| entry.is_improvement()).count(),
+                }
+            })
+            .collect();
+        GenerationStats {
+            generation: evolution.get_generation(),
+            best_fitness: fitnesses.last().cloned().unwrap_or(0.0),
+            mean_fitness: mean(fitnesses.iter().cloned()),
+            median_fitness: median(&fitnesses),
+            unique_programs: genomes.iter().collect::<HashSet<_>>().len(),
+            mean_distance: mean_distance(&genomes),
+            nr_species: evolution.get_species().len(),
+            mean_length: mean(genomes.iter().map(|genome| genome.len() as f64)),
+            mean_expanded_length: mean(genomes.iter().map(|genome| genome.expanded_len() as f64)),
+            bloat: *evolution.get_bloat_stats(),
+            operators,
+        }
+    }
+
+    /// The names of the columns written by `to_csv`.
+    pub fn csv_header() -> String {
+        let mut columns: Vec<String> = ["generation",
+                                        "best_fitness",
+                                        "mean_fitness",
+                                        "median_fitness",
+                                        "unique_programs",
+                                        "mean_distance",
+                                        "nr_species",
+                                        "mean_length",
+                                        "mean_expanded_length",
+                                        "pruned_programs",
+                                        "pruned_tokens",
+                                        "rejected_offspring"]
+            .iter()
+            .map(|column| column.to_string())
+            .collect();
+        for operator in &VARIATION_OPERATORS {
+            columns.push(format!("{}_created", operator.name()));
+            columns.push(format!("{}_improved", operator.name()));
+        }
+        columns.join(",")
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut values = vec![self.generation.to_string(),
+                              self.best_fitness.to_string(),
+                              self.mean_fitness.to_string(),
+                              self.median_fitness.to_string(),
+                              self.unique_programs.to_string(),
+                              self.mean_distance.to_string(),
+                              self.nr_species.to_string(),
+                              self.mean_length.to_string(),
+                              self.mean_expanded_length.to_string(),
+                              self.bloat.pruned_programs.to_string(),
+                              self.bloat.pruned_tokens.to_string(),
+                              self.bloat.rejected_offspring.to_string()];
+        for operator_stats in &self.operators {
+            values.push(operator_stats.created.to_string());
+            values.push(operator_stats.improved.to_string());
+        }
+        values.join(",")
+    }
+
+    /// Writes the statistics as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        let operators: Vec<String> = self.operators
+            .iter()
+            .map(|operator_stats| {
+                format!("\"{}\":{{\"created\":{},\"improved\":{}}}",
+                        operator_stats.operator.name(),
+                        operator_stats.created,
+                        operator_stats.improved)
+            })
+            .collect();
+        format!("{{\"generation\":{},\"best_fitness\":{},\"mean_fitness\":{},\
+                 \"median_fitness\":{},\"unique_programs\":{},\"mean_distance\":{},\
+                 \"nr_species\":{},\"mean_length\":{},\"mean_expanded_length\":{},\
+                 \"pruned_programs\":{},\"pruned_tokens\":{},\"rejected_offspring\":{},\
+                 \"operators\":{{{}}}}}",
+                self.generation,
+                json_number(self.best_fitness),
+                json_number(self.mean_fitness),
+                json_number(self.median_fitness),
+                self.unique_programs,
+                json_number(self.mean_distance),
+                self.nr_species,
+                json_number(self.mean_length),
+                json_number(self.mean_expanded_length),
+                self.bloat.pruned_programs,
+                self.bloat.pruned_tokens,
+                self.bloat.rejected_offspring,
+                operators.join(","))
+    }
+}
+
+/// The file formats of a statistics log.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LogFormat {
+    Csv,
+    JsonLines,
+}
+
+impl LogFormat {
+    /// Determines the format from the extension of a file: `.csv`, or `.jsonl` for JSON lines.
+    pub fn from_path(path: &Path) -> Option<LogFormat> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => Some(LogFormat::Csv),
+            Some("jsonl") => Some(LogFormat::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+/// A file that statistics are appended to, one line per generation.
+pub struct StatsLog {
+    writer: BufWriter<File>,
+    format: LogFormat,
+}
+
+impl StatsLog {
+    /// Creates a new log, or continues an existing one if `append` is set. A CSV log starts with
+    /// a header line.
+    pub fn open(path: &Path, format: LogFormat, append: bool) -> io::Result<StatsLog> {
+        let is_new = !append || !path.exists();
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        let mut log = StatsLog {
+            writer: BufWriter::new(file),
+            format,
+        };
+        if is_new && format == LogFormat::Csv {
+            writeln!(log.writer, "{}", GenerationStats::csv_header())?;
+        }
+        Ok(log)
+    }
+
+    pub fn write(&mut self, stats: &GenerationStats) -> io::Result<()> {
+        match self.format {
+            LogFormat::Csv => writeln!(self.writer, "{}", stats.to_csv())?,
+            LogFormat::JsonLines => writeln!(self.writer, "{}", stats.to_json())?,
+        }
+        self.writer.flush()
+    }
+}
+
+fn mean<I: Iterator<Item = f64>>(values: I) -> f64 {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    if count == 0 { 0.0 } else { sum / count as f64 }
+}
+
+/// The median of a sorted list of values.
+fn median(sorted: &[f64]) -> f64 {
+    match sorted.len() {
+        0 => 0.0,
+        length if length % 2 == 0 => (sorted[length / 2 - 1] + sorted[length / 2]) / 2.0,
+        length => sorted[length / 2],
+    }
+}
+
+/// The average distance over all pairs of genomes.
+fn mean_distance(genomes: &[&Genome]) -> f64 {
+    mean(genomes.iter().enumerate().flat_map(|(index, a)| {
+        genomes[index + 1..].iter().map(move |b| speciation::distance(a, b))
+    }))
+}
+
+/// JSON has no representation for infinity and NaN, so those become null.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf_bot_core::simul_round::RoundParams;
+    use evolution::EvolutionParams;
+    use genome::Genome;
+
+    fn make_evolution() -> Evolution {
+        let params = EvolutionParams {
+            population_size: 6,
+            initial_program_length: 5,
+            rounds: vec![RoundParams {
+                             tape_length: 10,
+                             invert_polarity: false,
+                             max_steps: 100,
+                         }],
+            ..EvolutionParams::default()
+        };
+        let mut evolution =
+            Evolution::new(params, vec![Genome::from_source("[-]").unwrap().to_bot()], 3);
+        evolution.next_generation();
+        evolution
+    }
+
+    #[test]
+    fn median_evenLength_averagesMiddleValues() {
+        assert_eq!(median(&[1.0, 2.0, 4.0, 8.0]), 3.0);
+        assert_eq!(median(&[1.0, 2.0, 4.0]), 2.0);
+    }
+
+    #[test]
+    fn collect_countsOffspringOfThisGeneration() {
+        let stats = GenerationStats::collect(&make_evolution());
+        assert_eq!(stats.generation, 1);
+        let nr_offspring: usize =
+            stats.operators.iter().map(|operator_stats| operator_stats.created).sum();
+        assert_eq!(nr_offspring, 4);
+        assert!(stats.best_fitness >= stats.median_fitness);
+        assert!(stats.unique_programs >= 1 && stats.unique_programs <= 6);
+    }
+
+    #[test]
+    fn toCsv_matchesHeader() {
+        let stats = GenerationStats::collect(&make_evolution());
+        assert_eq!(stats.to_csv().split(',').count(),
+                   GenerationStats::csv_header().split(',').count());
+    }
+
+    #[test]
+    fn toJson_isSingleLine() {
+        let json = GenerationStats::collect(&make_evolution()).to_json();
+        assert!(json.starts_with("{\"generation\":1,"));
+        assert!(!json.contains('\n'));
+    }
+
+    #[test]
+    fn logFormat_fromPath_usesExtension() {
+        assert_eq!(LogFormat::from_path(Path::new("run.csv")), Some(LogFormat::Csv));
+        assert_eq!(LogFormat::from_path(Path::new("run.jsonl")), Some(LogFormat::JsonLines));
+        assert_eq!(LogFormat::from_path(Path::new("run.txt")), None);
+    }
+}
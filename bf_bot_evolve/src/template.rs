@@ -0,0 +1,251 @@
+//! Parametric bot templates.
+//! A template is BrainFuck Joust source code in which iteration counts may be replaced by named
+//! holes, such as `(>)*$skip`. Every hole is declared on a line of its own, with the inclusive
+//! range of values it may take:
+//!
+//! ```text
+//! $skip = 7..11
+//! $decoys = 2..6
+//! (>)*$skip       Skip the cells that can't hold the enemy flag
+//! (>(+)*$decoys)*3
+//! ```
+//!
+//! Filling in a value for every hole gives a regular program, so a family of bots can be tuned
+//! automatically; see `tuning`.
+
+use std::fmt;
+
+use bf_bot_compiler::parser::ParseError;
+use genome::Genome;
+
+/// A named integer hole in a template.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Hole {
+    pub name: String,
+    /// The lowest allowed value.
+    pub min: usize,
+    /// The highest allowed value.
+    pub max: usize,
+}
+
+impl Hole {
+    /// Number of values the hole can take.
+    pub fn nr_values(&self) -> usize {
+        self.max - self.min + 1
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Part {
+    Code(String),
+    /// The index of a hole.
+    Hole(usize),
+}
+
+/// Source code with holes.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+    holes: Vec<Hole>,
+}
+
+/// Reasons why a template can't be read. Line numbers start at 1.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TemplateError {
+    /// A line starting with '$' is not of the form `$name = min..max`.
+    InvalidDeclaration(usize),
+    /// A hole is declared more than once.
+    DuplicateDeclaration(usize, String),
+    /// A hole is used on the given line, but never declared.
+    UndeclaredHole(usize, String),
+    /// A hole is used on the given line somewhere else than directly after the '*' of a for
+    /// loop.
+    MisplacedHole(usize, String),
+    /// Filling in the holes doesn't give valid source code.
+    InvalidSource(ParseError),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TemplateError::InvalidDeclaration(line) => {
+                write!(f, "line {}: expected a declaration like '$name = 1..10'.", line)
+            }
+            TemplateError::DuplicateDeclaration(line, ref name) => {
+                write!(f, "line {}: hole '${}' is already declared.", line, name)
+            }
+            TemplateError::UndeclaredHole(line, ref name) => {
+                write!(f, "line {}: hole '${}' is not declared.", line, name)
+            }
+            TemplateError::MisplacedHole(line, ref name) => {
+                write!(f, "line {}: hole '${}' must directly follow the '*' of a for loop.",
+                       line,
+                       name)
+            }
+            TemplateError::InvalidSource(ref error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, TemplateError> {
+        let mut holes: Vec<Hole> = vec![];
+        for (index, line) in text.lines().enumerate() {
+            if line.trim_start().starts_with('$') {
+                let number = index + 1;
+                let hole =
+                    parse_declaration(line).ok_or(TemplateError::InvalidDeclaration(number))?;
+                if holes.iter().any(|existing| existing.name == hole.name) {
+                    return Err(TemplateError::DuplicateDeclaration(number, hole.name));
+                }
+                holes.push(hole);
+            }
+        }
+        let mut parts = vec![];
+        let mut code = String::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim_start().starts_with('$') {
+                continue;
+            }
+            let mut chars = line.chars().peekable();
+            while let Some(character) = chars.next() {
+                if character != '$' {
+                    code.push(character);
+                    continue;
+                }
+                let mut name = String::new();
+                while let Some(&next) = chars.peek() {
+                    if !is_name_char(next) {
+                        break;
+                    }
+                    name.push(next);
+                    chars.next();
+                }
+                if !code.ends_with('*') {
+                    return Err(TemplateError::MisplacedHole(index + 1, name));
+                }
+                let hole = holes.iter()
+                    .position(|hole| hole.name == name)
+                    .ok_or_else(|| TemplateError::UndeclaredHole(index + 1, name.clone()))?;
+                parts.push(Part::Code(code));
+                parts.push(Part::Hole(hole));
+                code = String::new();
+            }
+            code.push('\n');
+        }
+        parts.push(Part::Code(code));
+        let template = Template { parts, holes };
+        let lowest: Vec<usize> = template.holes.iter().map(|hole| hole.min).collect();
+        template.instantiate_genome(&lowest).map_err(TemplateError::InvalidSource)?;
+        Ok(template)
+    }
+
+    /// Returns the holes in the order of their declaration.
+    pub fn get_holes(&self) -> &Vec<Hole> {
+        &self.holes
+    }
+
+    /// Number of different programs the template describes. Saturates at `usize::MAX`.
+    pub fn nr_combinations(&self) -> usize {
+        self.holes.iter().fold(1, |product: usize, hole| product.saturating_mul(hole.nr_values()))
+    }
+
+    /// Fills in the holes, given a value for each of them in the order of `get_holes`, and
+    /// returns the resulting source code. Panics if the number of values doesn't match.
+    pub fn instantiate(&self, values: &[usize]) -> String {
+        assert_eq!(values.len(), self.holes.len(), "Every hole needs exactly one value.");
+        self.parts
+            .iter()
+            .map(|part| match *part {
+                Part::Code(ref code) => code.clone(),
+                Part::Hole(index) => values[index].to_string(),
+            })
+            .collect()
+    }
+
+    pub fn instantiate_genome(&self, values: &[usize]) -> Result<Genome, ParseError> {
+        Genome::from_source(&self.instantiate(values))
+    }
+}
+
+/// Parses a line of the form `$name = min..max`.
+fn parse_declaration(line: &str) -> Option<Hole> {
+    let mut sides = line.trim()[1..].splitn(2, '=');
+    let name = sides.next()?.trim();
+    let mut bounds = sides.next()?.trim().splitn(2, "..");
+    let min = bounds.next()?.trim().parse().ok()?;
+    let max = bounds.next()?.trim().parse().ok()?;
+    if name.is_empty() || !name.chars().all(is_name_char) || min > max {
+        return None;
+    }
+    Some(Hole {
+        name: name.to_string(),
+        min,
+        max,
+    })
+}
+
+fn is_name_char(character: char) -> bool {
+    character.is_ascii_alphanumeric() || character == '_'
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = "$skip = 7..11\n$clear = 1..3\n(>)*$skip\n([(+)*$clear[-]]>)*21\n";
+
+    #[test]
+    fn parse_readsDeclarations() {
+        let template = Template::parse(TEMPLATE).unwrap();
+        assert_eq!(template.get_holes(),
+                   &vec![Hole {
+                             name: "skip".to_string(),
+                             min: 7,
+                             max: 11,
+                         },
+                         Hole {
+                             name: "clear".to_string(),
+                             min: 1,
+                             max: 3,
+                         }]);
+        assert_eq!(template.nr_combinations(), 15);
+    }
+
+    #[test]
+    fn parse_sampleTemplate_isValid() {
+        let template = Template::parse(include_str!("../../bots/templates/FastClearBot.bft"))
+            .unwrap();
+        assert_eq!(template.nr_combinations(), 8 * 7);
+    }
+
+    #[test]
+    fn instantiate_fillsInValues() {
+        let template = Template::parse(TEMPLATE).unwrap();
+        assert_eq!(template.instantiate(&[9, 2]), "(>)*9\n([(+)*2[-]]>)*21\n");
+    }
+
+    #[test]
+    fn parse_undeclaredHole_reportsLine() {
+        assert_eq!(Template::parse("$a = 1..2\n(>)*$a\n(+)*$b\n"),
+                   Err(TemplateError::UndeclaredHole(3, "b".to_string())));
+    }
+
+    #[test]
+    fn parse_holeOutsideForLoop_isRejected() {
+        assert_eq!(Template::parse("$a = 1..2\n>$a\n"),
+                   Err(TemplateError::MisplacedHole(2, "a".to_string())));
+    }
+
+    #[test]
+    fn parse_emptyRange_isRejected() {
+        assert_eq!(Template::parse("$a = 5..2\n"), Err(TemplateError::InvalidDeclaration(1)));
+    }
+
+    #[test]
+    fn parse_unbalancedBrackets_isRejected() {
+        assert_eq!(Template::parse("$a = 1..2\n((>)*$a\n"),
+                   Err(TemplateError::InvalidSource(ParseError::UnmatchedOpeningBracket(1, 1))));
+    }
+}
@@ -0,0 +1,246 @@
+//! Tuning the holes of a template against a set of opponents.
+//! A grid search tries every combination of values, which is exhaustive but only feasible for a
+//! few holes with small ranges. An evolutionary search breeds vectors of values instead, and only
+//! evaluates a sample of the combinations.
+
+use std::collections::HashMap;
+
+use bf_bot_core::bf::Bot;
+use bf_bot_core::simul_game::AllRounds;
+use bf_bot_core::simul_round::RoundParams;
+use evolution::evaluate;
+use rng::Rng;
+use template::{Hole, Template};
+
+/// The ways in which the values of the holes can be searched.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Search {
+    /// Tries every combination of values.
+    Grid,
+    /// Evolves a population of value vectors.
+    Evolutionary {
+        population_size: usize,
+        generations: usize,
+        /// Probability that a single value is mutated in an offspring.
+        mutation_rate: f64,
+    },
+}
+
+/// Settings for tuning a template.
+#[derive(Debug, Clone)]
+pub struct TuningParams {
+    pub search: Search,
+    /// The rounds each instance of the template plays against every opponent.
+    pub rounds: Vec<RoundParams>,
+    pub seed: u64,
+}
+
+impl Default for TuningParams {
+    fn default() -> TuningParams {
+        TuningParams {
+            search: Search::Evolutionary {
+                population_size: 20,
+                generations: 20,
+                mutation_rate: 0.3,
+            },
+            rounds: AllRounds::new().collect(),
+            seed: 0,
+        }
+    }
+}
+
+/// The best values found for the holes of a template.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tuning {
+    /// A value for every hole, in the order of `Template::get_holes`.
+    pub values: Vec<usize>,
+    /// The points the instance scored against the opponents.
+    pub fitness: f64,
+    /// Number of different instances that were evaluated.
+    pub nr_evaluations: usize,
+}
+
+/// Searches for the values of the holes that score the most points against the opponents.
+pub fn tune(template: &Template, opponents: &[Bot], params: &TuningParams) -> Tuning {
+    let mut evaluator = Evaluator {
+        template,
+        opponents,
+        rounds: &params.rounds,
+        cache: HashMap::new(),
+    };
+    let (values, fitness) = match params.search {
+        Search::Grid => grid_search(template.get_holes(), &mut evaluator),
+        Search::Evolutionary { population_size, generations, mutation_rate } => {
+            let mut rng = Rng::new(params.seed);
+            evolutionary_search(template.get_holes(),
+                                population_size.max(1),
+                                generations,
+                                mutation_rate,
+                                &mut evaluator,
+                                &mut rng)
+        }
+    };
+    Tuning {
+        values,
+        fitness,
+        nr_evaluations: evaluator.cache.len(),
+    }
+}
+
+/// Evaluates instances of a template, remembering earlier results.
+struct Evaluator<'a> {
+    template: &'a Template,
+    opponents: &'a [Bot],
+    rounds: &'a [RoundParams],
+    cache: HashMap<Vec<usize>, f64>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn fitness(&mut self, values: &[usize]) -> f64 {
+        if let Some(&fitness) = self.cache.get(values) {
+            return fitness;
+        }
+        // Values within the declared ranges always give valid source code, as the template was
+        // checked when it was parsed.
+        let genome = self.template
+            .instantiate_genome(values)
+            .expect("A template instance is not a valid program.");
+        let fitness = evaluate(&genome.to_bot(), self.opponents, self.rounds);
+        self.cache.insert(values.to_vec(), fitness);
+        fitness
+    }
+}
+
+fn grid_search(holes: &[Hole], evaluator: &mut Evaluator) -> (Vec<usize>, f64) {
+    let mut values: Vec<usize> = holes.iter().map(|hole| hole.min).collect();
+    let mut best = (values.clone(), evaluator.fitness(&values));
+    // Counts through all combinations like an odometer, with the last hole turning fastest.
+    while let Some(position) =
+        (0..holes.len()).rev().find(|&index| values[index] < holes[index].max) {
+        values[position] += 1;
+        for index in position + 1..holes.len() {
+            values[index] = holes[index].min;
+        }
+        let fitness = evaluator.fitness(&values);
+        if fitness > best.1 {
+            best = (values.clone(), fitness);
+        }
+    }
+    best
+}
+
+fn evolutionary_search(holes: &[Hole],
+                       population_size: usize,
+                       generations: usize,
+                       mutation_rate: f64,
+                       evaluator: &mut Evaluator,
+                       rng: &mut Rng)
+                       -> (Vec<usize>, f64) {
+    let mut population: Vec<(Vec<usize>, f64)> = (0..population_size)
+        .map(|_| {
+            let values: Vec<usize> =
+                holes.iter().map(|hole| rng.in_range(hole.min, hole.max)).collect();
+            let fitness = evaluator.fitness(&values);
+            (values, fitness)
+        })
+        .collect();
+    for _ in 0..generations {
+        population.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        // The best vector survives; the rest is replaced by offspring of tournament winners.
+        let mut next = vec![population[0].clone()];
+        while next.len() < population_size {
+            let mother = &population[select(&population, rng)].0;
+            let father = &population[select(&population, rng)].0;
+            let values: Vec<usize> = holes.iter()
+                .enumerate()
+                .map(|(index, hole)| {
+                    let value = if rng.chance(0.5) { mother[index] } else { father[index] };
+                    if rng.chance(mutation_rate) {
+                        mutate(value, hole, rng)
+                    } else {
+                        value
+                    }
+                })
+                .collect();
+            let fitness = evaluator.fitness(&values);
+            next.push((values, fitness));
+        }
+        population = next;
+    }
+    population.into_iter()
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .unwrap()
+}
+
+/// Returns the index of the fittest of two random members of the population.
+fn select(population: &[(Vec<usize>, f64)], rng: &mut Rng) -> usize {
+    let a = rng.below(population.len());
+    let b = rng.below(population.len());
+    if population[a].1 >= population[b].1 { a } else { b }
+}
+
+/// Usually nudges a value up or down by one, and sometimes picks a new value from the whole range.
+fn mutate(value: usize, hole: &Hole, rng: &mut Rng) -> usize {
+    if rng.chance(0.25) {
+        rng.in_range(hole.min, hole.max)
+    } else if rng.chance(0.5) {
+        (value + 1).min(hole.max)
+    } else {
+        value.saturating_sub(1).max(hole.min)
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use genome::Genome;
+
+    fn make_params(search: Search) -> TuningParams {
+        TuningParams {
+            search,
+            rounds: vec![RoundParams {
+                             tape_length: 12,
+                             invert_polarity: false,
+                             max_steps: 1_000,
+                         }],
+            seed: 1,
+        }
+    }
+
+    fn make_opponents() -> Vec<Bot> {
+        vec![Genome::from_source(".").unwrap().to_bot()]
+    }
+
+    #[test]
+    fn tune_gridSearch_findsDistanceToFlag() {
+        // On a tape of 12 cells, the enemy flag is 11 cells away. Only a bot that skips exactly
+        // that far and then clears the cell wins against an idle opponent.
+        let template = Template::parse("$skip = 1..15\n(>)*$skip[-]").unwrap();
+        let tuning = tune(&template, &make_opponents(), &make_params(Search::Grid));
+        assert_eq!(tuning.values, vec![11]);
+        assert_eq!(tuning.fitness, 1.0);
+        assert_eq!(tuning.nr_evaluations, 15);
+    }
+
+    #[test]
+    fn tune_evolutionarySearch_staysWithinRanges() {
+        let template = Template::parse("$a = 1..4\n$b = 3..3\n(>)*$a(+)*$b").unwrap();
+        let search = Search::Evolutionary {
+            population_size: 4,
+            generations: 3,
+            mutation_rate: 0.5,
+        };
+        let tuning = tune(&template, &make_opponents(), &make_params(search));
+        assert!(tuning.values[0] >= 1 && tuning.values[0] <= 4);
+        assert_eq!(tuning.values[1], 3);
+    }
+
+    #[test]
+    fn tune_noHoles_evaluatesOnce() {
+        let template = Template::parse("[-]").unwrap();
+        let tuning = tune(&template, &make_opponents(), &make_params(Search::Grid));
+        assert_eq!(tuning.values, Vec::<usize>::new());
+        assert_eq!(tuning.nr_evaluations, 1);
+    }
+}
@@ -0,0 +1,454 @@
+//! Gauntlets: fixed sets of opponents to benchmark bots against.
+//! A gauntlet is described in a plain text file of `key = value` lines, so the same benchmark can
+//! be versioned and shared between evolution runs, regression checks and ad-hoc evaluation. Lines
+//! starting with `#` are comments. Every opponent has a name, and either a source file, whose
+//! path is resolved against the directory of the gauntlet file, or inline source code. An
+//! opponent can have a weight, 1 by default, and any number of tags.
+//!
+//! ```text
+//! # Everything a rusher should beat.
+//! decoy = ../bots/DecoyBot.bf
+//! decoy.weight = 2
+//! decoy.tags = defender, decoy
+//! clear.source = >>>>>>>>>([-]>)*21
+//! clear.tags = rusher
+//! ```
+//!
+//! A candidate's score against a gauntlet is the weighted average of the points it scored per
+//! game, from -42 to 42. It is also broken down per opponent and per tag.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bf_bot_compiler::loader::{self, LoadError};
+use bf_bot_compiler::parser;
+use bf_bot_core::bf::Bot;
+use bf_bot_core::json::{Object, ToJson};
+use bf_bot_core::simul_game::AllRounds;
+use bf_bot_core::simul_game::game_profile::GameProfile;
+use round_robin::{self, Entrant};
+
+/// A single opponent of a gauntlet.
+#[derive(Debug, Clone)]
+pub struct Opponent {
+    pub name: String,
+    pub bot: Bot,
+    pub weight: f64,
+    pub tags: Vec<String>,
+}
+
+/// Reasons why a gauntlet can't be read.
+#[derive(Debug)]
+pub enum GauntletError {
+    Io(PathBuf, io::Error),
+    /// The source file of an opponent can't be loaded.
+    Load(PathBuf, LoadError),
+    /// The gauntlet is not valid. Holds the line number, if any, and a description.
+    Malformed(Option<usize>, String),
+}
+
+impl fmt::Display for GauntletError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GauntletError::Io(ref path, ref error) => write!(f, "{}: {}", path.display(), error),
+            GauntletError::Load(ref path, ref error) => write!(f, "{}: {}", path.display(), error),
+            GauntletError::Malformed(Some(line), ref description) => {
+                write!(f, "line {}: {}", line, description)
+            }
+            GauntletError::Malformed(None, ref description) => write!(f, "{}", description),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Gauntlet {
+    opponents: Vec<Opponent>,
+}
+
+/// The keys of a single opponent, before its program is loaded.
+struct Definition {
+    name: String,
+    line: usize,
+    path: Option<PathBuf>,
+    source_code: Option<String>,
+    weight: f64,
+    tags: Vec<String>,
+}
+
+impl Gauntlet {
+    pub fn new(opponents: Vec<Opponent>) -> Gauntlet {
+        Gauntlet { opponents }
+    }
+
+    /// Reads a gauntlet file, and the source files of its opponents.
+    pub fn load(path: &Path) -> Result<Gauntlet, GauntletError> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| GauntletError::Io(path.to_path_buf(), error))?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Gauntlet::parse(&text, base_dir)
+    }
+
+    /// Parses the text of a gauntlet file. Relative paths are resolved against the given
+    /// directory.
+    pub fn parse(text: &str, base_dir: &Path) -> Result<Gauntlet, GauntletError> {
+        let mut definitions: Vec<Definition> = vec![];
+        for (index, line) in text.lines().enumerate() {
+            let line_nr = index + 1;
+            let malformed = |description: String| {
+                GauntletError::Malformed(Some(line_nr), description)
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.find('=') {
+                Some(position) => (line[..position].trim(), line[position + 1..].trim()),
+                None => return Err(malformed("Expected 'key = value'.".to_string())),
+            };
+            let (name, field) = match key.find('.') {
+                Some(position) => (&key[..position], Some(&key[position + 1..])),
+                None => (key, None),
+            };
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(malformed(format!("'{}' is not a valid opponent name.", name)));
+            }
+            if !definitions.iter().any(|definition| definition.name == name) {
+                definitions.push(Definition {
+                    name: name.to_string(),
+                    line: line_nr,
+                    path: None,
+                    source_code: None,
+                    weight: 1.0,
+                    tags: vec![],
+                });
+            }
+            let definition = definitions.iter_mut()
+                .find(|definition| definition.name == name)
+                .unwrap();
+            match field {
+                None if definition.path.is_none() => definition.path = Some(base_dir.join(value)),
+                Some("source") if definition.source_code.is_none() => {
+                    definition.source_code = Some(value.to_string())
+                }
+                Some("weight") => {
+                    definition.weight = match value.parse::<f64>() {
+                        Ok(weight) if weight.is_finite() && weight > 0.0 => weight,
+                        _ => {
+                            return Err(malformed(format!("'{}' is not a finite, positive weight.",
+                                                         value)))
+                        }
+                    }
+                }
+                Some("tags") => {
+                    definition.tags = value.split(',')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                }
+                None | Some("source") => {
+                    return Err(malformed(format!("{} already has a program.", name)))
+                }
+                Some(other) => {
+                    return Err(malformed(format!("Unknown key '{}', expected source, weight or \
+                                                  tags.",
+                                                 other)))
+                }
+            }
+        }
+        if definitions.is_empty() {
+            return Err(GauntletError::Malformed(None,
+                                                "A gauntlet needs at least one opponent."
+                                                    .to_string()));
+        }
+        let opponents = definitions.into_iter()
+            .map(load_opponent)
+            .collect::<Result<Vec<Opponent>, GauntletError>>()?;
+        Ok(Gauntlet { opponents })
+    }
+
+    pub fn get_opponents(&self) -> &[Opponent] {
+        &self.opponents
+    }
+
+    /// Returns the programs of the opponents, in order.
+    pub fn bots(&self) -> Vec<Bot> {
+        self.opponents.iter().map(|opponent| opponent.bot.clone()).collect()
+    }
+
+    /// Returns the weights of the opponents, in order.
+    pub fn weights(&self) -> Vec<f64> {
+        self.opponents.iter().map(|opponent| opponent.weight).collect()
+    }
+
+    /// Profiles a bot against every opponent of the gauntlet in a complete game each, spread
+    /// over the given number of threads. The weights of the opponents are not taken into account.
+    pub fn profile(&self, bot: &Bot, nr_threads: usize) -> GameProfile {
+        let pairs: Vec<(usize, usize)> =
+            (0..self.opponents.len()).map(|opponent| (0, opponent)).collect();
+        let profiles = round_robin::for_pairs(&pairs, nr_threads, |_, opponent| {
+            let mut game_profile = GameProfile::new(bot.get_program().len());
+            game_profile.add_game(bot, &self.opponents[opponent].bot, AllRounds::new());
+            game_profile
+        });
+        profiles.into_iter().fold(GameProfile::new(bot.get_program().len()), |mut total, profile| {
+            total.merge(profile);
+            total
+        })
+    }
+
+    /// Plays every candidate against every opponent, spread over the given number of threads.
+    /// Returns a report for every candidate, in order.
+    pub fn run(&self, candidates: &[Entrant], nr_threads: usize) -> Vec<GauntletReport> {
+        let mut entrants = candidates.to_vec();
+        entrants.extend(self.opponents
+            .iter()
+            .map(|opponent| Entrant::new(&opponent.name, opponent.bot.clone())));
+        let pairs: Vec<(usize, usize)> = (0..candidates.len())
+            .flat_map(|candidate| {
+                (0..self.opponents.len())
+                    .map(move |opponent| (candidate, candidates.len() + opponent))
+            })
+            .collect();
+        let mut points = round_robin::play_pairs(&entrants, &pairs, nr_threads).into_iter();
+        candidates.iter()
+            .map(|candidate| {
+                GauntletReport {
+                    candidate: candidate.name.clone(),
+                    results: self.opponents
+                        .iter()
+                        .map(|opponent| {
+                            OpponentResult {
+                                name: opponent.name.clone(),
+                                weight: opponent.weight,
+                                tags: opponent.tags.clone(),
+                                points: points.next().unwrap(),
+                            }
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+fn load_opponent(definition: Definition) -> Result<Opponent, GauntletError> {
+    let Definition { name, line, path, source_code, weight, tags } = definition;
+    let bot = match (path, source_code) {
+        (Some(path), None) => {
+            loader::load_bot(&path).map_err(|error| GauntletError::Load(path, error))?.bot
+        }
+        (None, Some(source_code)) => {
+            parser::try_parse_bot(&source_code)
+                .map(Bot::new)
+                .map_err(|error| {
+                    GauntletError::Malformed(Some(line), format!("{}: {}", name, error))
+                })?
+        }
+        _ => {
+            return Err(GauntletError::Malformed(Some(line),
+                                                format!("{} needs either a path or a source, \
+                                                         but not both.",
+                                                        name)))
+        }
+    };
+    Ok(Opponent { name, bot, weight, tags })
+}
+
+/// The outcome of a single game of a gauntlet.
+#[derive(Debug, PartialEq, Clone)]
+pub struct OpponentResult {
+    pub name: String,
+    pub weight: f64,
+    pub tags: Vec<String>,
+    /// The points the candidate scored against the opponent.
+    pub points: i8,
+}
+
+/// The outcome of a gauntlet for a single candidate.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GauntletReport {
+    pub candidate: String,
+    /// The results against every opponent, in the order of the gauntlet.
+    pub results: Vec<OpponentResult>,
+}
+
+impl GauntletReport {
+    /// Returns the weighted average of the points per game.
+    pub fn score(&self) -> f64 {
+        weighted_average(self.results.iter())
+    }
+
+    /// Returns the weighted average of the points per game against the opponents with each tag,
+    /// in alphabetical order of the tags.
+    pub fn tag_scores(&self) -> Vec<(String, f64)> {
+        let mut tags: BTreeMap<&str, Vec<&OpponentResult>> = BTreeMap::new();
+        for result in &self.results {
+            for tag in &result.tags {
+                tags.entry(tag).or_default().push(result);
+            }
+        }
+        tags.into_iter()
+            .map(|(tag, results)| (tag.to_string(), weighted_average(results.into_iter())))
+            .collect()
+    }
+}
+
+fn weighted_average<'a, I>(results: I) -> f64
+    where I: Iterator<Item = &'a OpponentResult>
+{
+    let (total, total_weight) = results.fold((0.0, 0.0), |(total, total_weight), result| {
+        (total + result.weight * f64::from(result.points), total_weight + result.weight)
+    });
+    if total_weight == 0.0 {
+        0.0
+    } else {
+        total / total_weight
+    }
+}
+
+impl fmt::Display for GauntletReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}: {:.2}", self.candidate, self.score())?;
+        let name_width = self.results.iter().map(|result| result.name.len()).max().unwrap_or(0);
+        for result in &self.results {
+            write!(f,
+                   "  {:<width$} {:>+4}  weight {}",
+                   result.name,
+                   result.points,
+                   result.weight,
+                   width = name_width)?;
+            if !result.tags.is_empty() {
+                write!(f, "  [{}]", result.tags.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        for (tag, score) in self.tag_scores() {
+            writeln!(f, "  tag {}: {:.2}", tag, score)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for OpponentResult {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("name", &self.name)
+            .field("weight", &self.weight)
+            .field("tags", &self.tags)
+            .field("points", &self.points)
+            .build()
+    }
+}
+
+impl ToJson for GauntletReport {
+    fn to_json(&self) -> String {
+        let tags: Vec<String> = self.tag_scores()
+            .iter()
+            .map(|(tag, score)| Object::new().field("tag", tag).field("score", score).build())
+            .collect();
+        Object::new()
+            .field("candidate", &self.candidate)
+            .field("score", &self.score())
+            .field("results", &self.results)
+            .raw_field("tags", format!("[{}]", tags.join(",")))
+            .build()
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Gauntlet, GauntletError> {
+        Gauntlet::parse(text, Path::new(""))
+    }
+
+    fn error_line(text: &str) -> Option<usize> {
+        match parse(text) {
+            Err(GauntletError::Malformed(line, _)) => line,
+            other => panic!("Expected a malformed gauntlet, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn parse_pathsSourcesWeightsAndTags() {
+        let gauntlet = Gauntlet::parse("# The sample bots.\n\
+                                        decoy = bots/DecoyBot.bf\n\
+                                        decoy.weight = 2.5\n\
+                                        clear.source = >>>>>>>>>([-]>)*21\n\
+                                        clear.tags = rusher, clear\n",
+                                       Path::new(".."))
+            .unwrap();
+        let opponents = gauntlet.get_opponents();
+        assert_eq!(opponents.len(), 2);
+        assert_eq!((opponents[0].name.as_str(), opponents[0].weight), ("decoy", 2.5));
+        assert_eq!(opponents[1].tags, vec!["rusher", "clear"]);
+        assert_eq!(gauntlet.weights(), vec![2.5, 1.0]);
+    }
+
+    #[test]
+    fn parse_invalidEntries_reportLine() {
+        assert_eq!(error_line("a.source = .\na.weight = 0\n"), Some(2));
+        assert_eq!(error_line("a.source = .\na.weight = inf\n"), Some(2));
+        assert_eq!(error_line("a.source = .\na.weight = NaN\n"), Some(2));
+        assert_eq!(error_line("a.source = .\na.colour = red\n"), Some(2));
+        assert_eq!(error_line("a.source = [\n"), Some(1));
+        assert_eq!(error_line("a.source = .\nb.tags = x\n"), Some(2));
+        assert_eq!(error_line("a.source = .\nno equals sign\n"), Some(2));
+        assert_eq!(error_line("# Nothing here.\n"), None);
+    }
+
+    #[test]
+    fn parse_missingFile_isALoadError() {
+        match parse("a = nonexistent.bf\n") {
+            Err(GauntletError::Load(path, LoadError::Io(_))) => {
+                assert_eq!(path, PathBuf::from("nonexistent.bf"))
+            }
+            other => panic!("Expected a load error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn run_breaksScoresDownPerOpponentAndTag() {
+        let gauntlet = parse("idle.source = .\n\
+                              idle.tags = passive\n\
+                              idle.weight = 3\n\
+                              clear.source = >>>>>>>>>([-]>)*21\n\
+                              clear.tags = rusher\n")
+            .unwrap();
+        let candidates = vec![Entrant::new("suicide", Bot::new(parser::parse_bot("<".to_string()))),
+                              Entrant::new("idle", Bot::new(parser::parse_bot(".".to_string())))];
+        let reports = gauntlet.run(&candidates, 2);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].score(), -42.0);
+        let idle = &reports[1];
+        assert_eq!(idle.results[0].points, 0);
+        assert_eq!(idle.results[1].points, -42);
+        assert_eq!(idle.score(), -42.0 / 4.0);
+        assert_eq!(idle.tag_scores(),
+                   vec![("passive".to_string(), 0.0), ("rusher".to_string(), -42.0)]);
+        assert!(idle.to_string().starts_with("idle: -10.50\n"));
+    }
+
+    #[test]
+    fn toJson_report_includesScoreAndTagScores() {
+        let report = GauntletReport {
+            candidate: "idle".to_string(),
+            results: vec![OpponentResult {
+                              name: "clear".to_string(),
+                              weight: 2.0,
+                              tags: vec!["rusher".to_string()],
+                              points: -4,
+                          }],
+        };
+        assert_eq!(report.to_json(),
+                   "{\"candidate\":\"idle\",\"score\":-4,\"results\":[{\"name\":\"clear\",\
+                    \"weight\":2,\"tags\":[\"rusher\"],\"points\":-4}],\"tags\":\
+                    [{\"tag\":\"rusher\",\"score\":-4}]}");
+    }
+}
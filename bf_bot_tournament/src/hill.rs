@@ -0,0 +1,643 @@
+//! A persistent king of the hill.
+//! A hill is a directory that holds a fixed number of bots, each in a `.bf` file named after it,
+//! and a `hill.txt` file with the state of the hill: its size, its scoring, its members, the
+//! results of the games between them, and the history of every challenge. A challenger plays
+//! every member, the hill is rescored, and if the hill has grown too large, the lowest-ranked bot
+//! is evicted, which may be the challenger itself.
+//!
+//! ```text
+//! size 2
+//! scoring markov
+//! member rush
+//! member decoy
+//! result decoy rush 12
+//! challenge rush
+//! before decoy 100
+//! after decoy 50 rush 50
+//! challenge decoy
+//! before rush 100
+//! after decoy 63.3 rush 36.7
+//! ```
+//!
+//! The scores in the history are written in full precision; they are shortened here.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bf_bot_compiler::loader::{self, LoadError};
+use bf_bot_compiler::parser;
+use bf_bot_core::bf::Bot;
+use bf_bot_core::json::{Object, ToJson};
+use bf_bot_core::simul_game;
+use leaderboard::Leaderboard;
+use round_robin::RoundRobin;
+use scoring::Scoring;
+
+/// The name of the file that holds the state of a hill.
+pub const STATE_FILE: &str = "hill.txt";
+
+/// Names and scores of bots, from best to worst.
+pub type Ranking = Vec<(String, f64)>;
+
+/// Reasons why a hill can't be read, written or challenged.
+#[derive(Debug)]
+pub enum HillError {
+    Io(PathBuf, io::Error),
+    /// The source file of a member can't be loaded.
+    Load(PathBuf, LoadError),
+    /// The state file is not valid. Holds the line number and a description.
+    Malformed(usize, String),
+    /// There already is a hill in the directory.
+    AlreadyExists(PathBuf),
+    /// A hill needs room for at least one bot.
+    InvalidSize,
+    /// A bot with this name is already on the hill.
+    NameTaken(String),
+    /// Names must be non-empty and can't contain whitespace, path separators or `..`.
+    InvalidName(String),
+}
+
+impl fmt::Display for HillError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HillError::Io(ref path, ref error) => write!(f, "{}: {}", path.display(), error),
+            HillError::Load(ref path, ref error) => write!(f, "{}: {}", path.display(), error),
+            HillError::Malformed(line, ref description) => {
+                write!(f, "{} line {}: {}", STATE_FILE, line, description)
+            }
+            HillError::AlreadyExists(ref path) => {
+                write!(f, "{}: there already is a hill here", path.display())
+            }
+            HillError::InvalidSize => write!(f, "a hill needs room for at least one bot"),
+            HillError::NameTaken(ref name) => write!(f, "{} is already on the hill", name),
+            HillError::InvalidName(ref name) => {
+                write!(f,
+                       "'{}' is not a valid name: it must be non-empty, without spaces, path \
+                        separators or '..'",
+                       name)
+            }
+        }
+    }
+}
+
+/// A single challenge of the hill.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Change {
+    pub challenger: String,
+    /// The ranking of the hill before the challenge.
+    pub before: Ranking,
+    /// The ranking of the hill after the challenge, without the evicted bot.
+    pub after: Ranking,
+    pub evicted: Option<String>,
+}
+
+impl Change {
+    /// Returns the rank at which the challenger entered the hill, counted from 1, or None if it
+    /// didn't make it onto the hill.
+    pub fn rank(&self) -> Option<usize> {
+        self.after.iter().position(|(name, _)| *name == self.challenger).map(|index| index + 1)
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.rank() {
+            Some(rank) => write!(f, "{} entered the hill at rank {}", self.challenger, rank)?,
+            None => write!(f, "{} did not make it onto the hill", self.challenger)?,
+        }
+        match self.evicted {
+            Some(ref evicted) if *evicted != self.challenger => {
+                write!(f, "; {} was evicted", evicted)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ToJson for Change {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("challenger", &self.challenger)
+            .field("rank", &self.rank())
+            .field("evicted", &self.evicted)
+            .raw_field("before", ranking_to_json(&self.before))
+            .raw_field("after", ranking_to_json(&self.after))
+            .build()
+    }
+}
+
+fn ranking_to_json(ranking: &Ranking) -> String {
+    let entries: Vec<String> = ranking.iter()
+        .map(|(name, score)| Object::new().field("name", name).field("score", score).build())
+        .collect();
+    format!("[{}]", entries.join(","))
+}
+
+/// A member of the hill.
+#[derive(Debug, Clone)]
+struct Member {
+    name: String,
+    bot: Bot,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hill {
+    dir: PathBuf,
+    size: usize,
+    scoring: Scoring,
+    members: Vec<Member>,
+    /// The points of the first bot against the second, keyed on their names in alphabetical
+    /// order.
+    results: BTreeMap<(String, String), i8>,
+    history: Vec<Change>,
+}
+
+impl Hill {
+    /// Creates an empty hill in the directory, which is created if it doesn't exist yet.
+    pub fn create(dir: &Path, size: usize, scoring: Scoring) -> Result<Hill, HillError> {
+        if size == 0 {
+            return Err(HillError::InvalidSize);
+        }
+        let state_path = dir.join(STATE_FILE);
+        if state_path.exists() {
+            return Err(HillError::AlreadyExists(dir.to_path_buf()));
+        }
+        fs::create_dir_all(dir).map_err(|error| HillError::Io(dir.to_path_buf(), error))?;
+        let hill = Hill {
+            dir: dir.to_path_buf(),
+            size,
+            scoring,
+            members: vec![],
+            results: BTreeMap::new(),
+            history: vec![],
+        };
+        hill.save()?;
+        Ok(hill)
+    }
+
+    /// Reads the hill in the directory, including the source files of its members.
+    pub fn open(dir: &Path) -> Result<Hill, HillError> {
+        let state_path = dir.join(STATE_FILE);
+        let text = fs::read_to_string(&state_path)
+            .map_err(|error| HillError::Io(state_path.clone(), error))?;
+        let mut hill = Hill::parse(dir, &text)?;
+        hill.members = hill.members
+            .iter()
+            .map(|member| {
+                let path = hill.member_path(&member.name);
+                let loaded =
+                    loader::load_bot(&path).map_err(|error| HillError::Load(path, error))?;
+                Ok(Member {
+                    name: member.name.clone(),
+                    bot: loaded.bot,
+                })
+            })
+            .collect::<Result<Vec<Member>, HillError>>()?;
+        Ok(hill)
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get_scoring(&self) -> Scoring {
+        self.scoring
+    }
+
+    /// Returns the names of the members, in the order they entered the hill.
+    pub fn get_members(&self) -> Vec<&str> {
+        self.members.iter().map(|member| member.name.as_str()).collect()
+    }
+
+    /// Returns every challenge of the hill, oldest first.
+    pub fn get_history(&self) -> &[Change] {
+        &self.history
+    }
+
+    /// Ranks the current members.
+    pub fn leaderboard(&self) -> Leaderboard {
+        Leaderboard::new(&self.round_robin(), self.scoring)
+    }
+
+    /// Lets a bot challenge the hill. It plays every member, and if the hill is full, the
+    /// lowest-ranked bot is evicted. The new state of the hill is saved right away. If it can't
+    /// be saved, the hill is left as it was.
+    pub fn challenge(&mut self, name: &str, source_code: &str) -> Result<&Change, HillError> {
+        if !is_valid_name(name) {
+            return Err(HillError::InvalidName(name.to_string()));
+        }
+        if self.members.iter().any(|member| member.name == name) {
+            return Err(HillError::NameTaken(name.to_string()));
+        }
+        let path = self.member_path(name);
+        let bot = parser::try_parse_bot(source_code)
+            .map(Bot::new)
+            .map_err(|error| HillError::Load(path.clone(), LoadError::Parse(error)))?;
+        // The challenge is played out on a copy, which only replaces this hill once its state
+        // has been saved.
+        let mut hill = self.clone();
+        let before = hill.ranking();
+        let results: Vec<(String, i8)> = hill.members
+            .iter()
+            .map(|member| {
+                (member.name.clone(), simul_game::run_complete(&bot, &member.bot).bot_a_points)
+            })
+            .collect();
+        for (member, points) in results {
+            hill.insert_result(name, &member, points);
+        }
+        hill.members.push(Member {
+            name: name.to_string(),
+            bot,
+        });
+        let evicted = if hill.members.len() > hill.size {
+            let evicted = hill.ranking().pop().unwrap().0;
+            hill.members.retain(|member| member.name != evicted);
+            hill.results.retain(|(a, b), _| *a != evicted && *b != evicted);
+            Some(evicted)
+        } else {
+            None
+        };
+        hill.history.push(Change {
+            challenger: name.to_string(),
+            before,
+            after: hill.ranking(),
+            evicted: evicted.clone(),
+        });
+        let entered = evicted.as_deref() != Some(name);
+        if entered {
+            fs::write(&path, source_code).map_err(|error| HillError::Io(path.clone(), error))?;
+        }
+        if let Err(error) = hill.save() {
+            if entered {
+                let _ = fs::remove_file(&path);
+            }
+            return Err(error);
+        }
+        *self = hill;
+        if let Some(evicted) = evicted.filter(|evicted| evicted != name) {
+            let evicted_path = self.member_path(&evicted);
+            fs::remove_file(&evicted_path).map_err(|error| HillError::Io(evicted_path, error))?;
+        }
+        Ok(self.history.last().unwrap())
+    }
+
+    fn member_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", name, loader::EXTENSION))
+    }
+
+    fn is_member(&self, name: &str) -> bool {
+        self.members.iter().any(|member| member.name == name)
+    }
+
+    fn has_result(&self, a: &str, b: &str) -> bool {
+        let key = if a < b { (a, b) } else { (b, a) };
+        self.results.contains_key(&(key.0.to_string(), key.1.to_string()))
+    }
+
+    fn insert_result(&mut self, a: &str, b: &str, a_points: i8) {
+        if a < b {
+            self.results.insert((a.to_string(), b.to_string()), a_points);
+        } else {
+            self.results.insert((b.to_string(), a.to_string()), -a_points);
+        }
+    }
+
+    fn round_robin(&self) -> RoundRobin {
+        let index = |name: &str| self.members.iter().position(|member| member.name == name);
+        let results: Vec<(usize, usize, i8)> = self.results
+            .iter()
+            .filter_map(|((a, b), &points)| Some((index(a)?, index(b)?, points)))
+            .collect();
+        RoundRobin::from_points(self.members.iter().map(|member| member.name.clone()).collect(),
+                                &results)
+    }
+
+    fn ranking(&self) -> Ranking {
+        self.leaderboard()
+            .get_standings()
+            .iter()
+            .map(|standing| (standing.name.clone(), standing.score))
+            .collect()
+    }
+
+    fn save(&self) -> Result<(), HillError> {
+        let path = self.dir.join(STATE_FILE);
+        fs::write(&path, self.to_string()).map_err(|error| HillError::Io(path, error))
+    }
+
+    /// Reads the state file. The members are left without their programs.
+    fn parse(dir: &Path, text: &str) -> Result<Hill, HillError> {
+        let mut hill = Hill {
+            dir: dir.to_path_buf(),
+            size: 0,
+            scoring: Scoring::Points,
+            members: vec![],
+            results: BTreeMap::new(),
+            history: vec![],
+        };
+        let mut member_line_nrs = vec![];
+        for (index, line) in text.lines().enumerate() {
+            let line_nr = index + 1;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match (words.first().cloned(), &words[1..]) {
+                (None, _) => {}
+                (Some("size"), &[size]) => {
+                    hill.size = size.parse().map_err(|_| malformed(line_nr, "invalid size"))?;
+                }
+                (Some("scoring"), &[name]) => {
+                    hill.scoring = Scoring::from_name(name)
+                        .ok_or_else(|| malformed(line_nr, "unknown scoring"))?;
+                }
+                (Some("member"), &[name]) => {
+                    if !is_valid_name(name) {
+                        return Err(malformed(line_nr, "invalid member name"));
+                    }
+                    if hill.is_member(name) {
+                        return Err(malformed(line_nr, &format!("{} is listed twice", name)));
+                    }
+                    member_line_nrs.push(line_nr);
+                    hill.members.push(Member {
+                        name: name.to_string(),
+                        bot: Bot::new(vec![]),
+                    })
+                }
+                (Some("result"), &[a, b, points]) => {
+                    let points = points.parse().map_err(|_| malformed(line_nr, "invalid points"))?;
+                    if !hill.is_member(a) || !hill.is_member(b) || a == b {
+                        return Err(malformed(line_nr, "result for bots that aren't two members"));
+                    }
+                    if hill.has_result(a, b) {
+                        return Err(malformed(line_nr,
+                                             &format!("second result of {} against {}", a, b)));
+                    }
+                    hill.insert_result(a, b, points);
+                }
+                (Some("challenge"), &[name]) => {
+                    hill.history.push(Change {
+                        challenger: name.to_string(),
+                        before: vec![],
+                        after: vec![],
+                        evicted: None,
+                    })
+                }
+                (Some("before"), ranking) => {
+                    last_change(&mut hill, line_nr)?.before = parse_ranking(ranking, line_nr)?;
+                }
+                (Some("after"), ranking) => {
+                    last_change(&mut hill, line_nr)?.after = parse_ranking(ranking, line_nr)?;
+                }
+                (Some("evicted"), &[name]) => {
+                    last_change(&mut hill, line_nr)?.evicted = Some(name.to_string());
+                }
+                _ => return Err(malformed(line_nr, &format!("unexpected '{}'", line))),
+            }
+        }
+        if hill.size == 0 {
+            return Err(malformed(0, "missing 'size' line"));
+        }
+        // Scoring needs the result of every pair, so a missing one is reported at the member
+        // line that completes the pair.
+        for (index, a) in hill.members.iter().enumerate() {
+            for (b, &line_nr) in hill.members[..index].iter().zip(&member_line_nrs) {
+                if !hill.has_result(&a.name, &b.name) {
+                    return Err(malformed(member_line_nrs[index].max(line_nr),
+                                         &format!("missing result of {} against {}",
+                                                  b.name,
+                                                  a.name)));
+                }
+            }
+        }
+        Ok(hill)
+    }
+}
+
+/// Returns whether the name can be used for a member. Names become file names within the
+/// directory of the hill, so they can't lead out of it.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(char::is_whitespace) &&
+    !name.contains(std::path::is_separator) && !name.contains("..")
+}
+
+fn malformed(line_nr: usize, description: &str) -> HillError {
+    HillError::Malformed(line_nr, description.to_string())
+}
+
+fn last_change(hill: &mut Hill, line_nr: usize) -> Result<&mut Change, HillError> {
+    hill.history.last_mut().ok_or_else(|| malformed(line_nr, "expected a challenge first"))
+}
+
+/// Reads a ranking of alternating names and scores.
+fn parse_ranking(words: &[&str], line_nr: usize) -> Result<Ranking, HillError> {
+    if !words.len().is_multiple_of(2) {
+        return Err(malformed(line_nr, "expected a name and a score for every bot"));
+    }
+    words.chunks(2)
+        .map(|pair| {
+            pair[1]
+                .parse()
+                .map(|score| (pair[0].to_string(), score))
+                .map_err(|_| malformed(line_nr, "invalid score"))
+        })
+        .collect()
+}
+
+fn write_ranking(f: &mut fmt::Formatter, keyword: &str, ranking: &Ranking) -> fmt::Result {
+    write!(f, "{}", keyword)?;
+    for (name, score) in ranking {
+        write!(f, " {} {}", name, score)?;
+    }
+    writeln!(f)
+}
+
+/// Writes the state file.
+impl fmt::Display for Hill {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "size {}", self.size)?;
+        writeln!(f, "scoring {}", self.scoring.name())?;
+        for member in &self.members {
+            writeln!(f, "member {}", member.name)?;
+        }
+        for ((a, b), points) in &self.results {
+            writeln!(f, "result {} {} {}", a, b, points)?;
+        }
+        for change in &self.history {
+            writeln!(f, "challenge {}", change.challenger)?;
+            write_ranking(f, "before", &change.before)?;
+            write_ranking(f, "after", &change.after)?;
+            if let Some(ref evicted) = change.evicted {
+                writeln!(f, "evicted {}", evicted)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    const CLEAR: &str = ">>>>>>>>>([-]>)*21";
+
+    fn make_dir(name: &str) -> PathBuf {
+        let dir =
+            env::temp_dir().join(format!("bf_bot_tournament_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn challenge_fullHill_evictsLowestRanked() {
+        let dir = make_dir("evict");
+        let mut hill = Hill::create(&dir, 1, Scoring::Points).unwrap();
+        hill.challenge("idle", ".").unwrap();
+        let change = hill.challenge("clear", CLEAR).unwrap().clone();
+        assert_eq!(change.before, vec![("idle".to_string(), 0.0)]);
+        assert_eq!(change.after, vec![("clear".to_string(), 0.0)]);
+        assert_eq!(change.evicted, Some("idle".to_string()));
+        assert_eq!(change.to_string(), "clear entered the hill at rank 1; idle was evicted");
+        assert!(!dir.join("idle.bf").exists());
+        assert!(dir.join("clear.bf").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn challenge_weakChallenger_evictsItself() {
+        let dir = make_dir("reject");
+        let mut hill = Hill::create(&dir, 2, Scoring::Markov).unwrap();
+        hill.challenge("idle", ".").unwrap();
+        hill.challenge("clear", CLEAR).unwrap();
+        let change = hill.challenge("suicide", "<").unwrap().clone();
+        assert_eq!(change.evicted, Some("suicide".to_string()));
+        assert_eq!(change.to_string(), "suicide did not make it onto the hill");
+        assert_eq!(hill.get_members(), vec!["idle", "clear"]);
+        assert!(!dir.join("suicide.bf").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn challenge_takenName_isRejected() {
+        let dir = make_dir("taken");
+        let mut hill = Hill::create(&dir, 2, Scoring::Points).unwrap();
+        hill.challenge("idle", ".").unwrap();
+        match hill.challenge("idle", CLEAR) {
+            Err(HillError::NameTaken(ref name)) => assert_eq!(name, "idle"),
+            other => panic!("Expected a taken name, got {:?}", other),
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn challenge_nameLeadingOutOfDirectory_isRejected() {
+        let dir = make_dir("escape");
+        let mut hill = Hill::create(&dir, 2, Scoring::Points).unwrap();
+        for name in &["a/b", "../x", "..", "a\\..\\b"] {
+            match hill.challenge(name, ".") {
+                Err(HillError::InvalidName(ref invalid)) => assert_eq!(invalid, name),
+                other => panic!("Expected an invalid name, got {:?}", other),
+            }
+        }
+        assert!(hill.get_members().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn challenge_sourceFileCantBeWritten_leavesHillUnchanged() {
+        let dir = make_dir("unwritable");
+        let mut hill = Hill::create(&dir, 2, Scoring::Points).unwrap();
+        hill.challenge("idle", ".").unwrap();
+        fs::create_dir(dir.join("clear.bf")).unwrap();
+        match hill.challenge("clear", CLEAR) {
+            Err(HillError::Io(ref path, _)) => assert_eq!(path, &dir.join("clear.bf")),
+            other => panic!("Expected an io error, got {:?}", other),
+        }
+        assert_eq!(hill.get_members(), vec!["idle"]);
+        assert_eq!(hill.get_history().len(), 1);
+        assert_eq!(Hill::open(&dir).unwrap().get_history(), hill.get_history());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn open_restoresMembersResultsAndHistory() {
+        let dir = make_dir("reopen");
+        let mut hill = Hill::create(&dir, 2, Scoring::Markov).unwrap();
+        hill.challenge("idle", ".").unwrap();
+        hill.challenge("clear", CLEAR).unwrap();
+        hill.challenge("suicide", "<").unwrap();
+        let reopened = Hill::open(&dir).unwrap();
+        assert_eq!(reopened.get_size(), 2);
+        assert_eq!(reopened.get_scoring(), Scoring::Markov);
+        assert_eq!(reopened.get_members(), hill.get_members());
+        assert_eq!(reopened.get_history(), hill.get_history());
+        assert_eq!(reopened.leaderboard(), hill.leaderboard());
+        assert!(Hill::create(&dir, 2, Scoring::Markov).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn toJson_change_writesRankingsAndEviction() {
+        let change = Change {
+            challenger: "clear".to_string(),
+            before: vec![("idle".to_string(), 0.0)],
+            after: vec![("clear".to_string(), 1.5)],
+            evicted: Some("idle".to_string()),
+        };
+        assert_eq!(change.to_json(),
+                   "{\"challenger\":\"clear\",\"rank\":1,\"evicted\":\"idle\",\
+                    \"before\":[{\"name\":\"idle\",\"score\":0}],\
+                    \"after\":[{\"name\":\"clear\",\"score\":1.5}]}");
+    }
+
+    #[test]
+    fn parse_unknownLine_reportsLine() {
+        match Hill::parse(Path::new("."), "size 3\nscoring points\nking idle\n") {
+            Err(HillError::Malformed(line, _)) => assert_eq!(line, 3),
+            other => panic!("Expected a malformed hill, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn open_missingResult_isMalformed() {
+        let dir = make_dir("missing_result");
+        let mut hill = Hill::create(&dir, 3, Scoring::Points).unwrap();
+        hill.challenge("idle", ".").unwrap();
+        hill.challenge("clear", CLEAR).unwrap();
+        let state_path = dir.join(STATE_FILE);
+        let state = fs::read_to_string(&state_path).unwrap();
+        let without_result: String = state.lines()
+            .filter(|line| !line.starts_with("result"))
+            .map(|line| line.to_string() + "\n")
+            .collect();
+        fs::write(&state_path, without_result).unwrap();
+        match Hill::open(&dir) {
+            Err(HillError::Malformed(line, ref description)) => {
+                assert_eq!(line, 4);
+                assert_eq!(description, "missing result of idle against clear");
+            }
+            other => panic!("Expected a malformed hill, got {:?}", other),
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn parse_resultOfNonMember_reportsLine() {
+        match Hill::parse(Path::new("."), "size 3\nmember idle\nresult idle clear 2\n") {
+            Err(HillError::Malformed(line, _)) => assert_eq!(line, 3),
+            other => panic!("Expected a malformed hill, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_duplicateResult_reportsLine() {
+        let text = "size 3\nmember idle\nmember clear\nresult clear idle 2\nresult idle clear -2\n";
+        match Hill::parse(Path::new("."), text) {
+            Err(HillError::Malformed(line, _)) => assert_eq!(line, 5),
+            other => panic!("Expected a malformed hill, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Ranks the entrants of a round robin.
+//! Entrants are ranked on their score, then on their total points over all of their games, then
+//! on their number of games won, and finally on their name, so the ranking is always the same for
+//! the same results.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use bf_bot_core::json::{Object, ToJson};
+use round_robin::RoundRobin;
+use scoring::Scoring;
+
+/// The totals of a single entrant.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Standing {
+    pub name: String,
+    /// The score the entrants are ranked on.
+    pub score: f64,
+    /// The sum of the points of all games, from -42 to 42 per game.
+    pub points: i32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+/// The entrants of a round robin from best to worst, with their results against each other.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Leaderboard {
+    scoring: Scoring,
+    standings: Vec<Standing>,
+    head_to_head: Vec<Vec<Option<i8>>>,
+}
+
+impl Leaderboard {
+    pub fn new(round_robin: &RoundRobin, scoring: Scoring) -> Leaderboard {
+        let mut standings: Vec<(usize, Standing)> = scoring.scores(round_robin)
+            .into_iter()
+            .enumerate()
+            .map(|(entrant, score)| (entrant, standing(round_robin, entrant, score)))
+            .collect();
+        standings.sort_by(|(_, a), (_, b)| compare_standings(a, b));
+        let head_to_head = standings.iter()
+            .map(|&(a, _)| standings.iter().map(|&(b, _)| round_robin.get_points(a, b)).collect())
+            .collect();
+        Leaderboard {
+            scoring,
+            standings: standings.into_iter().map(|(_, standing)| standing).collect(),
+            head_to_head,
+        }
+    }
+
+    pub fn get_scoring(&self) -> Scoring {
+        self.scoring
+    }
+
+    /// Returns the standings, best first.
+    pub fn get_standings(&self) -> &[Standing] {
+        &self.standings
+    }
+
+    /// Returns the points the entrant at rank `a` scored against the one at rank `b`, both
+    /// counted from 0, or `None` if they are the same entrant.
+    pub fn get_head_to_head(&self, a: usize, b: usize) -> Option<i8> {
+        self.head_to_head[a][b]
+    }
+}
+
+fn standing(round_robin: &RoundRobin, entrant: usize, score: f64) -> Standing {
+    let mut standing = Standing {
+        name: round_robin.get_names()[entrant].clone(),
+        score,
+        points: 0,
+        wins: 0,
+        losses: 0,
+        draws: 0,
+    };
+    let results = (0..round_robin.nr_entrants())
+        .filter_map(|opponent| round_robin.get_points(entrant, opponent));
+    for points in results {
+        standing.points += i32::from(points);
+        match points.cmp(&0) {
+            Ordering::Greater => standing.wins += 1,
+            Ordering::Less => standing.losses += 1,
+            Ordering::Equal => standing.draws += 1,
+        }
+    }
+    standing
+}
+
+fn compare_standings(a: &Standing, b: &Standing) -> Ordering {
+    b.score
+        .partial_cmp(&a.score)
+        .unwrap_or(Ordering::Equal)
+        .then(b.points.cmp(&a.points))
+        .then(b.wins.cmp(&a.wins))
+        .then_with(|| a.name.cmp(&b.name))
+}
+
+/// Writes the standings as a table, with the head-to-head matrix on the right. The columns of the
+/// matrix are numbered by rank. The score only gets a column of its own if it isn't the points.
+impl fmt::Display for Leaderboard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name_width =
+            self.standings.iter().map(|standing| standing.name.len()).max().unwrap_or(0);
+        let show_score = self.scoring != Scoring::Points;
+        write!(f, "{:>4}  {:<width$} ", "rank", "name", width = name_width)?;
+        if show_score {
+            write!(f, "{:>8} ", "score")?;
+        }
+        write!(f, "{:>6} {:>4} {:>4} {:>4} |",
+               "points",
+               "W",
+               "L",
+               "D")?;
+        for rank in 1..self.standings.len() + 1 {
+            write!(f, " {:>4}", rank)?;
+        }
+        writeln!(f)?;
+        for (rank, standing) in self.standings.iter().enumerate() {
+            write!(f, "{:>4}  {:<width$} ", rank + 1, standing.name, width = name_width)?;
+            if show_score {
+                write!(f, "{:>8.2} ", standing.score)?;
+            }
+            write!(f, "{:>6} {:>4} {:>4} {:>4} |",
+                   standing.points,
+                   standing.wins,
+                   standing.losses,
+                   standing.draws)?;
+            for points in &self.head_to_head[rank] {
+                match *points {
+                    Some(points) => write!(f, " {:>+4}", points)?,
+                    None => write!(f, " {:>4}", "-")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for Standing {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("name", &self.name)
+            .field("score", &self.score)
+            .field("points", &self.points)
+            .field("wins", &self.wins)
+            .field("losses", &self.losses)
+            .field("draws", &self.draws)
+            .build()
+    }
+}
+
+impl ToJson for Leaderboard {
+    fn to_json(&self) -> String {
+        self.json_fields(Object::new()).build()
+    }
+}
+
+impl Leaderboard {
+    /// Adds the fields of the leaderboard to a JSON object.
+    pub fn json_fields(&self, object: Object) -> Object {
+        object.field("scoring", self.scoring.name())
+            .field("standings", &self.standings)
+            .field("head_to_head", &self.head_to_head)
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf_bot_compiler::parser;
+    use bf_bot_core::bf::Bot;
+    use round_robin::{self, Entrant};
+
+    fn make_leaderboard(scoring: Scoring) -> Leaderboard {
+        let sources = [("suicide", "<"), ("idle", "."), ("clear", ">>>>>>>>>([-]>)*21")];
+        let entrants: Vec<Entrant> = sources.iter()
+            .map(|&(name, source_code)| {
+                Entrant::new(name, Bot::new(parser::parse_bot(source_code.to_string())))
+            })
+            .collect();
+        Leaderboard::new(&round_robin::play(&entrants, 1), scoring)
+    }
+
+    #[test]
+    fn new_ranksOnPoints() {
+        let leaderboard = make_leaderboard(Scoring::Points);
+        let names: Vec<&str> =
+            leaderboard.get_standings().iter().map(|standing| standing.name.as_str()).collect();
+        assert_eq!(names, vec!["clear", "idle", "suicide"]);
+        let suicide = &leaderboard.get_standings()[2];
+        assert_eq!((suicide.points, suicide.wins, suicide.losses, suicide.draws),
+                   (-84, 0, 2, 0));
+    }
+
+    #[test]
+    fn new_headToHeadFollowsRanking() {
+        let leaderboard = make_leaderboard(Scoring::Points);
+        assert_eq!(leaderboard.get_head_to_head(1, 2), Some(42));
+        assert_eq!(leaderboard.get_head_to_head(2, 1), Some(-42));
+        assert_eq!(leaderboard.get_head_to_head(0, 0), None);
+    }
+
+    #[test]
+    fn compareStandings_tiedPoints_moreWinsFirst() {
+        let make_standing = |name: &str, wins, draws| {
+            Standing {
+                name: name.to_string(),
+                score: 0.0,
+                points: 0,
+                wins,
+                losses: wins,
+                draws,
+            }
+        };
+        assert_eq!(compare_standings(&make_standing("a", 0, 2), &make_standing("b", 1, 0)),
+                   Ordering::Greater);
+        assert_eq!(compare_standings(&make_standing("a", 1, 0), &make_standing("b", 1, 0)),
+                   Ordering::Less);
+    }
+
+    #[test]
+    fn new_markovScoring_ranksOnScore() {
+        let round_robin = RoundRobin::from_points(vec!["a".to_string(), "b".to_string()],
+                                                  &[(0, 1, -2)]);
+        let leaderboard = Leaderboard::new(&round_robin, Scoring::Markov);
+        let best = &leaderboard.get_standings()[0];
+        assert_eq!(best.name, "b");
+        assert!((best.score - 50.0 * 44.0 / 42.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn toJson_includesHeadToHeadInRankOrder() {
+        let round_robin = RoundRobin::from_points(vec!["a".to_string(), "b".to_string()],
+                                                  &[(0, 1, -2)]);
+        let json = Leaderboard::new(&round_robin, Scoring::Points).to_json();
+        assert!(json.starts_with("{\"scoring\":\"points\",\"standings\":[{\"name\":\"b\""));
+        assert!(json.ends_with(",\"head_to_head\":[[null,2],[-2,null]]}"));
+    }
+
+    #[test]
+    fn display_showsOneLinePerEntrant() {
+        let table = make_leaderboard(Scoring::Points).to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[3].starts_with("   3  suicide"));
+        assert!(lines[3].ends_with(" -42    -"));
+        assert!(!lines[0].contains("score"));
+    }
+
+    #[test]
+    fn display_otherScoring_showsScore() {
+        let table = make_leaderboard(Scoring::Markov).to_string();
+        assert!(table.lines().next().unwrap().contains("score"));
+    }
+}
@@ -0,0 +1,18 @@
+//! Ranks collections of BrainFuck Joust bots against each other.
+//! A `round_robin` plays a complete game between every pair of bots, and the `leaderboard` ranks
+//! the bots on their points, or on one of the other ways of `scoring` them. A `swiss` tournament
+//! ranks large collections at a fraction of the cost of a round robin. A `hill` keeps the
+//! best bots of a long-running competition on disk, and `ratings` rate bots that never all play
+//! each other. A `gauntlet` is a fixed benchmark of opponents to score candidate bots against,
+//! and a `regression` compares two versions of a bot on one.
+extern crate bf_bot_core;
+extern crate bf_bot_compiler;
+
+pub mod round_robin;
+pub mod leaderboard;
+pub mod scoring;
+pub mod hill;
+pub mod ratings;
+pub mod swiss;
+pub mod gauntlet;
+pub mod regression;
@@ -0,0 +1,462 @@
+//! Elo and Glicko-2 ratings for bots that don't all play each other.
+//! A round robin needs every pair of bots to play, which doesn't scale to the thousands of bots an
+//! evolution run produces. Ratings are updated one game at a time instead, from whichever games
+//! happen to be played. Bots are identified by a hash of their program, so the same bot keeps its
+//! rating under a different name, and with different comments or formatting.
+//!
+//! Every game counts as a single win, loss or draw, depending on the sign of its points, so games
+//! with any number of rounds can be rated. Glicko-2 treats every game as a rating period of its
+//! own.
+//!
+//! Ratings are saved as a text file with one bot per line: its hash, its Elo rating, its Glicko-2
+//! rating, deviation and volatility, and its number of games.
+//!
+//! ```text
+//! 5f0ad8c3a9b1e2d4 1516 1662.3 290.3 0.06 1
+//! c2e3d1a4b5f60718 1484 1337.7 290.3 0.06 1
+//! ```
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use bf_bot_core::bf::{Bot, Instruction};
+use bf_bot_core::json::{Object, ToJson};
+use bf_bot_core::simul_game::game_result::GameResult;
+use round_robin::RoundRobin;
+
+/// The ratio between the Glicko scale and the Glicko-2 scale.
+const GLICKO2_SCALE: f64 = 173.7178;
+
+/// The precision to which the new volatility is computed.
+const VOLATILITY_TOLERANCE: f64 = 0.000_001;
+
+/// Returns a hash of the program of the bot. It only depends on the instructions, so comments
+/// and formatting don't change it, and it is the same on every platform and in every version.
+pub fn content_hash(bot: &Bot) -> u64 {
+    // 64-bit FNV-1a over the source code of the program without any formatting.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut feed = |text: &str| {
+        for byte in text.bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    for instruction in bot.get_program() {
+        match *instruction {
+            Instruction::MoveBack => feed("<"),
+            Instruction::MoveForward => feed(">"),
+            Instruction::Increment => feed("+"),
+            Instruction::Decrement => feed("-"),
+            Instruction::StartWhileNotZero { .. } => feed("["),
+            Instruction::EndWhileNotZero { .. } => feed("]"),
+            Instruction::SkipExecution => feed("."),
+            Instruction::StartFor { .. } => feed("("),
+            Instruction::EndFor { nr_iterations, .. } => feed(&format!(")*{}", nr_iterations)),
+        }
+    }
+    hash
+}
+
+/// Settings of both rating systems.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RatingParams {
+    /// The Elo rating of a bot that hasn't played yet.
+    pub initial_elo: f64,
+    /// The largest change of an Elo rating in a single game.
+    pub elo_k: f64,
+    /// The Glicko-2 rating of a bot that hasn't played yet, on the Glicko scale.
+    pub initial_rating: f64,
+    pub initial_deviation: f64,
+    pub initial_volatility: f64,
+    /// Constrains the change in volatility over time. Reasonable values are from 0.3 to 1.2.
+    pub tau: f64,
+}
+
+impl Default for RatingParams {
+    fn default() -> RatingParams {
+        RatingParams {
+            initial_elo: 1500.0,
+            elo_k: 32.0,
+            initial_rating: 1500.0,
+            initial_deviation: 350.0,
+            initial_volatility: 0.06,
+            tau: 0.5,
+        }
+    }
+}
+
+/// The ratings of a single bot.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rating {
+    pub elo: f64,
+    /// The Glicko-2 rating, on the Glicko scale.
+    pub rating: f64,
+    /// The Glicko-2 rating deviation, on the Glicko scale.
+    pub deviation: f64,
+    pub volatility: f64,
+    pub games: u32,
+}
+
+impl ToJson for Rating {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("elo", &self.elo)
+            .field("rating", &self.rating)
+            .field("deviation", &self.deviation)
+            .field("volatility", &self.volatility)
+            .field("games", &self.games)
+            .build()
+    }
+}
+
+/// Reasons why saved ratings can't be read.
+#[derive(Debug)]
+pub enum RatingsError {
+    Io(io::Error),
+    /// The file is not valid. Holds the line number and a description.
+    Malformed(usize, String),
+}
+
+impl fmt::Display for RatingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RatingsError::Io(ref error) => write!(f, "{}", error),
+            RatingsError::Malformed(line, ref description) => {
+                write!(f, "line {}: {}", line, description)
+            }
+        }
+    }
+}
+
+/// The ratings of any number of bots, keyed on the hashes of their programs.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ratings {
+    params: RatingParams,
+    ratings: BTreeMap<u64, Rating>,
+}
+
+impl Ratings {
+    pub fn new(params: RatingParams) -> Ratings {
+        Ratings {
+            params,
+            ratings: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the ratings of the bot, if it has played.
+    pub fn get(&self, hash: u64) -> Option<&Rating> {
+        self.ratings.get(&hash)
+    }
+
+    /// Returns the ratings of the bot, or the initial ratings if it hasn't played yet.
+    pub fn get_or_initial(&self, hash: u64) -> Rating {
+        self.ratings.get(&hash).cloned().unwrap_or_else(|| self.initial())
+    }
+
+    pub fn len(&self) -> usize {
+        self.ratings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ratings.is_empty()
+    }
+
+    /// Returns every rated bot, best Glicko-2 rating first.
+    pub fn ranked(&self) -> Vec<(u64, &Rating)> {
+        let mut ranked: Vec<(u64, &Rating)> =
+            self.ratings.iter().map(|(&hash, rating)| (hash, rating)).collect();
+        ranked.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating).then(a.0.cmp(&b.0)));
+        ranked
+    }
+
+    /// Updates the ratings of both bots with the result of a game between them.
+    pub fn record(&mut self, bot_a: u64, bot_b: u64, result: &GameResult) {
+        let score = if result.bot_a_points > 0 {
+            1.0
+        } else if result.bot_a_points < 0 {
+            0.0
+        } else {
+            0.5
+        };
+        let a = self.get_or_initial(bot_a);
+        let b = self.get_or_initial(bot_b);
+        let new_a = self.update(&a, &b, score);
+        let new_b = self.update(&b, &a, 1.0 - score);
+        self.ratings.insert(bot_a, new_a);
+        self.ratings.insert(bot_b, new_b);
+    }
+
+    /// Updates the ratings with a stream of games, in order.
+    pub fn record_all<'a, I>(&mut self, games: I)
+        where I: IntoIterator<Item = (u64, u64, &'a GameResult)>
+    {
+        for (bot_a, bot_b, result) in games {
+            self.record(bot_a, bot_b, result);
+        }
+    }
+
+    /// Updates the ratings with every game of a round robin. The hashes are those of the
+    /// entrants, in the order they entered.
+    pub fn record_round_robin(&mut self, round_robin: &RoundRobin, hashes: &[u64]) {
+        assert_eq!(hashes.len(), round_robin.nr_entrants(), "Every entrant needs a hash.");
+        for a in 0..hashes.len() {
+            for b in a + 1..hashes.len() {
+                let bot_a_points = round_robin.get_points(a, b).unwrap();
+                let result = GameResult {
+                    bot_a_points,
+                    bot_b_points: -bot_a_points,
+                };
+                self.record(hashes[a], hashes[b], &result);
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// Reads saved ratings, to be updated with the given settings.
+    pub fn load(path: &Path, params: RatingParams) -> Result<Ratings, RatingsError> {
+        let text = fs::read_to_string(path).map_err(RatingsError::Io)?;
+        Ratings::parse(&text, params)
+    }
+
+    pub fn parse(text: &str, params: RatingParams) -> Result<Ratings, RatingsError> {
+        let mut ratings = Ratings::new(params);
+        for (index, line) in text.lines().enumerate() {
+            let malformed = || {
+                RatingsError::Malformed(index + 1,
+                                        "expected '<hash> <elo> <rating> <deviation> \
+                                         <volatility> <games>'"
+                                            .to_string())
+            };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.is_empty() {
+                continue;
+            }
+            if words.len() != 6 {
+                return Err(malformed());
+            }
+            let hash = u64::from_str_radix(words[0], 16).map_err(|_| malformed())?;
+            let number = |word: &str| {
+                word.parse::<f64>().ok().filter(|number| number.is_finite()).ok_or_else(malformed)
+            };
+            let rating = Rating {
+                elo: number(words[1])?,
+                rating: number(words[2])?,
+                deviation: number(words[3])?,
+                volatility: number(words[4])?,
+                games: words[5].parse().map_err(|_| malformed())?,
+            };
+            ratings.ratings.insert(hash, rating);
+        }
+        Ok(ratings)
+    }
+
+    fn initial(&self) -> Rating {
+        Rating {
+            elo: self.params.initial_elo,
+            rating: self.params.initial_rating,
+            deviation: self.params.initial_deviation,
+            volatility: self.params.initial_volatility,
+            games: 0,
+        }
+    }
+
+    /// Returns the new ratings of a bot that scored the given score against the opponent.
+    fn update(&self, bot: &Rating, opponent: &Rating, score: f64) -> Rating {
+        let expected_elo = 1.0 / (1.0 + 10f64.powf((opponent.elo - bot.elo) / 400.0));
+        let (rating, deviation, volatility) = self.update_glicko2(bot, opponent, score);
+        Rating {
+            elo: bot.elo + self.params.elo_k * (score - expected_elo),
+            rating,
+            deviation,
+            volatility,
+            games: bot.games + 1,
+        }
+    }
+
+    /// The Glicko-2 update for a rating period with a single game, following Glickman's
+    /// description of the algorithm.
+    fn update_glicko2(&self, bot: &Rating, opponent: &Rating, score: f64) -> (f64, f64, f64) {
+        let mu = (bot.rating - 1500.0) / GLICKO2_SCALE;
+        let phi = bot.deviation / GLICKO2_SCALE;
+        let opponent_mu = (opponent.rating - 1500.0) / GLICKO2_SCALE;
+        let opponent_phi = opponent.deviation / GLICKO2_SCALE;
+        let g = 1.0 / (1.0 + 3.0 * opponent_phi * opponent_phi / (PI * PI)).sqrt();
+        let expected = 1.0 / (1.0 + (-g * (mu - opponent_mu)).exp());
+        let variance = 1.0 / (g * g * expected * (1.0 - expected));
+        let delta = variance * g * (score - expected);
+
+        let tau = self.params.tau;
+        let a = (bot.volatility * bot.volatility).ln();
+        let f = |x: f64| {
+            let ex = x.exp();
+            ex * (delta * delta - phi * phi - variance - ex) /
+            (2.0 * (phi * phi + variance + ex).powi(2)) - (x - a) / (tau * tau)
+        };
+        let mut lower = a;
+        let mut upper = if delta * delta > phi * phi + variance {
+            (delta * delta - phi * phi - variance).ln()
+        } else {
+            let mut k = 1.0;
+            while f(a - k * tau) < 0.0 {
+                k += 1.0;
+            }
+            a - k * tau
+        };
+        let mut f_lower = f(lower);
+        let mut f_upper = f(upper);
+        while (upper - lower).abs() > VOLATILITY_TOLERANCE {
+            let middle = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+            let f_middle = f(middle);
+            if f_middle * f_upper <= 0.0 {
+                lower = upper;
+                f_lower = f_upper;
+            } else {
+                f_lower /= 2.0;
+            }
+            upper = middle;
+            f_upper = f_middle;
+        }
+        let volatility = (lower / 2.0).exp();
+
+        let pre_period_phi = (phi * phi + volatility * volatility).sqrt();
+        let new_phi = 1.0 / (1.0 / (pre_period_phi * pre_period_phi) + 1.0 / variance).sqrt();
+        let new_mu = mu + new_phi * new_phi * g * (score - expected);
+        (1500.0 + GLICKO2_SCALE * new_mu, GLICKO2_SCALE * new_phi, volatility)
+    }
+}
+
+/// Writes the ratings in the format `parse` reads.
+impl fmt::Display for Ratings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (hash, rating) in &self.ratings {
+            writeln!(f,
+                     "{:016x} {} {} {} {} {}",
+                     hash,
+                     rating.elo,
+                     rating.rating,
+                     rating.deviation,
+                     rating.volatility,
+                     rating.games)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf_bot_compiler::parser;
+
+    fn make_bot(source_code: &str) -> Bot {
+        Bot::new(parser::parse_bot(source_code.to_string()))
+    }
+
+    fn win() -> GameResult {
+        GameResult {
+            bot_a_points: 10,
+            bot_b_points: -10,
+        }
+    }
+
+    #[test]
+    fn contentHash_ignoresFormatting() {
+        assert_eq!(content_hash(&make_bot(">>[-] (+)*3")), content_hash(&make_bot(">> [-](+)*3")));
+        assert!(content_hash(&make_bot("(+)*3")) != content_hash(&make_bot("(+)*4")));
+    }
+
+    #[test]
+    fn record_winnerGainsWhatLoserLoses() {
+        let mut ratings = Ratings::new(RatingParams::default());
+        ratings.record(1, 2, &win());
+        let winner = ratings.get(1).unwrap();
+        let loser = ratings.get(2).unwrap();
+        assert_eq!((winner.elo, loser.elo), (1516.0, 1484.0));
+        assert!(winner.rating > 1500.0 && loser.rating < 1500.0);
+        assert!((winner.rating - 1500.0 - (1500.0 - loser.rating)).abs() < 1e-9);
+        assert!(winner.deviation < 350.0);
+        assert_eq!(winner.games, 1);
+    }
+
+    /// The example from Glickman's description of Glicko-2, with its three games played as
+    /// separate rating periods, stays close to the published result of 1464.06.
+    #[test]
+    fn record_glickmansExample_movesTheRightWay() {
+        let mut ratings = Ratings::new(RatingParams::default());
+        let set = |ratings: &mut Ratings, hash, rating, deviation| {
+            ratings.ratings.insert(hash,
+                                   Rating {
+                                       elo: 1500.0,
+                                       rating,
+                                       deviation,
+                                       volatility: 0.06,
+                                       games: 1,
+                                   });
+        };
+        set(&mut ratings, 0, 1500.0, 200.0);
+        set(&mut ratings, 1, 1400.0, 30.0);
+        set(&mut ratings, 2, 1550.0, 100.0);
+        set(&mut ratings, 3, 1700.0, 300.0);
+        let loss = || {
+            GameResult {
+                bot_a_points: -1,
+                bot_b_points: 1,
+            }
+        };
+        let opponents = [(1, win()), (2, loss()), (3, loss())];
+        for &(opponent, ref result) in &opponents {
+            let reset = ratings.get(opponent).unwrap().clone();
+            ratings.record(0, opponent, result);
+            ratings.ratings.insert(opponent, reset);
+        }
+        let rating = ratings.get(0).unwrap();
+        assert!((rating.rating - 1464.06).abs() < 15.0, "{}", rating.rating);
+        assert!(rating.deviation < 200.0);
+    }
+
+    #[test]
+    fn recordRoundRobin_ratesEveryEntrant() {
+        let round_robin = RoundRobin::from_points(vec!["a".to_string(), "b".to_string()],
+                                                  &[(0, 1, -4)]);
+        let mut ratings = Ratings::new(RatingParams::default());
+        ratings.record_round_robin(&round_robin, &[7, 9]);
+        assert_eq!(ratings.ranked().iter().map(|&(hash, _)| hash).collect::<Vec<u64>>(),
+                   vec![9, 7]);
+    }
+
+    #[test]
+    fn parse_roundTripsThroughDisplay() {
+        let mut ratings = Ratings::new(RatingParams::default());
+        ratings.record_all(vec![(1, 2, &win()), (2, 3, &win())]);
+        let parsed = Ratings::parse(&ratings.to_string(), RatingParams::default()).unwrap();
+        assert_eq!(parsed, ratings);
+    }
+
+    #[test]
+    fn parse_nonFiniteNumber_reportsLine() {
+        for number in &["nan", "inf", "-inf"] {
+            let text = format!("00000000000000ff 1500 1500 350 0.06 0\nff 1500 {} 350 0.06 0\n",
+                               number);
+            match Ratings::parse(&text, RatingParams::default()) {
+                Err(RatingsError::Malformed(line, _)) => assert_eq!(line, 2),
+                other => panic!("Expected malformed ratings, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_brokenLine_reportsLine() {
+        match Ratings::parse("00000000000000ff 1500 1500 350 0.06 0\nff 1500\n",
+                             RatingParams::default()) {
+            Err(RatingsError::Malformed(line, _)) => assert_eq!(line, 2),
+            other => panic!("Expected malformed ratings, got {:?}", other),
+        }
+    }
+}
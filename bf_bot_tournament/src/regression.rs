@@ -0,0 +1,267 @@
+//! Compares two versions of a bot on a gauntlet.
+//! Both versions play a complete game against every opponent of the gauntlet, and every round
+//! whose outcome differs between the versions is reported, along with the tape length and
+//! polarity it was played on. This shows right away which opponents and tape configurations an
+//! edit broke or fixed, and how much the score against the gauntlet changed as a result.
+
+use std::fmt;
+
+use bf_bot_core::bf::Bot;
+use bf_bot_core::json::{Object, ToJson};
+use bf_bot_core::simul_game::game_record::GameRecord;
+use bf_bot_core::simul_round::RoundResult;
+use gauntlet::{Gauntlet, GauntletReport, OpponentResult};
+use round_robin::{self, Entrant};
+
+/// The outcome of a single round, from the point of view of the compared bot.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl Outcome {
+    /// Returns the outcome for the first bot of the round.
+    pub fn from_result(result: &RoundResult) -> Outcome {
+        match (result.bot_a_lost, result.bot_b_lost) {
+            (false, true) => Outcome::Win,
+            (true, false) => Outcome::Loss,
+            _ => Outcome::Draw,
+        }
+    }
+
+    /// Returns the points the outcome is worth.
+    pub fn points(self) -> i8 {
+        match self {
+            Outcome::Win => 1,
+            Outcome::Loss => -1,
+            Outcome::Draw => 0,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Win => "win",
+            Outcome::Loss => "loss",
+            Outcome::Draw => "draw",
+        }
+    }
+}
+
+/// A round with a different outcome for the new version of the bot than for the old one.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RoundChange {
+    pub opponent: String,
+    pub tape_length: u32,
+    pub invert_polarity: bool,
+    pub old: Outcome,
+    pub new: Outcome,
+}
+
+impl RoundChange {
+    /// Returns true if the new version does better in this round than the old one.
+    pub fn is_improvement(&self) -> bool {
+        self.new.points() > self.old.points()
+    }
+}
+
+impl fmt::Display for RoundChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "{} {}: tape {:>2} {}, {} -> {}",
+               if self.is_improvement() { "fixed" } else { "broke" },
+               self.opponent,
+               self.tape_length,
+               if self.invert_polarity { "reversed" } else { "normal" },
+               self.old.name(),
+               self.new.name())
+    }
+}
+
+/// The differences between two versions of a bot on a gauntlet.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Regression {
+    old: GauntletReport,
+    new: GauntletReport,
+    changes: Vec<RoundChange>,
+}
+
+/// Plays both versions of the bot against every opponent of the gauntlet, spread over the given
+/// number of threads, and compares the outcomes of every round.
+pub fn compare(old: &Bot, new: &Bot, gauntlet: &Gauntlet, nr_threads: usize) -> Regression {
+    let opponents = gauntlet.get_opponents();
+    let mut entrants = vec![Entrant::new("old", old.clone()), Entrant::new("new", new.clone())];
+    entrants.extend(opponents.iter()
+        .map(|opponent| Entrant::new(&opponent.name, opponent.bot.clone())));
+    let pairs: Vec<(usize, usize)> = (0..2)
+        .flat_map(|version| (0..opponents.len()).map(move |opponent| (version, 2 + opponent)))
+        .collect();
+    let records = round_robin::record_pairs(&entrants, &pairs, nr_threads);
+    let (old_records, new_records) = records.split_at(opponents.len());
+    let mut changes = vec![];
+    for ((opponent, old_record), new_record) in opponents.iter().zip(old_records).zip(new_records) {
+        for (old_round, new_round) in old_record.rounds.iter().zip(&new_record.rounds) {
+            let old = Outcome::from_result(&old_round.result);
+            let new = Outcome::from_result(&new_round.result);
+            if old != new {
+                changes.push(RoundChange {
+                    opponent: opponent.name.clone(),
+                    tape_length: old_round.round_params.tape_length,
+                    invert_polarity: old_round.round_params.invert_polarity,
+                    old,
+                    new,
+                });
+            }
+        }
+    }
+    Regression {
+        old: make_report("old", gauntlet, old_records),
+        new: make_report("new", gauntlet, new_records),
+        changes,
+    }
+}
+
+fn make_report(candidate: &str, gauntlet: &Gauntlet, records: &[GameRecord]) -> GauntletReport {
+    GauntletReport {
+        candidate: candidate.to_string(),
+        results: gauntlet.get_opponents()
+            .iter()
+            .zip(records)
+            .map(|(opponent, record)| {
+                OpponentResult {
+                    name: opponent.name.clone(),
+                    weight: opponent.weight,
+                    tags: opponent.tags.clone(),
+                    points: record.to_result().bot_a_points,
+                }
+            })
+            .collect(),
+    }
+}
+
+impl Regression {
+    /// Returns the results of the old version against the gauntlet.
+    pub fn get_old(&self) -> &GauntletReport {
+        &self.old
+    }
+
+    /// Returns the results of the new version against the gauntlet.
+    pub fn get_new(&self) -> &GauntletReport {
+        &self.new
+    }
+
+    /// Returns every round with a different outcome, in the order of the opponents and rounds.
+    pub fn get_changes(&self) -> &[RoundChange] {
+        &self.changes
+    }
+
+    /// Returns the change in the weighted score against the gauntlet.
+    pub fn score_change(&self) -> f64 {
+        self.new.score() - self.old.score()
+    }
+
+    /// Returns true if the new version does worse than the old one in any round.
+    pub fn has_regressions(&self) -> bool {
+        self.changes.iter().any(|change| !change.is_improvement())
+    }
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name_width = self.old.results.iter().map(|result| result.name.len()).max().unwrap_or(0);
+        for (old, new) in self.old.results.iter().zip(&self.new.results) {
+            writeln!(f,
+                     "{:<width$} {:>+4} -> {:>+4} ({:+})",
+                     old.name,
+                     old.points,
+                     new.points,
+                     i32::from(new.points) - i32::from(old.points),
+                     width = name_width)?;
+        }
+        for change in &self.changes {
+            writeln!(f, "  {}", change)?;
+        }
+        writeln!(f,
+                 "score {:.2} -> {:.2} ({:+.2})",
+                 self.old.score(),
+                 self.new.score(),
+                 self.score_change())
+    }
+}
+
+impl ToJson for RoundChange {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("opponent", &self.opponent)
+            .field("tape_length", &self.tape_length)
+            .field("polarity", if self.invert_polarity { "reversed" } else { "normal" })
+            .field("old", self.old.name())
+            .field("new", self.new.name())
+            .build()
+    }
+}
+
+impl ToJson for Regression {
+    fn to_json(&self) -> String {
+        self.json_fields(Object::new()).build()
+    }
+}
+
+impl Regression {
+    /// Adds the fields of the regression to a JSON object.
+    pub fn json_fields(&self, object: Object) -> Object {
+        object.field("old", &self.old)
+            .field("new", &self.new)
+            .field("score_change", &self.score_change())
+            .field("changes", &self.changes)
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use bf_bot_compiler::parser;
+
+    fn make_bot(source_code: &str) -> Bot {
+        Bot::new(parser::parse_bot(source_code.to_string()))
+    }
+
+    fn make_gauntlet() -> Gauntlet {
+        Gauntlet::parse("idle.source = .\nclear.source = >>>>>>>>>([-]>)*21\n", Path::new(""))
+            .unwrap()
+    }
+
+    #[test]
+    fn compare_sameBot_noChanges() {
+        let bot = make_bot(">>>>>>>>>([-]>)*21");
+        let regression = compare(&bot, &bot, &make_gauntlet(), 2);
+        assert!(regression.get_changes().is_empty());
+        assert_eq!(regression.score_change(), 0.0);
+        assert!(!regression.has_regressions());
+    }
+
+    #[test]
+    fn compare_worseBot_reportsEveryBrokenRound() {
+        let regression = compare(&make_bot("."), &make_bot("<"), &make_gauntlet(), 1);
+        let changes = regression.get_changes();
+        // Against the idler, every draw became a loss. Against the clear, every round was
+        // already lost.
+        assert_eq!(changes.len(), 42);
+        assert!(changes.iter()
+            .all(|change| change.opponent == "idle" && change.new == Outcome::Loss));
+        assert_eq!(changes.iter().filter(|change| change.invert_polarity).count(), 21);
+        assert_eq!(regression.score_change(), -21.0);
+        assert!(regression.has_regressions());
+    }
+
+    #[test]
+    fn compare_betterBot_reportsImprovements() {
+        let regression = compare(&make_bot("<"), &make_bot("."), &make_gauntlet(), 3);
+        assert!(regression.get_changes().iter().all(RoundChange::is_improvement));
+        assert!(!regression.has_regressions());
+        assert!(regression.to_string().ends_with("score -42.00 -> -21.00 (+21.00)\n"));
+    }
+}
@@ -0,0 +1,180 @@
+//! Plays a complete game between every pair of bots.
+//! Games are zero-sum, so every pair only plays once: the points of the second bot are the
+//! negation of those of the first.
+
+use std::thread;
+
+use bf_bot_compiler::loader::LoadedBot;
+use bf_bot_core::bf::Bot;
+use bf_bot_core::simul_game::{self, AllRounds};
+use bf_bot_core::simul_game::game_record::GameRecord;
+
+/// A bot that takes part in a tournament.
+#[derive(Debug, Clone)]
+pub struct Entrant {
+    pub name: String,
+    pub bot: Bot,
+}
+
+impl Entrant {
+    pub fn new(name: &str, bot: Bot) -> Entrant {
+        Entrant {
+            name: name.to_string(),
+            bot,
+        }
+    }
+
+    /// Turns a bot that was read from a file into an entrant, named after the file.
+    pub fn from_loaded(loaded: &LoadedBot) -> Entrant {
+        Entrant {
+            name: loaded.name(),
+            bot: loaded.bot.clone(),
+        }
+    }
+}
+
+/// The outcome of a round robin: the points of every entrant against every other entrant.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RoundRobin {
+    names: Vec<String>,
+    points: Vec<Vec<Option<i8>>>,
+}
+
+impl RoundRobin {
+    /// Builds a round robin out of games that were played before. Every result is given as
+    /// `(a, b, points)`, with the points entrant `a` scored against entrant `b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a pair of entrants is missing, or if an entrant plays itself.
+    pub fn from_points(names: Vec<String>, results: &[(usize, usize, i8)]) -> RoundRobin {
+        let mut points = vec![vec![None; names.len()]; names.len()];
+        for &(a, b, a_points) in results {
+            assert!(a != b, "An entrant can't play itself.");
+            points[a][b] = Some(a_points);
+            points[b][a] = Some(-a_points);
+        }
+        for a in 0..names.len() {
+            for b in (0..names.len()).filter(|&b| b != a) {
+                assert!(points[a][b].is_some(), "{} never played {}.", names[a], names[b]);
+            }
+        }
+        RoundRobin { names, points }
+    }
+
+    /// Returns the names of the entrants, in the order they entered.
+    pub fn get_names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn nr_entrants(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns the points entrant `a` scored in its game against entrant `b`, or `None` if `a`
+    /// and `b` are the same entrant.
+    pub fn get_points(&self, a: usize, b: usize) -> Option<i8> {
+        self.points[a][b]
+    }
+}
+
+/// Plays a complete game between every pair of entrants, spread over the given number of threads.
+pub fn play(entrants: &[Entrant], nr_threads: usize) -> RoundRobin {
+    let pairs: Vec<(usize, usize)> = (0..entrants.len())
+        .flat_map(|a| (a + 1..entrants.len()).map(move |b| (a, b)))
+        .collect();
+    let results: Vec<(usize, usize, i8)> = pairs.iter()
+        .zip(play_pairs(entrants, &pairs, nr_threads))
+        .map(|(&(a, b), a_points)| (a, b, a_points))
+        .collect();
+    RoundRobin::from_points(entrants.iter().map(|entrant| entrant.name.clone()).collect(),
+                            &results)
+}
+
+/// Plays a complete game for every pair of entrants, spread over the given number of threads.
+/// Returns the points of the first entrant of every pair.
+pub(crate) fn play_pairs(entrants: &[Entrant],
+                         pairs: &[(usize, usize)],
+                         nr_threads: usize)
+                         -> Vec<i8> {
+    for_pairs(pairs, nr_threads, |a, b| {
+        simul_game::run_complete(&entrants[a].bot, &entrants[b].bot).bot_a_points
+    })
+}
+
+/// Like `play_pairs`, but returns a record of every round of every game.
+pub(crate) fn record_pairs(entrants: &[Entrant],
+                           pairs: &[(usize, usize)],
+                           nr_threads: usize)
+                           -> Vec<GameRecord> {
+    for_pairs(pairs, nr_threads, |a, b| {
+        simul_game::run_recorded(&entrants[a].bot, &entrants[b].bot, AllRounds::new())
+    })
+}
+
+/// Calls `play` for every pair, spread over the given number of threads, and returns the results
+/// in the order of the pairs.
+pub(crate) fn for_pairs<T, F>(pairs: &[(usize, usize)], nr_threads: usize, play: F) -> Vec<T>
+    where T: Send,
+          F: Fn(usize, usize) -> T + Sync
+{
+    let play = &play;
+    let play_chunk = move |pairs: &[(usize, usize)]| -> Vec<T> {
+        pairs.iter().map(|&(a, b)| play(a, b)).collect()
+    };
+    let nr_threads = nr_threads.max(1);
+    if nr_threads == 1 || pairs.len() < 2 {
+        return play_chunk(pairs);
+    }
+    let chunk_size = pairs.len().div_ceil(nr_threads);
+    thread::scope(|scope| {
+        let handles: Vec<_> = pairs.chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || play_chunk(chunk)))
+            .collect();
+        handles.into_iter()
+            .flat_map(|handle| handle.join().expect("A tournament thread panicked."))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf_bot_compiler::parser;
+
+    fn make_entrants() -> Vec<Entrant> {
+        [("clear", ">>>>>>>>>([-]>)*21"), ("idle", "."), ("suicide", "<")]
+            .iter()
+            .map(|&(name, source_code)| {
+                Entrant::new(name, Bot::new(parser::parse_bot(source_code.to_string())))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn play_everyPairPlaysZeroSum() {
+        let round_robin = play(&make_entrants(), 1);
+        assert_eq!(round_robin.nr_entrants(), 3);
+        for a in 0..3 {
+            assert_eq!(round_robin.get_points(a, a), None);
+            for b in (0..3).filter(|&b| b != a) {
+                assert_eq!(round_robin.get_points(a, b).map(|points| -points),
+                           round_robin.get_points(b, a));
+            }
+        }
+        assert_eq!(round_robin.get_points(2, 1), Some(-42));
+    }
+
+    #[test]
+    #[should_panic]
+    fn fromPoints_missingGame_panics() {
+        RoundRobin::from_points(vec!["a".to_string(), "b".to_string()], &[]);
+    }
+
+    #[test]
+    fn play_inParallel_matchesSingleThread() {
+        let entrants = make_entrants();
+        assert_eq!(play(&entrants, 3), play(&entrants, 1));
+    }
+}
@@ -0,0 +1,196 @@
+//! Ways to score the entrants of a round robin.
+//! Plain point sums treat every game the same, so beating a weak bot counts as much as beating the
+//! strongest one. The public BF Joust hill scores with a Markov chain instead: a title is passed
+//! around between the bots, and in every step the holder loses it to an opponent with the
+//! probability of losing a round to that opponent. A bot's score is the share of time it holds
+//! the title in the long run, which rewards beating the bots that often hold it themselves.
+
+use round_robin::RoundRobin;
+
+/// The number of rounds in a complete game.
+const ROUNDS_PER_GAME: f64 = 42.0;
+
+/// The Markov chain stops iterating once no score changes by more than this between steps.
+const TOLERANCE: f64 = 1e-12;
+
+const MAX_ITERATIONS: usize = 100_000;
+
+/// The ways to score an entrant.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Scoring {
+    /// The sum of the points of all games.
+    Points,
+    /// The long-run share of the title in the Markov chain, in percent. The scores of all
+    /// entrants add up to 100.
+    Markov,
+    /// The sum of the points of all games, with each game weighted by the strength of the
+    /// opponent. An opponent's strength is its Markov score relative to the average, so a game
+    /// against an average opponent counts once.
+    Weighted,
+}
+
+impl Scoring {
+    pub fn from_name(name: &str) -> Option<Scoring> {
+        match name {
+            "points" => Some(Scoring::Points),
+            "markov" => Some(Scoring::Markov),
+            "weighted" => Some(Scoring::Weighted),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the scoring, as accepted by `from_name`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Scoring::Points => "points",
+            Scoring::Markov => "markov",
+            Scoring::Weighted => "weighted",
+        }
+    }
+
+    /// Scores every entrant of the round robin, in the order they entered.
+    pub fn scores(self, round_robin: &RoundRobin) -> Vec<f64> {
+        match self {
+            Scoring::Points => point_sums(round_robin),
+            Scoring::Markov => markov_scores(round_robin),
+            Scoring::Weighted => weighted_scores(round_robin),
+        }
+    }
+}
+
+fn point_sums(round_robin: &RoundRobin) -> Vec<f64> {
+    let nr_entrants = round_robin.nr_entrants();
+    (0..nr_entrants)
+        .map(|a| {
+            (0..nr_entrants)
+                .filter_map(|b| round_robin.get_points(a, b))
+                .map(f64::from)
+                .sum()
+        })
+        .collect()
+}
+
+/// The fraction of rounds entrant `a` won against entrant `b`, with draws counting as half.
+fn win_fraction(round_robin: &RoundRobin, a: usize, b: usize) -> f64 {
+    round_robin.get_points(a, b)
+        .map_or(0.0, |points| (ROUNDS_PER_GAME + f64::from(points)) / (2.0 * ROUNDS_PER_GAME))
+}
+
+/// Returns the stationary distribution of the title, in percent.
+fn markov_scores(round_robin: &RoundRobin) -> Vec<f64> {
+    let nr_entrants = round_robin.nr_entrants();
+    if nr_entrants < 2 {
+        return vec![100.0; nr_entrants];
+    }
+    // The holder challenges a random opponent, so every transition is scaled down by the number
+    // of opponents. The title stays with the holder for the remaining probability.
+    let transitions: Vec<Vec<f64>> = (0..nr_entrants)
+        .map(|holder| {
+            (0..nr_entrants)
+                .map(|challenger| {
+                    win_fraction(round_robin, challenger, holder) / (nr_entrants - 1) as f64
+                })
+                .collect()
+        })
+        .collect();
+    let mut shares = vec![1.0 / nr_entrants as f64; nr_entrants];
+    for _ in 0..MAX_ITERATIONS {
+        let mut next = vec![0.0; nr_entrants];
+        for (holder, &share) in shares.iter().enumerate() {
+            let mut kept = share;
+            for (challenger, &probability) in transitions[holder].iter().enumerate() {
+                next[challenger] += share * probability;
+                kept -= share * probability;
+            }
+            next[holder] += kept;
+        }
+        let change = shares.iter()
+            .zip(&next)
+            .map(|(old, new)| (old - new).abs())
+            .fold(0.0, f64::max);
+        shares = next;
+        if change < TOLERANCE {
+            break;
+        }
+    }
+    shares.iter().map(|share| 100.0 * share).collect()
+}
+
+fn weighted_scores(round_robin: &RoundRobin) -> Vec<f64> {
+    let nr_entrants = round_robin.nr_entrants();
+    let strengths: Vec<f64> = markov_scores(round_robin)
+        .iter()
+        .map(|score| score * nr_entrants as f64 / 100.0)
+        .collect();
+    (0..nr_entrants)
+        .map(|a| {
+            (0..nr_entrants)
+                .filter_map(|b| {
+                    round_robin.get_points(a, b).map(|points| f64::from(points) * strengths[b])
+                })
+                .sum()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use round_robin::RoundRobin;
+
+    fn make_round_robin(nr_entrants: usize, results: &[(usize, usize, i8)]) -> RoundRobin {
+        RoundRobin::from_points((0..nr_entrants).map(|entrant| entrant.to_string()).collect(),
+                                results)
+    }
+
+    #[test]
+    fn markovScores_addUpToHundred() {
+        let round_robin = make_round_robin(3, &[(0, 1, 10), (0, 2, -4), (1, 2, 20)]);
+        let scores = markov_scores(&round_robin);
+        assert!((scores.iter().sum::<f64>() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn markovScores_evenlyMatched_shareEqually() {
+        let round_robin = make_round_robin(2, &[(0, 1, 0)]);
+        let scores = markov_scores(&round_robin);
+        assert!((scores[0] - 50.0).abs() < 1e-9);
+        assert!((scores[1] - 50.0).abs() < 1e-9);
+    }
+
+    /// Bot a crushes the weak bots c and d, and b beats a and the weak bots by a smaller margin.
+    /// Bot a has more points, but beating the strongest bot is worth more.
+    #[test]
+    fn markovScores_beatingStrongBotsCountsMore() {
+        let round_robin = make_round_robin(4,
+                                           &[(0, 1, -20),
+                                             (0, 2, 42),
+                                             (0, 3, 42),
+                                             (1, 2, 20),
+                                             (1, 3, 20),
+                                             (2, 3, 0)]);
+        let points = Scoring::Points.scores(&round_robin);
+        let markov = Scoring::Markov.scores(&round_robin);
+        assert!(points[0] > points[1]);
+        assert!(markov[1] > markov[0]);
+    }
+
+    #[test]
+    fn weightedScores_allDraws_areZero() {
+        let round_robin = make_round_robin(2, &[(0, 1, 0)]);
+        assert_eq!(Scoring::Weighted.scores(&round_robin), vec![0.0, 0.0]);
+        let round_robin = make_round_robin(3, &[(0, 1, 0), (0, 2, 0), (1, 2, 0)]);
+        let weighted = Scoring::Weighted.scores(&round_robin);
+        assert!(weighted.iter().all(|score| score.abs() < 1e-9));
+    }
+
+    #[test]
+    fn weightedScores_beatingStrongerOpponent_isWorthMore() {
+        let round_robin = make_round_robin(3, &[(0, 1, 42), (0, 2, 10), (1, 2, 10)]);
+        let weighted = Scoring::Weighted.scores(&round_robin);
+        let points = Scoring::Points.scores(&round_robin);
+        assert_eq!(points[2], -20.0);
+        assert!(weighted[2] < -20.0);
+    }
+}
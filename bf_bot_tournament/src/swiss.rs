@@ -0,0 +1,366 @@
+//! Swiss-system tournaments.
+//! A round robin over many bots is expensive, since the number of games grows with the square of
+//! the number of bots. A Swiss tournament plays a fixed number of rounds instead, and in every
+//! round pairs up bots with similar scores, so after a few rounds the strong bots play each other
+//! and the weak bots play each other. No pair plays twice if that can be avoided. With an odd
+//! number of bots, one of the lowest-ranked bots that hasn't had a bye yet sits the round out and
+//! is awarded a win.
+//!
+//! A won game is worth 1, a draw 0.5 and a lost game 0. Ties are broken on the Buchholz score,
+//! the sum of the scores of all opponents, then on the points of all games, and finally on name.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+
+use bf_bot_core::json::{Object, ToJson};
+use round_robin::{self, Entrant};
+
+/// The maximum number of partial pairings tried in a round before rematches are allowed.
+const MAX_PAIRING_ATTEMPTS: usize = 100_000;
+
+/// A game of a Swiss tournament, or a bye.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SwissGame {
+    /// Entrant `a` played entrant `b`, and scored `a_points`.
+    Played { a: usize, b: usize, a_points: i8 },
+    /// The entrant sat the round out.
+    Bye(usize),
+}
+
+/// The totals of a single entrant of a Swiss tournament.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SwissStanding {
+    pub name: String,
+    pub score: f64,
+    /// The sum of the scores of all opponents.
+    pub buchholz: f64,
+    /// The sum of the points of all games.
+    pub points: i32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub byes: u32,
+}
+
+/// The outcome of a Swiss tournament.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Swiss {
+    names: Vec<String>,
+    rounds: Vec<Vec<SwissGame>>,
+}
+
+/// Returns a number of rounds that reliably separates the best bots from the rest: two more than
+/// it takes to find a single winner.
+pub fn recommended_rounds(nr_entrants: usize) -> usize {
+    let mut rounds = 0;
+    while (1 << rounds) < nr_entrants {
+        rounds += 1;
+    }
+    rounds + 2
+}
+
+/// Plays a Swiss tournament of the given number of rounds. The games of every round are spread
+/// over the given number of threads.
+pub fn play(entrants: &[Entrant], nr_rounds: usize, nr_threads: usize) -> Swiss {
+    let mut swiss = Swiss {
+        names: entrants.iter().map(|entrant| entrant.name.clone()).collect(),
+        rounds: vec![],
+    };
+    if entrants.len() < 2 {
+        return swiss;
+    }
+    for _ in 0..nr_rounds {
+        let (pairs, bye) = swiss.pair_next_round();
+        let results = round_robin::play_pairs(entrants, &pairs, nr_threads);
+        let mut games: Vec<SwissGame> = pairs.iter()
+            .zip(results)
+            .map(|(&(a, b), a_points)| SwissGame::Played { a, b, a_points })
+            .collect();
+        games.extend(bye.map(SwissGame::Bye));
+        swiss.rounds.push(games);
+    }
+    swiss
+}
+
+impl Swiss {
+    /// Returns the games of every round.
+    pub fn get_rounds(&self) -> &[Vec<SwissGame>] {
+        &self.rounds
+    }
+
+    /// Returns the number of games that were played, not counting byes.
+    pub fn nr_games(&self) -> usize {
+        self.games().filter(|game| !matches!(**game, SwissGame::Bye(_))).count()
+    }
+
+    /// Returns the standings of all entrants, best first.
+    pub fn standings(&self) -> Vec<SwissStanding> {
+        let totals = self.totals();
+        let mut standings: Vec<(usize, SwissStanding)> = totals.iter()
+            .enumerate()
+            .map(|(entrant, standing)| (entrant, standing.clone()))
+            .collect();
+        standings.sort_by(|(a, a_standing), (b, b_standing)| {
+            compare(a_standing, b_standing).then(a.cmp(b))
+        });
+        standings.into_iter().map(|(_, standing)| standing).collect()
+    }
+
+    fn games(&self) -> impl Iterator<Item = &SwissGame> {
+        self.rounds.iter().flat_map(|round| round.iter())
+    }
+
+    /// Sums up the games of every entrant, in the order they entered.
+    fn totals(&self) -> Vec<SwissStanding> {
+        let mut totals: Vec<SwissStanding> = self.names
+            .iter()
+            .map(|name| {
+                SwissStanding {
+                    name: name.clone(),
+                    score: 0.0,
+                    buchholz: 0.0,
+                    points: 0,
+                    wins: 0,
+                    losses: 0,
+                    draws: 0,
+                    byes: 0,
+                }
+            })
+            .collect();
+        for game in self.games() {
+            match *game {
+                SwissGame::Played { a, b, a_points } => {
+                    add_game(&mut totals[a], a_points);
+                    add_game(&mut totals[b], -a_points);
+                }
+                SwissGame::Bye(entrant) => {
+                    totals[entrant].score += 1.0;
+                    totals[entrant].byes += 1;
+                }
+            }
+        }
+        let scores: Vec<f64> = totals.iter().map(|standing| standing.score).collect();
+        for game in self.games() {
+            if let SwissGame::Played { a, b, .. } = *game {
+                totals[a].buchholz += scores[b];
+                totals[b].buchholz += scores[a];
+            }
+        }
+        totals
+    }
+
+    /// Pairs up the entrants for the next round, and picks the entrant that gets a bye, if any.
+    fn pair_next_round(&self) -> (Vec<(usize, usize)>, Option<usize>) {
+        let totals = self.totals();
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by(|&a, &b| {
+            totals[b].score
+                .partial_cmp(&totals[a].score)
+                .unwrap_or(Ordering::Equal)
+                .then(totals[b].points.cmp(&totals[a].points))
+                .then(a.cmp(&b))
+        });
+        let bye = if order.len() % 2 == 1 {
+            let fewest_byes = order.iter().map(|&entrant| totals[entrant].byes).min().unwrap();
+            let position = order.iter()
+                .rposition(|&entrant| totals[entrant].byes == fewest_byes)
+                .unwrap();
+            Some(order.remove(position))
+        } else {
+            None
+        };
+        let played: HashSet<(usize, usize)> = self.games()
+            .filter_map(|game| match *game {
+                SwissGame::Played { a, b, .. } => Some((a.min(b), a.max(b))),
+                SwissGame::Bye(_) => None,
+            })
+            .collect();
+        let mut attempts = MAX_PAIRING_ATTEMPTS;
+        let pairs = pair_up(&order, &played, &mut attempts)
+            .unwrap_or_else(|| order.chunks(2).map(|pair| (pair[0], pair[1])).collect());
+        (pairs, bye)
+    }
+}
+
+fn add_game(standing: &mut SwissStanding, points: i8) {
+    standing.points += i32::from(points);
+    match points.cmp(&0) {
+        Ordering::Greater => {
+            standing.score += 1.0;
+            standing.wins += 1;
+        }
+        Ordering::Less => standing.losses += 1,
+        Ordering::Equal => {
+            standing.score += 0.5;
+            standing.draws += 1;
+        }
+    }
+}
+
+fn compare(a: &SwissStanding, b: &SwissStanding) -> Ordering {
+    b.score
+        .partial_cmp(&a.score)
+        .unwrap_or(Ordering::Equal)
+        .then(b.buchholz.partial_cmp(&a.buchholz).unwrap_or(Ordering::Equal))
+        .then(b.points.cmp(&a.points))
+        .then_with(|| a.name.cmp(&b.name))
+}
+
+/// Pairs up the players, which are ordered from best to worst, without rematches. Every player is
+/// paired with the best player below it that it hasn't played yet, backtracking if the players
+/// further down can't be paired up anymore. Returns `None` if there is no such pairing, or if it
+/// can't be found within the number of attempts.
+fn pair_up(players: &[usize],
+           played: &HashSet<(usize, usize)>,
+           attempts: &mut usize)
+           -> Option<Vec<(usize, usize)>> {
+    if players.is_empty() {
+        return Some(vec![]);
+    }
+    let first = players[0];
+    for (index, &opponent) in players.iter().enumerate().skip(1) {
+        if played.contains(&(first.min(opponent), first.max(opponent))) {
+            continue;
+        }
+        if *attempts == 0 {
+            return None;
+        }
+        *attempts -= 1;
+        let rest: Vec<usize> = players[1..]
+            .iter()
+            .enumerate()
+            .filter(|&(position, _)| position + 1 != index)
+            .map(|(_, &player)| player)
+            .collect();
+        if let Some(mut pairs) = pair_up(&rest, played, attempts) {
+            pairs.insert(0, (first, opponent));
+            return Some(pairs);
+        }
+    }
+    None
+}
+
+impl fmt::Display for Swiss {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let standings = self.standings();
+        let name_width = standings.iter().map(|standing| standing.name.len()).max().unwrap_or(0);
+        writeln!(f,
+                 "{:>4}  {:<width$} {:>5} {:>8} {:>6} {:>4} {:>4} {:>4} {:>4}",
+                 "rank",
+                 "name",
+                 "score",
+                 "buchholz",
+                 "points",
+                 "W",
+                 "L",
+                 "D",
+                 "byes",
+                 width = name_width)?;
+        for (rank, standing) in standings.iter().enumerate() {
+            writeln!(f,
+                     "{:>4}  {:<width$} {:>5.1} {:>8.1} {:>6} {:>4} {:>4} {:>4} {:>4}",
+                     rank + 1,
+                     standing.name,
+                     standing.score,
+                     standing.buchholz,
+                     standing.points,
+                     standing.wins,
+                     standing.losses,
+                     standing.draws,
+                     standing.byes,
+                     width = name_width)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for SwissStanding {
+    fn to_json(&self) -> String {
+        Object::new()
+            .field("name", &self.name)
+            .field("score", &self.score)
+            .field("buchholz", &self.buchholz)
+            .field("points", &self.points)
+            .field("wins", &self.wins)
+            .field("losses", &self.losses)
+            .field("draws", &self.draws)
+            .field("byes", &self.byes)
+            .build()
+    }
+}
+
+impl ToJson for Swiss {
+    fn to_json(&self) -> String {
+        self.json_fields(Object::new()).build()
+    }
+}
+
+impl Swiss {
+    /// Adds the fields of the tournament to a JSON object.
+    pub fn json_fields(&self, object: Object) -> Object {
+        object.field("rounds", &self.rounds.len()).field("standings", &self.standings())
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use bf_bot_compiler::parser;
+    use bf_bot_core::bf::Bot;
+
+    fn make_entrants(sources: &[(&str, &str)]) -> Vec<Entrant> {
+        sources.iter()
+            .map(|&(name, source_code)| {
+                Entrant::new(name, Bot::new(parser::parse_bot(source_code.to_string())))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn play_oddEntrants_noRematchesAndAtMostOneByeEach() {
+        let entrants = make_entrants(&[("clear", ">>>>>>>>>([-]>)*21"),
+                                       ("idle", "."),
+                                       ("suicide", "<"),
+                                       ("wait", "(.)*50"),
+                                       ("plus", "(+)*30")]);
+        let swiss = play(&entrants, 3, 2);
+        assert_eq!(swiss.get_rounds().len(), 3);
+        assert_eq!(swiss.nr_games(), 6);
+        let mut pairs = HashSet::new();
+        for game in swiss.get_rounds().iter().flat_map(|round| round.iter()) {
+            if let SwissGame::Played { a, b, .. } = *game {
+                assert!(pairs.insert((a.min(b), a.max(b))), "{} and {} met twice", a, b);
+            }
+        }
+        assert!(swiss.standings().iter().all(|standing| standing.byes <= 1));
+    }
+
+    #[test]
+    fn standings_strongestFirstWeakestLast() {
+        let entrants = make_entrants(&[("suicide", "<"),
+                                       ("idle", "."),
+                                       ("clear", ">>>>>>>>>([-]>)*21"),
+                                       ("wait", "(.)*50")]);
+        let standings = play(&entrants, 2, 1).standings();
+        assert_eq!(standings[0].name, "clear");
+        assert_eq!(standings[0].score, 2.0);
+        assert_eq!(standings[3].name, "suicide");
+    }
+
+    #[test]
+    fn pairUp_backtracksToAvoidRematches() {
+        let played: HashSet<(usize, usize)> = [(0, 1), (1, 3)].iter().cloned().collect();
+        assert_eq!(pair_up(&[0, 1, 2, 3], &played, &mut MAX_PAIRING_ATTEMPTS.clone()),
+                   Some(vec![(0, 3), (1, 2)]));
+        let played: HashSet<(usize, usize)> = [(0, 1), (0, 2), (0, 3)].iter().cloned().collect();
+        assert_eq!(pair_up(&[0, 1, 2, 3], &played, &mut MAX_PAIRING_ATTEMPTS.clone()), None);
+    }
+
+    #[test]
+    fn recommendedRounds_growsWithLogarithm() {
+        assert_eq!(recommended_rounds(2), 3);
+        assert_eq!(recommended_rounds(100), 9);
+    }
+}
@@ -0,0 +1,148 @@
+//! Helpers shared by the subcommands: argument parsing, exit codes and loading bots.
+
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+
+use compiler::loader::{self, LoadedBot};
+use core::simul_game::{COMPLETE_GAME_MAX_STEPS, MAX_TAPE_LENGTH, MIN_TAPE_LENGTH};
+use core::simul_round::RoundParams;
+
+/// The command did what was asked.
+pub const EXIT_SUCCESS: i32 = 0;
+/// The command ran, but found a problem: lint issues, an unformatted file, or a new version of a
+/// bot that does worse than the old one.
+pub const EXIT_CHECK_FAILED: i32 = 1;
+/// The command line is invalid.
+pub const EXIT_USAGE: i32 = 2;
+/// A file can't be read or written, or doesn't hold what it should.
+pub const EXIT_ERROR: i32 = 3;
+
+/// The arguments of a subcommand, split into positional arguments, flags and options with a
+/// value.
+pub struct Args {
+    usage: &'static str,
+    positional: Vec<String>,
+    flags: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    /// Splits the arguments of a subcommand. `flags` are the names of the options without a
+    /// value, like `--json`, and `options` those of the options with a value. Exits with the
+    /// usage if an argument is not recognised.
+    pub fn parse(args: &[String], usage: &'static str, flags: &[&str], options: &[&str]) -> Args {
+        let mut parsed = Args {
+            usage,
+            positional: vec![],
+            flags: vec![],
+            options: vec![],
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if flags.contains(&arg.as_str()) {
+                parsed.flags.push(arg.clone());
+            } else if options.contains(&arg.as_str()) {
+                let value = args.next().unwrap_or_else(|| parsed.usage_error());
+                parsed.options.push((arg.clone(), value.clone()));
+            } else if arg.starts_with("--") {
+                parsed.usage_error();
+            } else {
+                parsed.positional.push(arg.clone());
+            }
+        }
+        parsed
+    }
+
+    pub fn get_positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    /// Exits with the usage unless the number of positional arguments lies within the bounds.
+    /// Both bounds are inclusive.
+    pub fn expect_positional(&self, min: usize, max: usize) {
+        if self.positional.len() < min || self.positional.len() > max {
+            self.usage_error();
+        }
+    }
+
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    pub fn has_option(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    /// Returns the value of an option, if it was given. Exits with the usage if the value can't
+    /// be parsed.
+    pub fn get_option<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| value.parse().unwrap_or_else(|_| self.usage_error()))
+    }
+
+    pub fn get_option_or<T: FromStr>(&self, name: &str, default: T) -> T {
+        self.get_option(name).unwrap_or(default)
+    }
+
+    pub fn usage_error(&self) -> ! {
+        eprintln!("{}", self.usage);
+        process::exit(EXIT_USAGE);
+    }
+}
+
+/// Loads a bot, or exits with an error.
+pub fn load_bot(path: &Path) -> LoadedBot {
+    loader::load_bot(path)
+        .unwrap_or_else(|error| exit_with_error(&format!("{}: {}", path.display(), error)))
+}
+
+/// Reads the parameters of a single round from the `--tape`, `--reversed` and `--max-steps`
+/// options. Exits with the usage if there is no tape length, or with an error if it is out of
+/// bounds.
+pub fn round_params(args: &Args) -> RoundParams {
+    let round_params = RoundParams {
+        tape_length: args.get_option("--tape").unwrap_or_else(|| args.usage_error()),
+        invert_polarity: args.has_flag("--reversed"),
+        max_steps: args.get_option_or("--max-steps", COMPLETE_GAME_MAX_STEPS),
+    };
+    if round_params.tape_length < MIN_TAPE_LENGTH || round_params.tape_length > MAX_TAPE_LENGTH {
+        exit_with_error(&format!("The tape length must lie between {} and {}.",
+                                 MIN_TAPE_LENGTH,
+                                 MAX_TAPE_LENGTH));
+    }
+    round_params
+}
+
+pub fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(EXIT_ERROR);
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_splitsPositionalFlagsAndOptions() {
+        let args = Args::parse(&to_args(&["a.bf", "--tape", "15", "--reversed", "b.bf"]),
+                               "usage",
+                               &["--reversed", "--json"],
+                               &["--tape"]);
+        assert_eq!(args.get_positional(), &["a.bf".to_string(), "b.bf".to_string()]);
+        assert!(args.has_flag("--reversed"));
+        assert!(!args.has_flag("--json"));
+        assert_eq!(args.get_option::<u32>("--tape"), Some(15));
+        assert_eq!(args.get_option_or("--max-steps", 7), 7);
+        assert!(args.has_option("--tape"));
+        assert!(!args.has_option("--max-steps"));
+    }
+}
@@ -0,0 +1,111 @@
+//! Breeding bots: evolution experiments, and polishing a single bot with a local search.
+
+use std::path::{Path, PathBuf};
+
+use cli::{self, Args, EXIT_SUCCESS};
+use compiler::loader::LoadError;
+use core::json::Object;
+use core::bf::Bot;
+use core::simul_game;
+use evolve::config::RunConfig;
+use evolve::evolution::Evolution;
+use evolve::experiment::{self, Observer};
+use evolve::genome::Genome;
+use evolve::local_search::{self, Annealing, LocalSearchParams};
+use evolve::rng::Rng;
+
+/// Runs the evolution described by a configuration file, printing the best bot of every
+/// generation.
+pub fn run_evolve(args: &[String]) -> i32 {
+    let args = Args::parse(args, "Usage: evolve <config> [--json]", &["--json"], &[]);
+    args.expect_positional(1, 1);
+    let config_path = Path::new(&args.get_positional()[0]);
+    let config = RunConfig::from_file(config_path).unwrap_or_else(|error| {
+        cli::exit_with_error(&format!("{}: {}", config_path.display(), error))
+    });
+    let mut printer = Printer { json: args.has_flag("--json") };
+    if let Err(error) = experiment::run_observed(&config, &mut printer) {
+        cli::exit_with_error(&error.to_string());
+    }
+    EXIT_SUCCESS
+}
+
+/// Prints the best bot of every generation, as text or as a line of JSON, and warns about seed
+/// bots that failed to load or are too large.
+struct Printer {
+    json: bool,
+}
+
+impl Observer for Printer {
+    fn on_generation(&mut self, evolution: &Evolution) {
+        let best = evolution.best();
+        if self.json {
+            println!("{}",
+                     Object::document("generation")
+                         .field("generation", &evolution.get_generation())
+                         .field("fitness", &best.get_fitness())
+                         .field("bot", &best.get_genome().to_source())
+                         .build());
+        } else {
+            println!("generation {}: fitness {} {}",
+                     evolution.get_generation(),
+                     best.get_fitness(),
+                     best.get_genome());
+        }
+    }
+
+    fn on_seed_error(&mut self, path: &Path, error: &LoadError) {
+        eprintln!("warning: skipping seed bot {}: {}", path.display(), error);
+    }
+
+    fn on_seed_rejected(&mut self, path: &Path) {
+        eprintln!("warning: skipping seed bot {}: it exceeds the size limits", path.display());
+    }
+}
+
+/// Polishes a bot with a local search against a number of opponents.
+pub fn run_refine(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: refine <bot.bf> <opponent.bf>... [--iterations N] \
+                            [--temperature T] [--seed S] [--json]",
+                           &["--json"],
+                           &["--iterations", "--temperature", "--seed"]);
+    args.expect_positional(2, usize::MAX);
+    let mut params = LocalSearchParams::default();
+    params.iterations = args.get_option_or("--iterations", params.iterations);
+    if let Some(initial_temperature) = args.get_option("--temperature") {
+        params.annealing = Some(Annealing {
+            initial_temperature,
+            ..Annealing::default()
+        });
+    }
+    let genomes: Vec<Genome> =
+        args.get_positional().iter().map(|path| read_genome(&PathBuf::from(path))).collect();
+    let opponents: Vec<Bot> = genomes[1..].iter().map(Genome::to_bot).collect();
+    let rounds: Vec<_> = simul_game::AllRounds::new().collect();
+    let refinement = local_search::refine(&genomes[0],
+                                          &opponents,
+                                          &[],
+                                          &rounds,
+                                          &params,
+                                          &mut Rng::new(args.get_option_or("--seed", 0)));
+    if args.has_flag("--json") {
+        println!("{}",
+                 Object::document("refinement")
+                     .field("initial_fitness", &refinement.initial_fitness)
+                     .field("fitness", &refinement.fitness)
+                     .field("accepted", &refinement.nr_accepted)
+                     .field("bot", &refinement.genome.to_source())
+                     .build());
+    } else {
+        println!("fitness {} -> {}", refinement.initial_fitness, refinement.fitness);
+        println!("{}", refinement.genome);
+    }
+    EXIT_SUCCESS
+}
+
+fn read_genome(path: &Path) -> Genome {
+    let loaded = cli::load_bot(path);
+    Genome::from_source(&loaded.source_code)
+        .unwrap_or_else(|error| cli::exit_with_error(&format!("{}: {}", path.display(), error)))
+}
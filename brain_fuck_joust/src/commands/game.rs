@@ -0,0 +1,250 @@
+//! Playing bots against each other: a complete game, or a single round step by step or as a
+//! diagram.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use cli::{self, Args, EXIT_SUCCESS};
+use core::json::Object;
+use debugger::{self, Debugger};
+use terminal::{RawMode, CLEAR_SCREEN};
+use viewer::Viewer;
+use core::simul_game::{self, AllRounds, MAX_TAPE_LENGTH, MIN_TAPE_LENGTH};
+use core::simul_round::{self, DiagramParams, RoundResult};
+use core::simul_round::diagram;
+
+/// Plays a complete game between two bots, and shows the outcome of every round.
+pub fn run_match(args: &[String]) -> i32 {
+    let args = Args::parse(args, "Usage: match <a.bf> <b.bf> [--json]", &["--json"], &[]);
+    args.expect_positional(2, 2);
+    let bot_a = cli::load_bot(Path::new(&args.get_positional()[0]));
+    let bot_b = cli::load_bot(Path::new(&args.get_positional()[1]));
+    let record = simul_game::run_recorded(&bot_a.bot, &bot_b.bot, AllRounds::new());
+    if args.has_flag("--json") {
+        println!("{}",
+                 Object::document("match")
+                     .field("bot_a", &bot_a.name())
+                     .field("bot_b", &bot_b.name())
+                     .field("game", &record)
+                     .build());
+        return EXIT_SUCCESS;
+    }
+    // One column per tape length: 'a' or 'b' for the winner of the round, '-' for a draw.
+    println!("{:<9}{}",
+             "tape",
+             (MIN_TAPE_LENGTH..MAX_TAPE_LENGTH + 1)
+                 .map(|tape_length| (tape_length % 10).to_string())
+                 .collect::<String>());
+    for &invert_polarity in &[false, true] {
+        let outcomes: String = record.rounds
+            .iter()
+            .filter(|round| round.round_params.invert_polarity == invert_polarity)
+            .map(|round| match winner_name(&round.result) {
+                "draw" => '-',
+                winner => winner.chars().next().unwrap(),
+            })
+            .collect();
+        println!("{:<9}{}",
+                 if invert_polarity { "reversed" } else { "normal" },
+                 outcomes);
+    }
+    let result = record.to_result();
+    println!("a: {} {:+}", bot_a.name(), result.bot_a_points);
+    println!("b: {} {:+}", bot_b.name(), result.bot_b_points);
+    EXIT_SUCCESS
+}
+
+/// Plays a single round between two bots, and shows the tape and the positions of both bots
+/// after every step.
+pub fn run_trace(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: trace <a.bf> <b.bf> --tape N [--reversed] [--max-steps N] \
+                            [--json]",
+                           &["--reversed", "--json"],
+                           &["--tape", "--max-steps"]);
+    args.expect_positional(2, 2);
+    let round_params = cli::round_params(&args);
+    let bot_a = cli::load_bot(Path::new(&args.get_positional()[0]));
+    let bot_b = cli::load_bot(Path::new(&args.get_positional()[1]));
+    let round_trace = simul_round::trace(&bot_a.bot, &bot_b.bot, &round_params);
+    let end = round_params.tape_length as i32 - 1;
+    if args.has_flag("--json") {
+        println!("{}",
+                 Object::document("trace")
+                     .field("bot_a", &bot_a.name())
+                     .field("bot_b", &bot_b.name())
+                     .field("params", &round_params)
+                     .field("trace", &round_trace)
+                     .build());
+        return EXIT_SUCCESS;
+    }
+    let tape_text =
+        |tape: &[i8]| tape.iter().map(|value| format!("{:>5}", value)).collect::<String>();
+    println!("{:>6} {:>3} {:>3}  tape", "step", "a", "b");
+    println!("{:>6} {:>3} {:>3} {}",
+             0,
+             0,
+             end,
+             tape_text(&round_trace.initial_tape));
+    for (step_nr, step) in round_trace.steps.iter().enumerate() {
+        println!("{:>6} {:>3} {:>3} {}",
+                 step_nr + 1,
+                 step.start_bot_pos,
+                 step.end_bot_pos,
+                 tape_text(&step.tape));
+    }
+    match winner_name(&round_trace.result) {
+        "a" => println!("{} wins", bot_a.name()),
+        "b" => println!("{} wins", bot_b.name()),
+        _ => println!("draw"),
+    }
+    EXIT_SUCCESS
+}
+
+/// Plays a single round between two bots, and draws it as a space-time diagram in SVG. The
+/// image is written to standard output, unless a file is given.
+pub fn run_diagram(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: diagram <a.bf> <b.bf> --tape N [--reversed] [--max-steps N] \
+                            [--steps N] [--cell-width W] [--step-height H] [--output file]",
+                           &["--reversed"],
+                           &["--tape",
+                             "--max-steps",
+                             "--steps",
+                             "--cell-width",
+                             "--step-height",
+                             "--output"]);
+    args.expect_positional(2, 2);
+    let round_params = cli::round_params(&args);
+    let bot_a = cli::load_bot(Path::new(&args.get_positional()[0]));
+    let bot_b = cli::load_bot(Path::new(&args.get_positional()[1]));
+    let default_params = DiagramParams::default();
+    let params = DiagramParams {
+        cell_width: args.get_option_or("--cell-width", default_params.cell_width),
+        step_height: args.get_option_or("--step-height", default_params.step_height),
+        max_steps: args.get_option("--steps"),
+    };
+    if params.cell_width <= 0.0 || params.step_height <= 0.0 {
+        args.usage_error();
+    }
+    let round_trace = simul_round::trace(&bot_a.bot, &bot_b.bot, &round_params);
+    let title = format!("{} vs {}, tape {}, {} polarity: {}",
+                        bot_a.name(),
+                        bot_b.name(),
+                        round_params.tape_length,
+                        if round_params.invert_polarity { "reversed" } else { "normal" },
+                        match winner_name(&round_trace.result) {
+                            "a" => format!("{} wins", bot_a.name()),
+                            "b" => format!("{} wins", bot_b.name()),
+                            _ => "draw".to_string(),
+                        });
+    let svg = diagram::to_svg(&round_trace, &params, &title);
+    match args.get_option::<String>("--output") {
+        Some(path) => {
+            fs::write(&path, svg)
+                .unwrap_or_else(|error| cli::exit_with_error(&format!("{}: {}", path, error)))
+        }
+        None => print!("{}", svg),
+    }
+    EXIT_SUCCESS
+}
+
+/// The delays between the steps of a round that is being watched, from slow to fast.
+const VIEW_DELAYS_MS: [u64; 8] = [1000, 500, 250, 100, 50, 20, 5, 1];
+const VIEW_CONTROLS: &str = "space play/pause, n step, i next interaction, +/- speed, q quit";
+
+/// Replays a single round in the terminal. The round starts paused.
+pub fn run_view(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: view <a.bf> <b.bf> --tape N [--reversed] [--max-steps N]",
+                           &["--reversed"],
+                           &["--tape", "--max-steps"]);
+    args.expect_positional(2, 2);
+    let round_params = cli::round_params(&args);
+    let bot_a = cli::load_bot(Path::new(&args.get_positional()[0]));
+    let bot_b = cli::load_bot(Path::new(&args.get_positional()[1]));
+    let mut viewer =
+        Viewer::new(&bot_a.bot, &bot_a.name(), &bot_b.bot, &bot_b.name(), &round_params);
+    let raw_mode = RawMode::enable()
+        .unwrap_or_else(|error| cli::exit_with_error(&format!("view: {}", error)));
+    if let Err(error) = watch(&mut viewer, &raw_mode) {
+        drop(raw_mode);
+        cli::exit_with_error(&format!("view: {}", error));
+    }
+    EXIT_SUCCESS
+}
+
+fn watch(viewer: &mut Viewer, raw_mode: &RawMode) -> io::Result<()> {
+    let mut playing = false;
+    let mut speed = 3;
+    loop {
+        let delay = Duration::from_millis(VIEW_DELAYS_MS[speed]);
+        print!("{}{}\n{} at {} ms per step    {}\n",
+               CLEAR_SCREEN,
+               viewer.render(),
+               if playing { "playing" } else { "paused" },
+               delay.as_millis(),
+               VIEW_CONTROLS);
+        io::stdout().flush()?;
+        let timeout = if playing && !viewer.is_finished() {
+            Some(delay)
+        } else {
+            None
+        };
+        match raw_mode.read_key(timeout)? {
+            None => {
+                viewer.step();
+            }
+            Some(b' ') => playing = !playing,
+            Some(b'n') => {
+                playing = false;
+                viewer.step();
+            }
+            Some(b'i') => {
+                playing = false;
+                viewer.skip_to_interaction();
+            }
+            Some(b'+') => speed = (speed + 1).min(VIEW_DELAYS_MS.len() - 1),
+            Some(b'-') => speed = speed.saturating_sub(1),
+            Some(b'q') => return Ok(()),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Debugs a single round, with commands that are read from standard input one line at a time.
+pub fn run_debug(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: debug <a.bf> <b.bf> --tape N [--reversed] [--max-steps N]",
+                           &["--reversed"],
+                           &["--tape", "--max-steps"]);
+    args.expect_positional(2, 2);
+    let round_params = cli::round_params(&args);
+    let bot_a = cli::load_bot(Path::new(&args.get_positional()[0]));
+    let bot_b = cli::load_bot(Path::new(&args.get_positional()[1]));
+    let mut debugger = Debugger::new(&bot_a.bot, &bot_b.bot, &round_params);
+    println!("{}\n\n{}", debugger::HELP, debugger.execute("bots"));
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(debug) ");
+        io::stdout().flush().unwrap_or_else(|error| cli::exit_with_error(&error.to_string()));
+        let line = match lines.next() {
+            Some(line) => line.unwrap_or_else(|error| cli::exit_with_error(&error.to_string())),
+            None => break,
+        };
+        match line.trim() {
+            "quit" | "q" => break,
+            command => println!("{}", debugger.execute(command)),
+        }
+    }
+    EXIT_SUCCESS
+}
+
+/// Names the winner of a round: "a" for the bot that started at the start of the tape, "b" for
+/// the other one, or "draw".
+fn winner_name(result: &RoundResult) -> &'static str {
+    result.winner_name().unwrap_or("draw")
+}
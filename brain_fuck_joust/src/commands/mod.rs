@@ -0,0 +1,7 @@
+//! The subcommands of the command-line interface. Every subcommand takes its own arguments, and
+//! returns the code to exit with.
+
+pub mod game;
+pub mod source;
+pub mod ranking;
+pub mod evolve;
@@ -0,0 +1,324 @@
+//! Ranking collections of bots: tournaments, gauntlets, ratings and a king of the hill. Also
+//! profiling a bot against its opponents.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cli::{self, Args, EXIT_CHECK_FAILED, EXIT_SUCCESS};
+use compiler::loader;
+use core::json::Object;
+use heatmap;
+use tournament::gauntlet::{Gauntlet, Opponent};
+use tournament::hill::Hill;
+use tournament::leaderboard::Leaderboard;
+use tournament::ratings::{self, RatingParams, Ratings};
+use tournament::regression;
+use tournament::round_robin::{self, Entrant};
+use tournament::scoring::Scoring;
+use tournament::swiss;
+
+/// Plays every bot in a directory against every other bot, and prints the leaderboard. With
+/// `--swiss`, plays the given number of Swiss rounds instead. Swiss rounds are always scored on
+/// points, so `--scoring` can't be combined with `--swiss`.
+pub fn run_tournament(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: tournament <directory> [--threads N] \
+                            [--scoring points|markov|weighted | --swiss N] [--json]",
+                           &["--json"],
+                           &["--threads", "--scoring", "--swiss"]);
+    args.expect_positional(1, 1);
+    if args.has_option("--scoring") && args.has_option("--swiss") {
+        args.usage_error();
+    }
+    let nr_threads = args.get_option_or("--threads", 1);
+    let scoring = match args.get_option::<String>("--scoring") {
+        Some(name) => Scoring::from_name(&name).unwrap_or_else(|| args.usage_error()),
+        None => Scoring::Points,
+    };
+    let dir = PathBuf::from(&args.get_positional()[0]);
+    let loaded = loader::load_dir(&dir)
+        .unwrap_or_else(|error| cli::exit_with_error(&format!("{}: {}", dir.display(), error)));
+    for (path, error) in &loaded.failures {
+        eprintln!("warning: skipping {}: {}", path.display(), error);
+    }
+    let entrants: Vec<Entrant> = loaded.bots.iter().map(Entrant::from_loaded).collect();
+    let json = args.has_flag("--json");
+    match args.get_option("--swiss") {
+        Some(nr_rounds) => {
+            let swiss = swiss::play(&entrants, nr_rounds, nr_threads);
+            if json {
+                println!("{}", swiss.json_fields(Object::document("swiss")).build());
+            } else {
+                print!("{}", swiss);
+            }
+        }
+        None => {
+            let leaderboard = Leaderboard::new(&round_robin::play(&entrants, nr_threads), scoring);
+            if json {
+                println!("{}",
+                         leaderboard.json_fields(Object::document("leaderboard")).build());
+            } else {
+                print!("{}", leaderboard);
+            }
+        }
+    }
+    EXIT_SUCCESS
+}
+
+/// Scores every given bot against the opponents of a gauntlet file.
+pub fn run_gauntlet(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: gauntlet <file> <bot.bf>... [--threads N] [--json]",
+                           &["--json"],
+                           &["--threads"]);
+    args.expect_positional(2, usize::MAX);
+    let gauntlet = load_gauntlet(Path::new(&args.get_positional()[0]));
+    let candidates: Vec<Entrant> = args.get_positional()[1..]
+        .iter()
+        .map(|path| Entrant::from_loaded(&cli::load_bot(Path::new(path))))
+        .collect();
+    let reports = gauntlet.run(&candidates, args.get_option_or("--threads", 1));
+    if args.has_flag("--json") {
+        println!("{}", Object::document("gauntlet").field("reports", &reports).build());
+    } else {
+        for report in reports {
+            print!("{}", report);
+        }
+    }
+    EXIT_SUCCESS
+}
+
+/// Counts how often every instruction of a bot is executed in complete games against the given
+/// opponents, and those of a gauntlet, and shows the counts on the source code of the bot.
+/// `--tape` and `--normal` or `--reversed` only count the rounds with that tape length or
+/// polarity.
+pub fn run_profile(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: profile <bot.bf> [<opponent.bf>...] [--gauntlet file] \
+                            [--tape N] [--normal|--reversed] [--html file] [--threads N] [--json]",
+                           &["--normal", "--reversed", "--json"],
+                           &["--gauntlet", "--tape", "--html", "--threads"]);
+    args.expect_positional(1, usize::MAX);
+    let bot = cli::load_bot(Path::new(&args.get_positional()[0]));
+    let mut opponents: Vec<Opponent> = args.get_positional()[1..]
+        .iter()
+        .map(|path| {
+            let loaded = cli::load_bot(Path::new(path));
+            Opponent {
+                name: loaded.name(),
+                bot: loaded.bot,
+                weight: 1.0,
+                tags: vec![],
+            }
+        })
+        .collect();
+    if let Some(path) = args.get_option::<String>("--gauntlet") {
+        opponents.extend_from_slice(load_gauntlet(Path::new(&path)).get_opponents());
+    }
+    if opponents.is_empty() || (args.has_flag("--normal") && args.has_flag("--reversed")) {
+        args.usage_error();
+    }
+    let game_profile =
+        Gauntlet::new(opponents).profile(&bot.bot, args.get_option_or("--threads", 1));
+    let tape_length: Option<u32> = args.get_option("--tape");
+    let polarity = if args.has_flag("--normal") {
+        Some(false)
+    } else if args.has_flag("--reversed") {
+        Some(true)
+    } else {
+        None
+    };
+    let counts = game_profile.total(|params| {
+        tape_length.is_none_or(|tape_length| params.tape_length == tape_length) &&
+        polarity.is_none_or(|invert_polarity| params.invert_polarity == invert_polarity)
+    });
+    if let Some(path) = args.get_option::<String>("--html") {
+        fs::write(&path, heatmap::to_html(&bot.bot, &counts, &bot.name()))
+            .unwrap_or_else(|error| cli::exit_with_error(&format!("{}: {}", path, error)));
+    }
+    if args.has_flag("--json") {
+        let rounds: Vec<String> = game_profile.executed_per_round_kind()
+            .iter()
+            .map(|&(tape_length, invert_polarity, executed)| {
+                Object::new()
+                    .field("tape_length", &tape_length)
+                    .field("polarity", if invert_polarity { "reversed" } else { "normal" })
+                    .field("executed", &executed)
+                    .build()
+            })
+            .collect();
+        println!("{}",
+                 Object::document("profile")
+                     .field("bot", &bot.name())
+                     .field("counts", &counts)
+                     .raw_field("rounds", format!("[{}]", rounds.join(",")))
+                     .build());
+    } else {
+        print!("{}", heatmap::round_kind_table(&game_profile));
+        println!();
+        print!("{}", heatmap::annotate(&bot.bot, &counts));
+    }
+    EXIT_SUCCESS
+}
+
+/// Compares an old and a new version of a bot on a gauntlet, and shows every round that changed.
+/// Exits with `EXIT_CHECK_FAILED` if the new version does worse in any round.
+pub fn run_diff(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: diff <old.bf> <new.bf> <gauntlet file> [--threads N] [--json]",
+                           &["--json"],
+                           &["--threads"]);
+    args.expect_positional(3, 3);
+    let paths = args.get_positional();
+    let old = cli::load_bot(Path::new(&paths[0]));
+    let new = cli::load_bot(Path::new(&paths[1]));
+    let gauntlet = load_gauntlet(Path::new(&paths[2]));
+    let regression =
+        regression::compare(&old.bot, &new.bot, &gauntlet, args.get_option_or("--threads", 1));
+    if args.has_flag("--json") {
+        println!("{}", regression.json_fields(Object::document("diff")).build());
+    } else {
+        print!("{}", regression);
+    }
+    if regression.has_regressions() {
+        EXIT_CHECK_FAILED
+    } else {
+        EXIT_SUCCESS
+    }
+}
+
+fn load_gauntlet(path: &Path) -> Gauntlet {
+    Gauntlet::load(path)
+        .unwrap_or_else(|error| cli::exit_with_error(&format!("{}: {}", path.display(), error)))
+}
+
+/// Plays every given bot against every other, and updates their ratings in the ratings file,
+/// which is created if it doesn't exist yet.
+pub fn run_rating(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: rate <ratings file> <bot.bf> <bot.bf>... [--threads N] \
+                            [--json]",
+                           &["--json"],
+                           &["--threads"]);
+    args.expect_positional(3, usize::MAX);
+    let ratings_path = Path::new(&args.get_positional()[0]);
+    let mut ratings = if ratings_path.exists() {
+        Ratings::load(ratings_path, RatingParams::default()).unwrap_or_else(|error| {
+            cli::exit_with_error(&format!("{}: {}", ratings_path.display(), error))
+        })
+    } else {
+        Ratings::new(RatingParams::default())
+    };
+    let entrants: Vec<Entrant> = args.get_positional()[1..]
+        .iter()
+        .map(|path| Entrant::from_loaded(&cli::load_bot(Path::new(path))))
+        .collect();
+    let hashes: Vec<u64> =
+        entrants.iter().map(|entrant| ratings::content_hash(&entrant.bot)).collect();
+    ratings.record_round_robin(&round_robin::play(&entrants, args.get_option_or("--threads", 1)),
+                               &hashes);
+    ratings.save(ratings_path).unwrap_or_else(|error| {
+        cli::exit_with_error(&format!("{}: {}", ratings_path.display(), error))
+    });
+    if args.has_flag("--json") {
+        let rated: Vec<String> = entrants.iter()
+            .zip(&hashes)
+            .map(|(entrant, &hash)| {
+                Object::new()
+                    .field("name", &entrant.name)
+                    .field("hash", &format!("{:016x}", hash))
+                    .field("rating", ratings.get(hash).unwrap())
+                    .build()
+            })
+            .collect();
+        println!("{}",
+                 Object::document("ratings")
+                     .raw_field("ratings", format!("[{}]", rated.join(",")))
+                     .build());
+        return EXIT_SUCCESS;
+    }
+    for (entrant, &hash) in entrants.iter().zip(&hashes) {
+        let rating = ratings.get(hash).unwrap();
+        println!("{} {:016x}: elo {:.0}, glicko {:.0} +/- {:.0} after {} games",
+                 entrant.name,
+                 hash,
+                 rating.elo,
+                 rating.rating,
+                 2.0 * rating.deviation,
+                 rating.games);
+    }
+    EXIT_SUCCESS
+}
+
+/// Manages a king of the hill that is kept in a directory.
+pub fn run_hill(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: hill new <directory> <size> \
+                            [--scoring points|markov|weighted] [--json]\n       \
+                            hill challenge <directory> <bot.bf>... [--json]\n       \
+                            hill show <directory> [--json]\n       \
+                            hill history <directory> [--json]",
+                           &["--json"],
+                           &["--scoring"]);
+    args.expect_positional(2, usize::MAX);
+    let positional = args.get_positional();
+    let dir = Path::new(&positional[1]);
+    let json = args.has_flag("--json");
+    let open = || Hill::open(dir).unwrap_or_else(|error| cli::exit_with_error(&error.to_string()));
+    match positional[0].as_str() {
+        "new" => {
+            args.expect_positional(3, 3);
+            let size = positional[2].parse().unwrap_or_else(|_| args.usage_error());
+            let scoring = match args.get_option::<String>("--scoring") {
+                Some(name) => Scoring::from_name(&name).unwrap_or_else(|| args.usage_error()),
+                None => Scoring::Points,
+            };
+            let hill = Hill::create(dir, size, scoring)
+                .unwrap_or_else(|error| cli::exit_with_error(&error.to_string()));
+            if json {
+                println!("{}",
+                         Object::document("hill")
+                             .field("size", &hill.get_size())
+                             .field("scoring", hill.get_scoring().name())
+                             .build());
+            }
+        }
+        "challenge" => {
+            let mut hill = open();
+            for path in &positional[2..] {
+                let loaded = cli::load_bot(Path::new(path));
+                let change = hill.challenge(&loaded.name(), &loaded.source_code)
+                    .unwrap_or_else(|error| cli::exit_with_error(&error.to_string()));
+                if json {
+                    println!("{}", Object::document("hill_change").field("change", change).build());
+                } else {
+                    println!("{}", change);
+                }
+            }
+        }
+        "show" => {
+            let leaderboard = open().leaderboard();
+            if json {
+                println!("{}",
+                         leaderboard.json_fields(Object::document("leaderboard")).build());
+            } else {
+                print!("{}", leaderboard);
+            }
+        }
+        "history" => {
+            let hill = open();
+            if json {
+                println!("{}",
+                         Object::document("hill_history")
+                             .field("changes", hill.get_history())
+                             .build());
+            } else {
+                for change in hill.get_history() {
+                    println!("{}", change);
+                }
+            }
+        }
+        _ => args.usage_error(),
+    }
+    EXIT_SUCCESS
+}
@@ -0,0 +1,101 @@
+//! Working on the source code of bots: checking it for mistakes, and formatting it.
+
+use std::fs;
+use std::path::Path;
+
+use cli::{self, Args, EXIT_CHECK_FAILED, EXIT_SUCCESS};
+use compiler::formatter;
+use compiler::linter::{self, Issue};
+use core::json::{Object, ToJson};
+
+/// Checks bots for mistakes. Exits with `EXIT_CHECK_FAILED` if any issue was found.
+pub fn run_lint(args: &[String]) -> i32 {
+    let args = Args::parse(args, "Usage: lint <bot.bf>... [--json]", &["--json"], &[]);
+    args.expect_positional(1, usize::MAX);
+    let files: Vec<(&String, Vec<Issue>)> = args.get_positional()
+        .iter()
+        .map(|path| (path, linter::lint_check(&read_source(Path::new(path)))))
+        .collect();
+    if args.has_flag("--json") {
+        let linted: Vec<LintedFile> =
+            files.iter().map(|(path, issues)| LintedFile { path, issues }).collect();
+        println!("{}", Object::document("lint").field("files", &linted).build());
+    } else {
+        for (path, issues) in &files {
+            for issue in issues {
+                println!("{}:{}:{}: {}: {}",
+                         path,
+                         issue.get_code_location().get_line(),
+                         issue.get_code_location().get_column(),
+                         issue.get_severity().name(),
+                         issue.get_description());
+            }
+        }
+    }
+    if files.iter().all(|(_, issues)| issues.is_empty()) {
+        EXIT_SUCCESS
+    } else {
+        EXIT_CHECK_FAILED
+    }
+}
+
+/// The issues found in a single file.
+struct LintedFile<'a> {
+    path: &'a str,
+    issues: &'a [Issue],
+}
+
+impl<'a> ToJson for LintedFile<'a> {
+    fn to_json(&self) -> String {
+        Object::new().field("file", self.path).field("issues", self.issues).build()
+    }
+}
+
+/// Formats the source code of a bot, and prints it. With `--check`, only exits with
+/// `EXIT_CHECK_FAILED` if the file isn't formatted yet; with `--write`, rewrites the file.
+pub fn run_fmt(args: &[String]) -> i32 {
+    let args = Args::parse(args,
+                           "Usage: fmt <bot.bf> [--minify] [--check] [--write] [--json]",
+                           &["--minify", "--check", "--write", "--json"],
+                           &[]);
+    args.expect_positional(1, 1);
+    let path = Path::new(&args.get_positional()[0]);
+    let source_code = read_source(path);
+    let formatted = if args.has_flag("--minify") {
+        formatter::minify(&source_code).map(|minified| minified + "\n")
+    } else {
+        formatter::prettify(&source_code)
+    };
+    let formatted = formatted
+        .unwrap_or_else(|error| cli::exit_with_error(&format!("{}: {}", path.display(), error)));
+    let changed = formatted != source_code;
+    if args.has_flag("--json") {
+        println!("{}",
+                 Object::document("format")
+                     .field("file", &path.display().to_string())
+                     .field("formatted", &formatted)
+                     .field("changed", &changed)
+                     .build());
+    } else if args.has_flag("--check") {
+        if changed {
+            println!("{} is not formatted", path.display());
+        }
+    } else if !args.has_flag("--write") {
+        print!("{}", formatted);
+    }
+    if args.has_flag("--write") && changed {
+        fs::write(path, &formatted).unwrap_or_else(|error| {
+            cli::exit_with_error(&format!("{}: {}", path.display(), error))
+        });
+    }
+    if args.has_flag("--check") && changed {
+        EXIT_CHECK_FAILED
+    } else {
+        EXIT_SUCCESS
+    }
+}
+
+fn read_source(path: &Path) -> String {
+    fs::read_to_string(path)
+        .unwrap_or_else(|error| cli::exit_with_error(&format!("{}: {}", path.display(), error)))
+}
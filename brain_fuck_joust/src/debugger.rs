@@ -0,0 +1,455 @@
+//! A debugger for a single round, controlled by commands that are typed in one line at a time.
+//! The round can be stepped through a given number of cycles at a time, or run until it hits a
+//! breakpoint. Breakpoints stop the round when a bot is about to execute the instruction at a
+//! location in its source code, when a cell of the tape comes to hold a value, or when a bot
+//! reaches a position on the tape. A breakpoint only fires when its condition starts to hold, so
+//! the round can be continued past a state that stays the same for a while. Every instruction is
+//! traced back to its line and column in the source code, so the debugger can show which code a
+//! bot is running.
+
+use std::fmt;
+
+use core::bf::{Bot, Instruction};
+use core::engine::{Arena, BotInPlay, History};
+use core::simul_round::{RoundParams, RoundResult};
+use viewer::Side;
+
+/// The number of steps that can be undone.
+const HISTORY_LENGTH: usize = 10_000;
+
+pub const HELP: &str = "\
+Commands:
+  step [N]                   Execute N cycles, 1 by default, or up to the next breakpoint.
+  back [N]                   Undo the last N cycles, 1 by default.
+  continue                   Run until a breakpoint is hit or the round is finished.
+  break a|b LINE:COLUMN      Stop before a bot executes the instruction at a location.
+  break cell INDEX VALUE     Stop when a cell of the tape comes to hold a value.
+  break pos a|b INDEX        Stop when a bot reaches a position on the tape.
+  delete N                   Delete a breakpoint.
+  breakpoints                List the breakpoints.
+  tape                       Show the tape.
+  bots                       Show the positions, next instructions and loop counters.
+  quit                       Stop debugging.
+  help                       Show this list.
+step, continue, break, delete, tape, quit and help can be shortened to their first letter.";
+
+/// A condition on which the round is stopped.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Breakpoint {
+    /// A bot is about to execute the instruction at the given index of its program, which lies
+    /// at the given line and column of its source code.
+    Location {
+        side: Side,
+        index: usize,
+        line: u32,
+        column: u32,
+    },
+    /// A cell of the tape holds the given value.
+    CellValue { cell: usize, value: i8 },
+    /// A bot is at the given position on the tape.
+    Position { side: Side, pos: i32 },
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Breakpoint::Location { side, line, column, .. } => {
+                write!(f, "{} at {}:{}", side.name(), line, column)
+            }
+            Breakpoint::CellValue { cell, value } => write!(f, "cell {} is {}", cell, value),
+            Breakpoint::Position { side, pos } => write!(f, "{} at position {}", side.name(), pos),
+        }
+    }
+}
+
+/// A round that is being debugged.
+pub struct Debugger<'a> {
+    history: History<'a>,
+    result: RoundResult,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl<'a> Debugger<'a> {
+    /// Locations in the source code can only be shown for bots that keep their source code.
+    pub fn new(bot_a: &'a Bot, bot_b: &'a Bot, round_params: &RoundParams) -> Debugger<'a> {
+        Debugger {
+            history: History::new(Arena::new(bot_a, bot_b, round_params), HISTORY_LENGTH),
+            result: RoundResult::round_ongoing(),
+            breakpoints: vec![],
+        }
+    }
+
+    /// Executes a command, and returns what it has to say.
+    pub fn execute(&mut self, line: &str) -> String {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let result = match words.split_first() {
+            None => Ok(String::new()),
+            Some((&command, args)) => {
+                match command {
+                    "step" | "s" => self.execute_step(args),
+                    "back" => self.execute_back(args),
+                    "continue" | "c" => Ok(self.run(None)),
+                    "break" | "b" => self.execute_break(args),
+                    "delete" | "d" => self.execute_delete(args),
+                    "breakpoints" => Ok(self.describe_breakpoints()),
+                    "tape" | "t" => Ok(self.describe_tape()),
+                    "bots" => Ok(self.describe_bots()),
+                    "help" | "h" => Ok(HELP.to_string()),
+                    _ => Err(format!("Unknown command '{}'. Type 'help' for a list.", command)),
+                }
+            }
+        };
+        result.unwrap_or_else(|error| error)
+    }
+
+    fn execute_step(&mut self, args: &[&str]) -> Result<String, String> {
+        let nr_steps = match args {
+            [] => 1,
+            [nr_steps] => nr_steps.parse().map_err(|_| "Usage: step [N]".to_string())?,
+            _ => return Err("Usage: step [N]".to_string()),
+        };
+        Ok(self.run(Some(nr_steps)))
+    }
+
+    fn execute_back(&mut self, args: &[&str]) -> Result<String, String> {
+        let nr_steps: usize = match args {
+            [] => 1,
+            [nr_steps] => nr_steps.parse().map_err(|_| "Usage: back [N]".to_string())?,
+            _ => return Err("Usage: back [N]".to_string()),
+        };
+        let mut description = String::new();
+        for nr_undone in 0..nr_steps {
+            if !self.history.step_back() {
+                description = format!("only {} steps could be undone\n", nr_undone);
+                break;
+            }
+            // Every state that was stepped from was one in which the round was still going on.
+            self.result = RoundResult::round_ongoing();
+        }
+        description.push_str(&self.describe_bots());
+        Ok(description)
+    }
+
+    fn execute_break(&mut self, args: &[&str]) -> Result<String, String> {
+        let breakpoint = match args {
+            ["cell", cell, value] => {
+                let cell = parse_number(cell)?;
+                if cell >= self.history.get_arena().get_tape().len() {
+                    return Err(format!("The tape has no cell {}.", cell));
+                }
+                Breakpoint::CellValue {
+                    cell,
+                    value: parse_number(value)?,
+                }
+            }
+            ["pos", side, pos] => {
+                Breakpoint::Position {
+                    side: parse_side(side)?,
+                    pos: parse_number(pos)?,
+                }
+            }
+            [side, location] => self.location_breakpoint(parse_side(side)?, location)?,
+            _ => {
+                return Err("Usage: break a|b LINE:COLUMN, break cell INDEX VALUE or \
+                            break pos a|b INDEX"
+                    .to_string())
+            }
+        };
+        self.breakpoints.push(breakpoint);
+        Ok(format!("breakpoint {}: {}", self.breakpoints.len(), self.breakpoints.last().unwrap()))
+    }
+
+    /// Finds the instruction at the given `LINE:COLUMN` of the source code of a bot. The brackets
+    /// of for loops are rejected, because a bot never rests on them between cycles, so a
+    /// breakpoint there would never be hit.
+    fn location_breakpoint(&self, side: Side, location: &str) -> Result<Breakpoint, String> {
+        let mut parts = location.splitn(2, ':');
+        let line: u32 = parse_number(parts.next().unwrap_or(""))?;
+        let column: u32 = parse_number(parts.next().unwrap_or(""))?;
+        let bot = self.bot(side);
+        let source_map = bot.get_source_map()
+            .ok_or_else(|| format!("The source code of {} is not known.", side.name()))?;
+        let index = source_map.find(line, column)
+            .ok_or_else(|| {
+                format!("There is no instruction at {}:{} of {}.", line, column, side.name())
+            })?;
+        match bot.get_program()[index] {
+            Instruction::StartFor { .. } |
+            Instruction::EndFor { .. } => {
+                Err(format!("{}:{} of {} is a bracket of a for loop, which never takes a cycle; \
+                             break at an instruction inside the loop instead.",
+                            line,
+                            column,
+                            side.name()))
+            }
+            _ => {
+                Ok(Breakpoint::Location {
+                    side,
+                    index,
+                    line,
+                    column,
+                })
+            }
+        }
+    }
+
+    fn execute_delete(&mut self, args: &[&str]) -> Result<String, String> {
+        match args {
+            [nr] => {
+                let nr: usize = parse_number(nr)?;
+                if nr == 0 || nr > self.breakpoints.len() {
+                    return Err(format!("There is no breakpoint {}.", nr));
+                }
+                let breakpoint = self.breakpoints.remove(nr - 1);
+                Ok(format!("deleted breakpoint {}: {}", nr, breakpoint))
+            }
+            _ => Err("Usage: delete N".to_string()),
+        }
+    }
+
+    /// Executes steps until a breakpoint is hit, the round is finished, or the given number of
+    /// steps has been executed. A breakpoint is hit when its condition holds after a step but did
+    /// not before it; otherwise a condition that holds for several steps in a row would stop every
+    /// one of them.
+    fn run(&mut self, max_steps: Option<u32>) -> String {
+        let mut nr_steps = 0;
+        while !self.result.round_is_finished() && max_steps.is_none_or(|max| nr_steps < max) {
+            let held: Vec<bool> =
+                self.breakpoints.iter().map(|breakpoint| self.is_hit(breakpoint)).collect();
+            self.result = self.history.step();
+            nr_steps += 1;
+            let hit = self.breakpoints
+                .iter()
+                .zip(held)
+                .position(|(breakpoint, held)| !held && self.is_hit(breakpoint));
+            if let Some(nr) = hit {
+                return format!("breakpoint {}: {}\n{}",
+                               nr + 1,
+                               self.breakpoints[nr],
+                               self.describe_bots());
+            }
+        }
+        self.describe_bots()
+    }
+
+    /// Returns whether the condition of the breakpoint holds in the current state.
+    fn is_hit(&self, breakpoint: &Breakpoint) -> bool {
+        match *breakpoint {
+            Breakpoint::Location { side, index, .. } => {
+                let bot_in_play = self.bot_in_play(side);
+                !bot_in_play.program_has_ended() && bot_in_play.get_code_pointer() == index
+            }
+            Breakpoint::CellValue { cell, value } => {
+                self.history.get_arena().get_tape()[cell] == value
+            }
+            Breakpoint::Position { side, pos } => self.bot_in_play(side).get_signed_pos() == pos,
+        }
+    }
+
+    fn describe_breakpoints(&self) -> String {
+        if self.breakpoints.is_empty() {
+            return "no breakpoints".to_string();
+        }
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .map(|(nr, breakpoint)| format!("{}: {}", nr + 1, breakpoint))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn describe_tape(&self) -> String {
+        let tape = self.history.get_arena().get_tape();
+        let indices: String = (0..tape.len()).map(|index| format!("{:>5}", index)).collect();
+        let values: String = tape.iter().map(|value| format!("{:>5}", value)).collect();
+        format!("{}\n{}", indices, values)
+    }
+
+    /// Describes the step number, the outcome of the round if it is finished, and the state of
+    /// both bots.
+    fn describe_bots(&self) -> String {
+        let mut description = format!("step {}", self.history.get_arena().get_step_nr());
+        match self.result.winner_name() {
+            None => {}
+            Some("draw") => description.push_str(", draw"),
+            Some(winner) => {
+                let name = self.bot_name(Side::from_name(winner).unwrap());
+                description.push_str(&format!(", {} wins", name));
+            }
+        }
+        for &side in &[Side::A, Side::B] {
+            description.push('\n');
+            description.push_str(&self.describe_bot(side));
+        }
+        description
+    }
+
+    fn describe_bot(&self, side: Side) -> String {
+        let bot_in_play = self.bot_in_play(side);
+        let mut description = format!("{} {}: position {}",
+                                      side.name(),
+                                      self.bot_name(side),
+                                      bot_in_play.get_signed_pos());
+        if !bot_in_play.get_remaining_iterations().is_empty() {
+            description.push_str(&format!(", remaining iterations {:?}",
+                                          bot_in_play.get_remaining_iterations()));
+        }
+        if bot_in_play.program_has_ended() {
+            description.push_str(", program ended");
+            return description;
+        }
+        let bot = self.bot(side);
+        let span = bot.get_source_map().and_then(|source_map| {
+            source_map.get(bot_in_play.get_code_pointer())
+        });
+        match (bot.get_source_code(), span) {
+            (Some(source_code), Some(span)) => {
+                let source_line = source_code.lines().nth(span.line as usize - 1);
+                description.push_str(&format!(", next instruction at {}:{}\n    {}\n    {}^",
+                                              span.line,
+                                              span.column,
+                                              source_line.unwrap_or(""),
+                                              " ".repeat(span.column as usize - 1)));
+            }
+            _ => {
+                description.push_str(&format!(", next instruction {}",
+                                              bot_in_play.get_code_pointer()))
+            }
+        }
+        description
+    }
+
+    fn bot(&self, side: Side) -> &'a Bot {
+        self.bot_in_play(side).get_bot()
+    }
+
+    fn bot_name(&self, side: Side) -> &'a str {
+        self.bot(side).get_name().unwrap_or_else(|| side.name())
+    }
+
+    fn bot_in_play(&self, side: Side) -> &BotInPlay<'a> {
+        match side {
+            Side::A => self.history.get_arena().get_start_bot(),
+            Side::B => self.history.get_arena().get_end_bot(),
+        }
+    }
+}
+
+fn parse_side(name: &str) -> Result<Side, String> {
+    Side::from_name(name).ok_or_else(|| format!("'{}' is not a bot; use a or b.", name))
+}
+
+fn parse_number<T: ::std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse().map_err(|_| format!("'{}' is not a valid number.", text))
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use compiler::parser;
+
+    fn make_bot(name: &str, source_code: &str) -> Bot {
+        parser::try_parse_source(source_code).unwrap().named(name)
+    }
+
+    fn make_round_params() -> RoundParams {
+        RoundParams {
+            tape_length: 10,
+            invert_polarity: false,
+            max_steps: 1000,
+        }
+    }
+
+    #[test]
+    fn execute_locationBreakpoint_stopsBeforeInstruction() {
+        let bot_a = make_bot("attacker", "Move to the flag\n(>)*9\nAttack it\n(-)*128\n");
+        let bot_b = make_bot("idle", ".");
+        let mut debugger = Debugger::new(&bot_a, &bot_b, &make_round_params());
+        assert_eq!(debugger.execute("break a 4:2"), "breakpoint 1: a at 4:2");
+        let output = debugger.execute("continue");
+        assert!(output.starts_with("breakpoint 1: a at 4:2\nstep 9\n"));
+        assert!(output.contains("a attacker: position 9, remaining iterations [127], next \
+                                 instruction at 4:2\n    (-)*128\n     ^"));
+    }
+
+    #[test]
+    fn execute_locationBreakpointOnForLoopBracket_isRejected() {
+        let bot_a = make_bot("attacker", "(>)*9(-)*128");
+        let bot_b = make_bot("idle", ".");
+        let mut debugger = Debugger::new(&bot_a, &bot_b, &make_round_params());
+        for location in &["1:1", "1:3", "1:6"] {
+            assert_eq!(debugger.execute(&format!("break a {}", location)),
+                       format!("{} of a is a bracket of a for loop, which never takes a cycle; \
+                                break at an instruction inside the loop instead.",
+                               location));
+        }
+        assert_eq!(debugger.execute("break a 1:7"), "breakpoint 1: a at 1:7");
+    }
+
+    #[test]
+    fn execute_cellAndPositionBreakpoints_stopWhenStateMatches() {
+        let bot_a = make_bot("attacker", ">>>>>>>>>(-)*128");
+        let bot_b = make_bot("idle", ".");
+        let mut debugger = Debugger::new(&bot_a, &bot_b, &make_round_params());
+        debugger.execute("break pos a 4");
+        debugger.execute("b cell 9 100");
+        assert!(debugger.execute("c").starts_with("breakpoint 1: a at position 4\nstep 4\n"));
+        assert_eq!(debugger.execute("delete 1"), "deleted breakpoint 1: a at position 4");
+        assert!(debugger.execute("c").starts_with("breakpoint 1: cell 9 is 100\nstep 37\n"));
+        assert!(debugger.execute("c").starts_with("step 138, attacker wins"));
+    }
+
+    #[test]
+    fn execute_continue_runsPastBreakpointThatStillHolds() {
+        let bot_a = make_bot("attacker", ">>>>(-)*50>>>>>(-)*128");
+        let bot_b = make_bot("idle", ".");
+        let mut debugger = Debugger::new(&bot_a, &bot_b, &make_round_params());
+        debugger.execute("break pos a 4");
+        assert!(debugger.execute("c").starts_with("breakpoint 1: a at position 4\nstep 4\n"));
+        assert!(debugger.execute("c").starts_with("step 188, attacker wins"));
+    }
+
+    #[test]
+    fn execute_continueAfterLeavingBreakpoint_stopsAgainOnReturn() {
+        let bot_a = make_bot("dancer", ">>><>");
+        let bot_b = make_bot("idle", ".");
+        let mut debugger = Debugger::new(&bot_a, &bot_b, &make_round_params());
+        debugger.execute("break pos a 3");
+        assert!(debugger.execute("c").starts_with("breakpoint 1: a at position 3\nstep 3\n"));
+        assert!(debugger.execute("c").starts_with("breakpoint 1: a at position 3\nstep 5\n"));
+    }
+
+    #[test]
+    fn execute_step_executesGivenNumberOfCycles() {
+        let bot_a = make_bot("a", ">>>");
+        let bot_b = make_bot("b", ".");
+        let mut debugger = Debugger::new(&bot_a, &bot_b, &make_round_params());
+        assert!(debugger.execute("step 2").starts_with("step 2\na a: position 2, next"));
+        assert!(debugger.execute("tape").ends_with(" -128    0    0    0    0    0    0    0    0 \
+                                                    -128"));
+    }
+
+    #[test]
+    fn execute_back_undoesSteps() {
+        let bot_a = make_bot("suicide", "<");
+        let bot_b = make_bot("idle", ".");
+        let mut debugger = Debugger::new(&bot_a, &bot_b, &make_round_params());
+        assert!(debugger.execute("step").starts_with("step 1, idle wins"));
+        assert!(debugger.execute("back 2")
+            .starts_with("only 1 steps could be undone\nstep 0\na suicide: position 0, next"));
+        assert!(debugger.execute("step").starts_with("step 1, idle wins"));
+    }
+
+    #[test]
+    fn execute_invalidCommands_explainWhatIsWrong() {
+        let bot = make_bot("a", ">");
+        let mut debugger = Debugger::new(&bot, &bot, &make_round_params());
+        assert_eq!(debugger.execute("break a 2:1"), "There is no instruction at 2:1 of a.");
+        assert_eq!(debugger.execute("break cell 10 0"), "The tape has no cell 10.");
+        assert_eq!(debugger.execute("break c 1:1"), "'c' is not a bot; use a or b.");
+        assert_eq!(debugger.execute("delete 1"), "There is no breakpoint 1.");
+        assert!(debugger.execute("jump").starts_with("Unknown command 'jump'."));
+        assert!(debugger.breakpoints.is_empty());
+    }
+}
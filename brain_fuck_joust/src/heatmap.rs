@@ -0,0 +1,191 @@
+//! Renders the counts of a profile onto the source code of a bot, as an annotated listing or as
+//! an HTML heatmap. The brackets of for loops never take a cycle, so they are left out of the
+//! counts; every other instruction that was never executed is dead code against the opponents
+//! that were profiled.
+
+use core::bf::{Bot, Instruction, SourceSpan};
+use core::simul_game::game_profile::GameProfile;
+
+/// Describes the instructions of a line of source code that are counted, if any.
+struct LineCounts {
+    /// The counted instructions on the line, with their counts.
+    instructions: Vec<(SourceSpan, u64)>,
+}
+
+impl LineCounts {
+    fn total(&self) -> u64 {
+        self.instructions.iter().map(|&(_, count)| count).sum()
+    }
+
+    fn is_dead(&self) -> bool {
+        self.instructions.iter().all(|&(_, count)| count == 0)
+    }
+}
+
+/// Returns true for the instructions whose executions are counted.
+fn is_counted(instruction: &Instruction) -> bool {
+    !matches!(*instruction, Instruction::StartFor { .. } | Instruction::EndFor { .. })
+}
+
+/// Groups the counted instructions of a bot by the line of source code they are on. The bot
+/// must keep its source code.
+fn count_lines<'a>(bot: &'a Bot, counts: &[u64]) -> Vec<(&'a str, LineCounts)> {
+    let source_code = bot.get_source_code().expect("The bot must keep its source code.");
+    let source_map = bot.get_source_map().expect("The bot must keep its source code.");
+    let mut lines: Vec<(&'a str, LineCounts)> = source_code.lines()
+        .map(|line| (line, LineCounts { instructions: vec![] }))
+        .collect();
+    for ((instruction, span), &count) in
+        bot.get_program().iter().zip(source_map.get_spans()).zip(counts) {
+        if is_counted(instruction) {
+            lines[span.line as usize - 1].1.instructions.push((*span, count));
+        }
+    }
+    lines
+}
+
+/// Writes the source code of a bot with the number of executed instructions on every line, and
+/// its share of all executed instructions. Lines with code that never ran are marked as dead,
+/// and if only part of a line never ran, that part is underlined.
+pub fn annotate(bot: &Bot, counts: &[u64]) -> String {
+    let lines = count_lines(bot, counts);
+    let total = counts.iter().sum::<u64>().max(1);
+    let mut listing = format!("{:>12} {:>6}  source\n", "executed", "share");
+    for (line, line_counts) in &lines {
+        if line_counts.instructions.is_empty() {
+            listing.push_str(&format!("{:>12} {:>6}  {}\n", "", "", line));
+            continue;
+        }
+        let line_total = line_counts.total();
+        listing.push_str(&format!("{:>12} {:>5.1}%  {}{}\n",
+                                  line_total,
+                                  100.0 * line_total as f64 / total as f64,
+                                  line,
+                                  if line_counts.is_dead() { "    <- dead" } else { "" }));
+        if !line_counts.is_dead() &&
+           line_counts.instructions.iter().any(|&(_, count)| count == 0) {
+            let mut marks = vec![' '; line.chars().count()];
+            for &(span, count) in &line_counts.instructions {
+                if count == 0 {
+                    marks[span.column as usize - 1] = '~';
+                }
+            }
+            let marks: String = marks.into_iter().collect();
+            listing.push_str(&format!("{:>21}{}\n", "", marks.trim_end()));
+        }
+    }
+    listing
+}
+
+/// Writes a standalone HTML page with the source code of a bot, on which every instruction is
+/// coloured after how often it was executed. Hovering over an instruction shows its count.
+pub fn to_html(bot: &Bot, counts: &[u64], title: &str) -> String {
+    let source_code = bot.get_source_code().expect("The bot must keep its source code.");
+    let source_map = bot.get_source_map().expect("The bot must keep its source code.");
+    // Counts are shown on a logarithmic scale, as loops easily run thousands of times more often
+    // than the code around them. Only counts above zero are coloured, so the maximum is never
+    // zero when it is divided by.
+    let max = counts.iter().cloned().max().unwrap_or(0);
+    let heat = |count: u64| (count as f64 + 1.0).ln() / (max as f64 + 1.0).ln();
+    let mut body = String::new();
+    let mut position = 0;
+    for ((instruction, span), &count) in
+        bot.get_program().iter().zip(source_map.get_spans()).zip(counts) {
+        body.push_str(&escape_html(&source_code[position..span.start]));
+        let text = escape_html(&source_code[span.start..span.end]);
+        if !is_counted(instruction) {
+            body.push_str(&text);
+        } else if count == 0 {
+            body.push_str(&format!("<span class=\"dead\" title=\"never executed\">{}</span>",
+                                   text));
+        } else {
+            body.push_str(&format!("<span style=\"background: rgba(255, 64, 0, {:.2})\" \
+                                    title=\"{} times\">{}</span>",
+                                   heat(count),
+                                   count,
+                                   text));
+        }
+        position = span.end;
+    }
+    body.push_str(&escape_html(&source_code[position..]));
+    format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>\n.dead {{ color: #999; text-decoration: line-through; }}\n</style>\n\
+             </head>\n<body>\n<h1>{title}</h1>\n<pre>{body}</pre>\n</body>\n</html>\n",
+            title = escape_html(title),
+            body = body)
+}
+
+/// Writes the number of instructions the bot executed on every tape length, for both
+/// polarities.
+pub fn round_kind_table(game_profile: &GameProfile) -> String {
+    let mut table = format!("{:>4} {:>12} {:>12}\n", "tape", "normal", "reversed");
+    let kinds = game_profile.executed_per_round_kind();
+    for (index, &(tape_length, invert_polarity, executed)) in kinds.iter().enumerate() {
+        if invert_polarity && index > 0 && kinds[index - 1].0 == tape_length {
+            continue;
+        }
+        let reversed = kinds.get(index + 1)
+            .filter(|kind| kind.0 == tape_length && kind.1)
+            .map(|kind| kind.2);
+        let (normal, reversed) = if invert_polarity {
+            (None, Some(executed))
+        } else {
+            (Some(executed), reversed)
+        };
+        let cell = |executed: Option<u64>| executed.map_or("-".to_string(), |n| n.to_string());
+        table.push_str(&format!("{:>4} {:>12} {:>12}\n",
+                                tape_length,
+                                cell(normal),
+                                cell(reversed)));
+    }
+    table
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use compiler::parser;
+    use core::simul_game::AllRounds;
+
+    fn make_profile(bot: &Bot) -> GameProfile {
+        let opponent = parser::try_parse_source(".").unwrap();
+        let mut game_profile = GameProfile::new(bot.get_program().len());
+        game_profile.add_game(bot, &opponent, AllRounds::new());
+        game_profile
+    }
+
+    #[test]
+    fn annotate_marksDeadLinesAndInstructions() {
+        let bot = parser::try_parse_source("Step forward\n>[-]\n(+)*2\n").unwrap();
+        let counts = make_profile(&bot).total(|_| true);
+        let listing = annotate(&bot, &counts);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines[1].trim(), "Step forward");
+        assert_eq!(lines[2], "          84  50.0%  >[-]");
+        assert_eq!(lines[3], "                       ~~");
+        assert_eq!(lines[4], "          84  50.0%  (+)*2");
+    }
+
+    #[test]
+    fn toHtml_escapesSourceAndColoursCounts() {
+        let bot = parser::try_parse_source("<b>\n[+]").unwrap();
+        let html = to_html(&bot, &[1, 1, 0, 0], "<test>");
+        assert!(html.contains("<title>&lt;test&gt;</title>"));
+        assert!(html.contains("<span style=\"background: rgba(255, 64, 0, 1.00)\" \
+                               title=\"1 times\">&lt;</span>b"));
+        assert!(html.contains("<span class=\"dead\" title=\"never executed\">+</span>"));
+    }
+
+    #[test]
+    fn roundKindTable_hasARowPerTapeLength() {
+        let bot = parser::try_parse_source(">").unwrap();
+        let table = round_kind_table(&make_profile(&bot));
+        assert_eq!(table.lines().count(), 22);
+        assert_eq!(table.lines().nth(1), Some("  10            1            1"));
+    }
+}
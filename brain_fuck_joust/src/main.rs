@@ -1,23 +1,97 @@
-#![allow(dead_code, unused_variables)]
-
 extern crate bf_bot_core as core;
 extern crate bf_bot_compiler as compiler;
+extern crate bf_bot_evolve as evolve;
+extern crate bf_bot_tournament as tournament;
 
-use compiler::parser;
-use core::bf::Bot;
-use core::simul_game;
+mod cli;
+mod commands;
+mod debugger;
+mod heatmap;
+mod terminal;
+mod viewer;
 
-fn main() {
-    print!("Bot1 input string: ");
-    let bot1: Bot = make_test_bot(">>> >>> >>> [-]".to_string());
-    print!("Bot2 input string: ");
-    let bot2: Bot = make_test_bot("[-]".to_string());
+use std::env;
+use std::process;
 
-    let result = simul_game::run_complete(&bot1, &bot2);
-    println!("{:?}", result);
-}
+use cli::{EXIT_SUCCESS, EXIT_USAGE};
+use commands::{evolve as evolve_commands, game, ranking, source};
+
+const USAGE: &str = "\
+Usage: brain_fuck_joust <command> [arguments]
 
-fn make_test_bot(code: String) -> Bot {
-    println!("{}", code);
-    Bot::new(parser::parse_bot(code))
+Commands:
+  match <a.bf> <b.bf>                Play a complete game between two bots.
+  trace <a.bf> <b.bf> --tape N [--reversed] [--max-steps N]
+                                     Show every step of a single round.
+  diagram <a.bf> <b.bf> --tape N [--reversed] [--max-steps N] [--steps N] [--output file]
+                                     Draw a single round as an SVG space-time diagram.
+  view <a.bf> <b.bf> --tape N [--reversed] [--max-steps N]
+                                     Watch a single round in the terminal.
+  debug <a.bf> <b.bf> --tape N [--reversed] [--max-steps N]
+                                     Step through a single round, with breakpoints.
+  lint <bot.bf>...                   Check bots for mistakes.
+  fmt <bot.bf> [--minify] [--check] [--write]
+                                     Format the source code of a bot.
+  tournament <directory> [--threads N] [--scoring points|markov|weighted | --swiss N]
+                                     Rank all bots in a directory.
+  gauntlet <file> <bot.bf>... [--threads N]
+                                     Score bots against a gauntlet.
+  profile <bot.bf> [<opponent.bf>...] [--gauntlet file] [--tape N] [--normal|--reversed]
+          [--html file] [--threads N]
+                                     Count how often every instruction of a bot runs.
+  diff <old.bf> <new.bf> <gauntlet file> [--threads N]
+                                     Show what changed between two versions of a bot.
+  rate <ratings file> <bot.bf> <bot.bf>... [--threads N]
+                                     Update the ratings of bots.
+  hill new|challenge|show|history <directory> ...
+                                     Manage a king of the hill.
+  evolve <config>                    Run an evolution experiment.
+  refine <bot.bf> <opponent.bf>... [--iterations N] [--temperature T] [--seed S]
+                                     Polish a bot with a local search.
+
+All commands but diagram, view and debug print JSON instead of text with --json. The schema is
+described in the documentation of bf_bot_core::json.
+
+Exit codes:
+  0  success
+  1  a check failed: lint found issues, fmt --check found an unformatted file, or diff found a
+     round that the new version does worse in
+  2  invalid command line
+  3  a file can't be read or written, or doesn't hold what it should";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match args.first() {
+        Some(command) => command.as_str(),
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    let run: fn(&[String]) -> i32 = match command {
+        "match" => game::run_match,
+        "trace" => game::run_trace,
+        "diagram" => game::run_diagram,
+        "view" => game::run_view,
+        "debug" => game::run_debug,
+        "lint" => source::run_lint,
+        "fmt" => source::run_fmt,
+        "tournament" => ranking::run_tournament,
+        "gauntlet" => ranking::run_gauntlet,
+        "profile" => ranking::run_profile,
+        "diff" => ranking::run_diff,
+        "rate" => ranking::run_rating,
+        "hill" => ranking::run_hill,
+        "evolve" => evolve_commands::run_evolve,
+        "refine" => evolve_commands::run_refine,
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            process::exit(EXIT_SUCCESS);
+        }
+        _ => {
+            eprintln!("Unknown command '{}'.\n\n{}", command, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    process::exit(run(&args[1..]));
 }
@@ -0,0 +1,59 @@
+//! Reading single key presses from the terminal, for the interactive commands.
+//! The terminal is switched out of line mode with `stty`, so no dependencies are needed; this
+//! only works on Unix-like systems, and only when standard input is a terminal.
+
+use std::io::{self, Read};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Clears the screen and moves the cursor to the top left corner.
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// How long to wait between polls of standard input.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Keeps the terminal in raw mode: key presses are read right away, without echoing them. The
+/// previous settings are restored when this is dropped.
+pub struct RawMode {
+    saved_settings: String,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<RawMode> {
+        let saved_settings = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "min", "0", "time", "0"])?;
+        Ok(RawMode { saved_settings: saved_settings.trim().to_string() })
+    }
+
+    /// Waits for a key press, for at most the given time if there is one. Returns None if no
+    /// key was pressed in time.
+    pub fn read_key(&self, timeout: Option<Duration>) -> io::Result<Option<u8>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut key = [0u8];
+        loop {
+            if io::stdin().read(&mut key)? == 1 {
+                return Ok(Some(key[0]));
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(None);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved_settings]);
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).output()?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(io::Error::other("standard input is not a terminal"))
+    }
+}
@@ -0,0 +1,336 @@
+//! Replays a single round in the terminal, with ANSI colours.
+//! The viewer drives an `Arena` itself, so the round is played while it is being watched. Every
+//! frame shows the tape, the heads of both bots, the state of their flags and the instruction
+//! each bot executes next. Cells are coloured after the bot that changed them last, which makes
+//! decoys, clears and attacks on the flags easy to tell apart.
+
+use core::bf::{Bot, Instruction, SourceSpan};
+use core::engine::{Arena, BotInPlay};
+use core::simul_round::{RoundParams, RoundResult};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const INVERSE: &str = "\x1b[7m";
+const COLOUR_A: &str = "\x1b[34m";
+const COLOUR_B: &str = "\x1b[31m";
+const WARNING: &str = "\x1b[1;33m";
+
+/// The number of instructions that are shown before the next instruction of a bot. Programs are
+/// cut off at `LISTING_LENGTH` instructions in total.
+const LISTING_CONTEXT: usize = 20;
+const LISTING_LENGTH: usize = 60;
+
+/// One of the two bots of a round: a for the bot that starts at the start of the tape, b for the
+/// other one.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Side {
+    A,
+    B,
+}
+
+impl Side {
+    pub fn from_name(name: &str) -> Option<Side> {
+        match name {
+            "a" => Some(Side::A),
+            "b" => Some(Side::B),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Side::A => "a",
+            Side::B => "b",
+        }
+    }
+}
+
+/// A round that is being watched.
+pub struct Viewer<'a> {
+    names: (String, String),
+    round_params: RoundParams,
+    arena: Arena<'a>,
+    result: RoundResult,
+    /// The bot that changed each cell last, if any.
+    last_writers: Vec<Option<Side>>,
+    /// Whether the bots interacted during the last step.
+    interacted: bool,
+}
+
+impl<'a> Viewer<'a> {
+    pub fn new(bot_a: &'a Bot,
+               name_a: &str,
+               bot_b: &'a Bot,
+               name_b: &str,
+               round_params: &RoundParams)
+               -> Viewer<'a> {
+        Viewer {
+            names: (name_a.to_string(), name_b.to_string()),
+            round_params: round_params.clone(),
+            arena: Arena::new(bot_a, bot_b, round_params),
+            result: RoundResult::round_ongoing(),
+            last_writers: vec![None; round_params.tape_length as usize],
+            interacted: false,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.result.round_is_finished()
+    }
+
+    /// Executes a single step, unless the round is already finished. Returns true if the bots
+    /// interacted during the step: if a flag changed, if a bot changed a cell that the other bot
+    /// changed last, or if the heads of the bots met, parted or passed each other.
+    pub fn step(&mut self) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+        let flags = self.flags();
+        let heads = self.heads();
+        let step_nr = self.arena.get_step_nr();
+        self.result = self.arena.step();
+        self.interacted = false;
+        if self.arena.get_step_nr() == step_nr {
+            return false;
+        }
+        let &(mutation_a, mutation_b) = self.arena.get_last_mutations();
+        for &(mutation, side, other) in &[(mutation_a, Side::A, Side::B),
+                                          (mutation_b, Side::B, Side::A)] {
+            if let Some(mutation) = mutation {
+                let writer = &mut self.last_writers[mutation.get_index()];
+                self.interacted |= *writer == Some(other);
+                *writer = Some(side);
+            }
+        }
+        let new_heads = self.heads();
+        self.interacted |= self.flags() != flags ||
+                           heads.0.cmp(&heads.1) != new_heads.0.cmp(&new_heads.1);
+        self.interacted
+    }
+
+    /// Executes steps until the bots interact or the round is finished. Returns the number of
+    /// steps that were executed.
+    pub fn skip_to_interaction(&mut self) -> u32 {
+        let step_nr = self.arena.get_step_nr();
+        while !self.is_finished() && !self.step() {}
+        self.arena.get_step_nr() - step_nr
+    }
+
+    fn flags(&self) -> (i8, i8) {
+        let tape = self.arena.get_tape();
+        (tape[0], tape[tape.len() - 1])
+    }
+
+    fn heads(&self) -> (i32, i32) {
+        (self.arena.get_start_bot().get_signed_pos(), self.arena.get_end_bot().get_signed_pos())
+    }
+
+    /// Draws the current state of the round, without clearing the screen first.
+    pub fn render(&self) -> String {
+        let mut frame = String::new();
+        frame.push_str(&format!("{}{}{}{} vs {}{}{}    tape {}, {} polarity\n",
+                                BOLD,
+                                COLOUR_A,
+                                self.names.0,
+                                RESET,
+                                COLOUR_B,
+                                self.names.1,
+                                RESET,
+                                self.round_params.tape_length,
+                                self.round_params.polarity_name()));
+        frame.push_str(&format!("step {} of at most {}    {}{}\n\n",
+                                self.arena.get_step_nr(),
+                                self.round_params.max_steps,
+                                self.status(),
+                                if self.interacted { "    interaction" } else { "" }));
+        frame.push_str(&self.render_tape());
+        frame.push_str(&self.render_heads());
+        let (flag_a, flag_b) = self.flags();
+        frame.push_str(&format!("\nflags: {} {}\n\n",
+                                render_flag(COLOUR_A, flag_a),
+                                render_flag(COLOUR_B, flag_b)));
+        frame.push_str(&render_listing(COLOUR_A, "a", self.arena.get_start_bot()));
+        frame.push_str(&render_listing(COLOUR_B, "b", self.arena.get_end_bot()));
+        frame
+    }
+
+    fn status(&self) -> String {
+        match self.result.winner_name() {
+            None => "playing".to_string(),
+            Some("a") => format!("{} wins", self.names.0),
+            Some("b") => format!("{} wins", self.names.1),
+            Some(_) => "draw".to_string(),
+        }
+    }
+
+    fn render_tape(&self) -> String {
+        let tape = self.arena.get_tape();
+        let mut line = String::new();
+        for (index, (&value, writer)) in tape.iter().zip(&self.last_writers).enumerate() {
+            let style = match *writer {
+                _ if index == 0 || index == tape.len() - 1 => BOLD,
+                Some(Side::A) => COLOUR_A,
+                Some(Side::B) => COLOUR_B,
+                None if value == 0 => DIM,
+                None => "",
+            };
+            line.push_str(&format!("{}{:>5}{}", style, value, RESET));
+        }
+        line.push('\n');
+        line
+    }
+
+    fn render_heads(&self) -> String {
+        let (head_a, head_b) = self.heads();
+        (0..self.last_writers.len() as i32)
+            .map(|index| match (index == head_a, index == head_b) {
+                (true, true) => format!("   {}a{}b{}", COLOUR_A, COLOUR_B, RESET),
+                (true, false) => format!("    {}a{}", COLOUR_A, RESET),
+                (false, true) => format!("    {}b{}", COLOUR_B, RESET),
+                (false, false) => "     ".to_string(),
+            })
+            .collect()
+    }
+}
+
+/// Draws the value of a flag, and warns when it is zero: a bot loses as soon as its flag has
+/// been zero for two steps in a row.
+fn render_flag(colour: &str, value: i8) -> String {
+    if value == 0 {
+        format!("{}{:>4}{} {}zeroed{}", colour, value, RESET, WARNING, RESET)
+    } else {
+        format!("{}{:>4}{}", colour, value, RESET)
+    }
+}
+
+/// Draws the instruction a bot executes next, highlighted: within its line of source code if the
+/// bot keeps its source code, or else within its program.
+fn render_listing(colour: &str, label: &str, bot_in_play: &BotInPlay) -> String {
+    let bot = bot_in_play.get_bot();
+    let program = bot.get_program();
+    let next = if bot_in_play.program_has_ended() {
+        None
+    } else {
+        Some(bot_in_play.get_code_pointer())
+    };
+    let span = next.and_then(|next| bot.get_source_map().and_then(|map| map.get(next)));
+    if let (Some(source_code), Some(span)) = (bot.get_source_code(), span) {
+        return render_source_line(colour, label, source_code, span);
+    }
+    let start = next.map_or(0, |next| next.saturating_sub(LISTING_CONTEXT));
+    let end = program.len().min(start + LISTING_LENGTH);
+    let mut line = format!("{}{}{}: ", colour, label, RESET);
+    if start > 0 {
+        line.push_str("...");
+    }
+    for (index, instruction) in program.iter().enumerate().take(end).skip(start) {
+        if Some(index) == next {
+            line.push_str(&format!("{}{}{}", INVERSE, instruction_text(instruction), RESET));
+        } else {
+            line.push_str(&instruction_text(instruction));
+        }
+    }
+    if end < program.len() {
+        line.push_str("...");
+    }
+    if next.is_none() {
+        line.push_str(&format!(" {}(ended){}", DIM, RESET));
+    }
+    line.push('\n');
+    line
+}
+
+/// Draws the line of source code that holds the next instruction of a bot, which is highlighted.
+fn render_source_line(colour: &str, label: &str, source_code: &str, span: &SourceSpan) -> String {
+    let line_start = source_code[..span.start].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = source_code[span.end..].find('\n').map_or(source_code.len(), |index| {
+        span.end + index
+    });
+    format!("{}{}{}: {:>3}:{:<3} {}{}{}{}{}\n",
+            colour,
+            label,
+            RESET,
+            span.line,
+            span.column,
+            &source_code[line_start..span.start],
+            INVERSE,
+            &source_code[span.start..span.end],
+            RESET,
+            &source_code[span.end..line_end])
+}
+
+/// Writes an instruction the way it is written in BrainFuck.
+fn instruction_text(instruction: &Instruction) -> String {
+    match *instruction {
+        Instruction::MoveBack => "<".to_string(),
+        Instruction::MoveForward => ">".to_string(),
+        Instruction::Increment => "+".to_string(),
+        Instruction::Decrement => "-".to_string(),
+        Instruction::StartWhileNotZero { .. } => "[".to_string(),
+        Instruction::EndWhileNotZero { .. } => "]".to_string(),
+        Instruction::SkipExecution => ".".to_string(),
+        Instruction::StartFor { .. } => "(".to_string(),
+        Instruction::EndFor { nr_iterations, .. } => format!(")*{}", nr_iterations),
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use compiler::parser;
+
+    fn make_bot(source_code: &str) -> Bot {
+        Bot::new(parser::parse_bot(source_code.to_string()))
+    }
+
+    fn make_round_params() -> RoundParams {
+        RoundParams {
+            tape_length: 10,
+            invert_polarity: false,
+            max_steps: 1000,
+        }
+    }
+
+    #[test]
+    fn skipToInteraction_stopsWhenAFlagIsAttacked() {
+        let bot_a = make_bot(">>>>>>>>>-");
+        let bot_b = make_bot(".");
+        let mut viewer = Viewer::new(&bot_a, "attacker", &bot_b, "idle", &make_round_params());
+        // The heads meet after nine steps, and the flag is hit in the step after that.
+        assert_eq!(viewer.skip_to_interaction(), 9);
+        assert_eq!(viewer.skip_to_interaction(), 1);
+        assert_eq!(viewer.flags(), (-128, 127));
+        assert!(!viewer.is_finished());
+    }
+
+    #[test]
+    fn step_afterRoundIsFinished_doesNothing() {
+        let bot_a = make_bot("<");
+        let bot_b = make_bot(".");
+        let mut viewer = Viewer::new(&bot_a, "suicide", &bot_b, "idle", &make_round_params());
+        viewer.step();
+        assert_eq!(viewer.result, RoundResult::end_bot_wins());
+        assert!(!viewer.step());
+        assert_eq!(viewer.arena.get_step_nr(), 1);
+        assert!(viewer.render().contains("idle wins"));
+    }
+
+    #[test]
+    fn render_highlightsNextInstructions() {
+        let bot = make_bot("(+)*3>");
+        let viewer = Viewer::new(&bot, "a", &bot, "b", &make_round_params());
+        let frame = viewer.render();
+        assert!(frame.contains(&format!("({}+{})*3>", INVERSE, RESET)));
+    }
+
+    #[test]
+    fn render_botWithSource_highlightsSourceLine() {
+        let bot_a = parser::try_parse_source("Set up a decoy\n>(+)*10>\n").unwrap();
+        let bot_b = make_bot(".");
+        let mut viewer = Viewer::new(&bot_a, "a", &bot_b, "b", &make_round_params());
+        viewer.step();
+        assert!(viewer.render().contains(&format!(":   2:3   >({}+{})*10>\n", INVERSE, RESET)));
+    }
+}